@@ -1,6 +1,6 @@
 use {
   dotenv::dotenv,
-  readwise::client::Client,
+  readwise::{client::Client, model::HighlightUpdate},
   std::{collections::HashMap, env},
 };
 
@@ -28,9 +28,15 @@ fn main() {
   }
 
   // Update a highlight by ID
-  let mut fields = HashMap::new();
-  fields.insert("text", "hello, world!");
-  client.update_highlight(138105649, fields).unwrap();
+  client
+    .update_highlight(
+      138105649,
+      HighlightUpdate {
+        text: Some("hello, world!".into()),
+        ..Default::default()
+      },
+    )
+    .unwrap();
 
   // Delete a highlight by ID
   client.delete_highlight(136887156).unwrap();
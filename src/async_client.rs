@@ -0,0 +1,1090 @@
+use {
+  crate::{
+    common::*,
+    sleep::{AsyncSleeper, TimerSleeper},
+  },
+  futures_util::future::{FutureExt, Shared},
+  std::sync::PoisonError,
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+use futures_util::future::BoxFuture;
+
+#[cfg(target_arch = "wasm32")]
+use futures_util::future::LocalBoxFuture;
+
+#[cfg(not(target_arch = "wasm32"))]
+type BookFuture = Shared<BoxFuture<'static, Result<Book, Arc<Error>>>>;
+
+#[cfg(target_arch = "wasm32")]
+type BookFuture = Shared<LocalBoxFuture<'static, Result<Book, Arc<Error>>>>;
+
+/// An asynchronous Readwise client, usable from WebAssembly
+///
+/// Dropping a future returned by the client cancels it, including any
+/// request in flight, so a long-running operation such as `export` can be
+/// stopped by dropping it or racing it against a shutdown signal; pages
+/// fetched before then are discarded.
+pub struct AsyncClient {
+  inner: Arc<Inner>,
+  /// In-flight `book` requests, when request coalescing is enabled
+  pending_books: Option<Mutex<HashMap<u64, BookFuture>>>,
+}
+
+struct Inner {
+  /// A readwise access token
+  access_token: SecretToken,
+  /// Sends requests with the access token and other default headers
+  http: reqwest::Client,
+  /// The server the API is served from, see `AsyncClientBuilder::base_url`
+  base_url: String,
+  /// How rate limited or failed requests are retried
+  retry: RetryPolicy,
+  /// Spaces requests, when enabled with `AsyncClientBuilder::rate_limit`
+  throttle: Option<Arc<Throttle>>,
+  /// Waits before throttled and retried requests, see
+  /// `AsyncClientBuilder::sleeper`
+  sleeper: Arc<dyn AsyncSleeper>,
+  /// Metadata of the most recently received response
+  last_response_meta: Mutex<Option<ResponseMeta>>,
+}
+
+/// Configures an `AsyncClient`, taking the same connection settings as
+/// `client::ClientBuilder`
+///
+/// The `reqwest` client is built once, so every request shares its connection
+/// pool. On `wasm32` the browser controls timeouts, the user agent and
+/// proxies, so those settings are ignored there.
+///
+/// ```no_run
+/// use {readwise::async_client::AsyncClientBuilder, std::time::Duration};
+///
+/// # async fn run() {
+/// let client = AsyncClientBuilder::new("token")
+///   .timeout(Duration::from_secs(30))
+///   .build_and_auth()
+///   .await
+///   .unwrap();
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct AsyncClientBuilder {
+  settings: HttpSettings,
+  sleeper: Arc<dyn AsyncSleeper>,
+}
+
+impl fmt::Debug for AsyncClientBuilder {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    f.debug_struct("AsyncClientBuilder")
+      .field("settings", &self.settings)
+      .finish_non_exhaustive()
+  }
+}
+
+impl AsyncClientBuilder {
+  /// Start configuring a client for the specified access token
+  pub fn new(access_token: &str) -> Self {
+    Self::with_settings(HttpSettings::new(access_token))
+  }
+
+  pub(crate) fn with_settings(settings: HttpSettings) -> Self {
+    Self {
+      settings,
+      sleeper: Arc::new(TimerSleeper),
+    }
+  }
+
+  /// See `ClientBuilder::base_url`
+  pub fn base_url(mut self, base_url: &str) -> Self {
+    self.settings.base_url = base_url.trim_end_matches('/').to_string();
+    self
+  }
+
+  /// See `ClientBuilder::timeout`
+  pub fn timeout(mut self, timeout: Duration) -> Self {
+    self.settings.timeout = Some(timeout);
+    self
+  }
+
+  /// See `ClientBuilder::user_agent`
+  pub fn user_agent(mut self, user_agent: &str) -> Self {
+    self.settings.user_agent = Some(user_agent.to_string());
+    self
+  }
+
+  /// See `ClientBuilder::proxy`
+  pub fn proxy(mut self, url: &str) -> Self {
+    self.settings.proxy = Some(url.to_string());
+    self
+  }
+
+  /// See `ClientBuilder::header`
+  pub fn header(mut self, name: &str, value: &str) -> Self {
+    self
+      .settings
+      .headers
+      .push((name.to_string(), value.to_string()));
+    self
+  }
+
+  /// See `Client::retries`
+  pub fn retries(mut self, retries: u32) -> Self {
+    self.settings.retries = retries;
+    self
+  }
+
+  /// See `Client::retry_budget`
+  pub fn retry_budget(mut self, budget: RetryBudget) -> Self {
+    self.settings.retry_budget = Some(budget);
+    self
+  }
+
+  /// See `ClientBuilder::backoff`
+  pub fn backoff(mut self, base: Duration, cap: Duration) -> Self {
+    self.settings.backoff_base = base;
+    self.settings.backoff_cap = cap;
+    self
+  }
+
+  /// See `ClientBuilder::rate_limit`
+  pub fn rate_limit(mut self, requests_per_minute: u32) -> Self {
+    self.settings.rate_limit = requests_per_minute;
+    self
+  }
+
+  /// Wait before throttled and retried requests with `sleeper` instead of
+  /// the executor independent `TimerSleeper`, for example to use the
+  /// application's runtime timer
+  pub fn sleeper(mut self, sleeper: Box<dyn AsyncSleeper>) -> Self {
+    self.sleeper = Arc::from(sleeper);
+    self
+  }
+
+  /// Build the client without checking the access token against the API
+  ///
+  /// A blank token fails with `Error::MissingToken`.
+  pub fn build(self) -> Result<AsyncClient> {
+    Ok(AsyncClient {
+      inner: Arc::new(Inner {
+        http: self.settings.async_client()?,
+        retry: self.settings.retry_policy(),
+        throttle: self.settings.throttle(),
+        access_token: self.settings.access_token,
+        base_url: self.settings.base_url,
+        sleeper: self.sleeper,
+        last_response_meta: Mutex::new(None),
+      }),
+      pending_books: None,
+    })
+  }
+
+  /// Build the client and check the access token against the API
+  pub async fn build_and_auth(self) -> Result<AsyncClient> {
+    let client = self.build()?;
+    client.verify_token().await?;
+    Ok(client)
+  }
+}
+
+impl fmt::Debug for AsyncClient {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    f.debug_struct("AsyncClient")
+      .field("access_token", &self.inner.access_token)
+      .field("coalesce_requests", &self.pending_books.is_some())
+      .finish_non_exhaustive()
+  }
+}
+
+impl AsyncClient {
+  /// Create and authenticate a new asynchronous Readwise client from a
+  /// specified access token
+  ///
+  /// ```no_run
+  /// use readwise::async_client::AsyncClient;
+  ///
+  /// # async fn run() {
+  /// let client = AsyncClient::new("token").await.unwrap();
+  /// # }
+  /// ```
+  pub async fn new(access_token: &str) -> Result<Self> {
+    AsyncClientBuilder::new(access_token).build_and_auth().await
+  }
+
+  /// Start configuring a client for the specified access token
+  pub fn builder(access_token: &str) -> AsyncClientBuilder {
+    AsyncClientBuilder::new(access_token)
+  }
+
+  /// Check that the access token is still valid, failing with
+  /// `Error::Unauthorized` once it has been revoked
+  pub async fn verify_token(&self) -> Result {
+    self.inner.request("/auth", Method::GET, None).await?;
+    Ok(())
+  }
+
+  /// Share a single in-flight request between concurrent `book` calls for the
+  /// same identifier
+  ///
+  /// This trades a little synchronization overhead for fewer requests when
+  /// several parts of an application ask for the same book at once.
+  ///
+  /// ```no_run
+  /// use readwise::async_client::AsyncClient;
+  ///
+  /// # async fn run() {
+  /// let client = AsyncClient::new("token")
+  ///   .await
+  ///   .unwrap()
+  ///   .coalesce_requests();
+  /// # }
+  /// ```
+  pub fn coalesce_requests(mut self) -> Self {
+    self.pending_books = Some(Mutex::new(HashMap::new()));
+    self
+  }
+
+  /// Create and authenticate a new asynchronous Readwise client from the access
+  /// token in the `READWISE_TOKEN` environment variable
+  pub async fn from_env() -> Result<Self> {
+    Self::from_env_var(TOKEN_ENV_VAR).await
+  }
+
+  /// Create and authenticate a new asynchronous Readwise client from the access
+  /// token in the specified environment variable
+  pub async fn from_env_var(name: &str) -> Result<Self> {
+    Self::new(
+      &env::var(name).context(error::EnvironmentVariableSnafu { name })?,
+    )
+    .await
+  }
+
+  /// Fetch all books from a specified page
+  ///
+  /// ```no_run
+  /// use readwise::async_client::AsyncClient;
+  ///
+  /// # async fn run() {
+  /// let client = AsyncClient::new("token").await.unwrap();
+  /// let books = client.books(1).await.unwrap();
+  /// # }
+  /// ```
+  pub async fn books(&self, page: u64) -> Result<Vec<Book>> {
+    Ok(
+      from_json::<BooksResponse>(
+        &self
+          .inner
+          .request(&BookFilter::new().page(page).endpoint(), Method::GET, None)
+          .await?
+          .bytes()
+          .await?,
+      )?
+      .results,
+    )
+  }
+
+  /// Fetch all highlights from a specified page
+  ///
+  /// ```no_run
+  /// use readwise::async_client::AsyncClient;
+  ///
+  /// # async fn run() {
+  /// let client = AsyncClient::new("token").await.unwrap();
+  /// let highlights = client.highlights(1).await.unwrap();
+  /// # }
+  /// ```
+  pub async fn highlights(&self, page: u64) -> Result<Vec<Highlight>> {
+    Ok(
+      from_json::<HighlightsResponse>(
+        &self
+          .inner
+          .request(
+            &HighlightFilter::new().page(page).endpoint(),
+            Method::GET,
+            None,
+          )
+          .await?
+          .bytes()
+          .await?,
+      )?
+      .results,
+    )
+  }
+
+  /// Fetch a page of books matching a filter, along with the total count and
+  /// pagination links
+  pub async fn books_with(&self, filter: &BookFilter) -> Result<BooksResponse> {
+    from_json::<BooksResponse>(
+      &self
+        .inner
+        .request(&filter.endpoint(), Method::GET, None)
+        .await?
+        .bytes()
+        .await?,
+    )
+  }
+
+  /// Fetch a page of highlights matching a filter, along with the total count
+  /// and pagination links
+  pub async fn highlights_with(
+    &self,
+    filter: &HighlightFilter,
+  ) -> Result<HighlightsResponse> {
+    let mut response = from_json::<HighlightsResponse>(
+      &self
+        .inner
+        .request(&filter.endpoint(), Method::GET, None)
+        .await?
+        .bytes()
+        .await?,
+    )?;
+
+    response
+      .results
+      .retain(|highlight| filter.matches(highlight));
+
+    Ok(response)
+  }
+
+  /// Fetch a single book by identifier
+  ///
+  /// ```no_run
+  /// use readwise::async_client::AsyncClient;
+  ///
+  /// # async fn run() {
+  /// let client = AsyncClient::new("token").await.unwrap();
+  /// let book = client.book(1).await.unwrap();
+  /// # }
+  /// ```
+  pub async fn book(&self, id: u64) -> Result<Book> {
+    let pending = match &self.pending_books {
+      Some(pending) => pending,
+      None => return self.inner.book(id).await,
+    };
+
+    let future = pending
+      .lock()
+      .unwrap_or_else(PoisonError::into_inner)
+      .entry(id)
+      .or_insert_with(|| {
+        let inner = self.inner.clone();
+
+        let future = async move { inner.book(id).await.map_err(Arc::new) };
+
+        #[cfg(not(target_arch = "wasm32"))]
+        return future.boxed().shared();
+        #[cfg(target_arch = "wasm32")]
+        return future.boxed_local().shared();
+      })
+      .clone();
+
+    let result = future.await;
+
+    pending
+      .lock()
+      .unwrap_or_else(PoisonError::into_inner)
+      .remove(&id);
+
+    result.map_err(|error| {
+      Arc::try_unwrap(error).unwrap_or_else(|source| Error::Shared { source })
+    })
+  }
+
+  /// Fetch a single highlight by identifier
+  ///
+  /// ```no_run
+  /// use readwise::async_client::AsyncClient;
+  ///
+  /// # async fn run() {
+  /// let client = AsyncClient::new("token").await.unwrap();
+  /// let highlight = client.highlight(1).await.unwrap();
+  /// # }
+  /// ```
+  pub async fn highlight(&self, id: u64) -> Result<Highlight> {
+    from_json::<Highlight>(
+      &self
+        .inner
+        .request(&format!("/highlights/{}", id), Method::GET, None)
+        .await?
+        .bytes()
+        .await?,
+    )
+  }
+
+  /// Fetch a book along with all of its highlights, following pagination
+  ///
+  /// The book and the highlights are requested concurrently.
+  ///
+  /// ```no_run
+  /// use readwise::async_client::AsyncClient;
+  ///
+  /// # async fn run() {
+  /// let client = AsyncClient::new("token").await.unwrap();
+  ///
+  /// let (book, highlights) = client.book_with_highlights(1).await.unwrap();
+  ///
+  /// println!("{}: {} highlights", book.title, highlights.len());
+  /// # }
+  /// ```
+  pub async fn book_with_highlights(
+    &self,
+    id: u64,
+  ) -> Result<(Book, Vec<Highlight>)> {
+    futures_util::future::try_join(self.book(id), self.collect_highlights(id))
+      .await
+  }
+
+  async fn collect_highlights(&self, book_id: u64) -> Result<Vec<Highlight>> {
+    let mut highlights = Vec::new();
+
+    for page in 1.. {
+      let response = self
+        .highlights_with(
+          &HighlightFilter::new()
+            .page(page)
+            .page_size(MAX_PAGE_SIZE)
+            .book_id(book_id),
+        )
+        .await?;
+
+      highlights.extend(response.results);
+
+      if response.next.is_none() {
+        break;
+      }
+    }
+
+    Ok(highlights)
+  }
+
+  /// Create and return one or more highlights
+  ///
+  /// Highlights are sent in batches of at most `model::MAX_CREATE_BATCH_SIZE`,
+  /// one request after another. If a batch fails, those before it have
+  /// already been created. Each returned highlight has its `highlight_url`
+  /// set, linking to it on readwise.io.
+  ///
+  /// ```no_run
+  /// use readwise::{async_client::AsyncClient, model::HighlightCreate};
+  ///
+  /// # async fn run() {
+  /// let client = AsyncClient::new("token").await.unwrap();
+  ///
+  /// let new_highlight = HighlightCreate {
+  ///   text: "hello world!".into(),
+  ///   ..Default::default()
+  /// };
+  ///
+  /// for highlight in client.create_highlights(vec![new_highlight]).await.unwrap() {
+  ///   println!("{}", highlight.text);
+  /// }
+  /// # }
+  /// ```
+  pub async fn create_highlights<T: Serialize>(
+    &self,
+    highlights: Vec<T>,
+  ) -> Result<Vec<Highlight>> {
+    let mut identifiers = Vec::new();
+
+    for batch in create_payloads(highlights)?.chunks(MAX_CREATE_BATCH_SIZE) {
+      identifiers.extend(
+        from_json::<Vec<HighlightCreateResponse>>(
+          &self
+            .inner
+            .request(
+              "/highlights",
+              Method::POST,
+              Some(serde_json::json!({ "highlights": batch })),
+            )
+            .await?
+            .bytes()
+            .await?,
+        )?
+        .into_iter()
+        .flat_map(|item| item.modified_highlights),
+      );
+    }
+
+    let mut created = Vec::with_capacity(identifiers.len());
+
+    for identifier in identifiers {
+      let mut highlight = self.highlight(identifier).await?;
+      highlight.highlight_url = Some(highlight.readwise_url());
+      created.push(highlight);
+    }
+
+    Ok(created)
+  }
+
+  /// Update a single highlight by identifier
+  ///
+  /// When the response has an empty body, such as a `204 No Content`, the
+  /// updated highlight is fetched instead.
+  ///
+  /// ```no_run
+  /// use readwise::{async_client::AsyncClient, model::HighlightUpdate};
+  ///
+  /// # async fn run() {
+  /// let client = AsyncClient::new("token").await.unwrap();
+  ///
+  /// client
+  ///   .update_highlight(
+  ///     1,
+  ///     HighlightUpdate {
+  ///       text: Some("hello, world!".into()),
+  ///       ..Default::default()
+  ///     },
+  ///   )
+  ///   .await
+  ///   .unwrap();
+  /// # }
+  /// ```
+  pub async fn update_highlight(
+    &self,
+    id: u64,
+    update: HighlightUpdate,
+  ) -> Result<Highlight> {
+    let body = self
+      .inner
+      .request(
+        &format!("/highlights/{}", id),
+        Method::PATCH,
+        Some(serde_json::to_value(update)?),
+      )
+      .await?
+      .bytes()
+      .await?;
+
+    match body.trim_ascii().is_empty() {
+      true => self.highlight(id).await,
+      false => from_json::<Highlight>(&body),
+    }
+  }
+
+  /// Delete a single highlight by identifier, reporting a highlight that
+  /// does not exist as `DeleteOutcome::NotFound`
+  ///
+  /// ```no_run
+  /// use readwise::async_client::AsyncClient;
+  ///
+  /// # async fn run() {
+  /// let client = AsyncClient::new("token").await.unwrap();
+  /// client.delete_highlight(1).await.unwrap();
+  /// # }
+  /// ```
+  pub async fn delete_highlight(&self, id: u64) -> Result<DeleteOutcome> {
+    DeleteOutcome::from_result(
+      self
+        .inner
+        .request(&format!("/highlights/{}", id), Method::DELETE, None)
+        .await,
+    )
+  }
+
+  /// Fetch every book and its highlights from the export endpoint
+  ///
+  /// ```no_run
+  /// use readwise::{async_client::AsyncClient, model::Category};
+  ///
+  /// # async fn run() {
+  /// let client = AsyncClient::new("token").await.unwrap();
+  ///
+  /// let books = client.export(None, Some(Category::Articles)).await.unwrap();
+  /// # }
+  /// ```
+  pub async fn export(
+    &self,
+    updated_after: Option<&str>,
+    category: Option<Category>,
+  ) -> Result<Vec<ExportBook>> {
+    let mut books = Vec::new();
+
+    let mut cursor: Option<String> = None;
+
+    loop {
+      let endpoint =
+        export_endpoint(updated_after, category, cursor.as_deref());
+
+      let response = from_json::<ExportResponse>(
+        &self
+          .inner
+          .request(&endpoint, Method::GET, None)
+          .await?
+          .bytes()
+          .await?,
+      )?;
+
+      books.extend(response.results);
+
+      cursor = match response.next_page_cursor {
+        Some(next) => Some(next),
+        None => break,
+      };
+    }
+
+    Ok(books)
+  }
+
+  /// Fetch every tag in the account, sorted by name, by scanning a full
+  /// export
+  pub async fn tags(&self) -> Result<Vec<Tag>> {
+    Ok(unique_tags(&self.export(None, None).await?))
+  }
+
+  /// Send an authenticated `GET` request to an arbitrary v2 API endpoint,
+  /// returning the raw JSON response
+  pub async fn get_raw(&self, endpoint: &str) -> Result<serde_json::Value> {
+    from_json(
+      &self
+        .inner
+        .request(endpoint, Method::GET, None)
+        .await?
+        .bytes()
+        .await?,
+    )
+  }
+
+  /// Send an authenticated `POST` request with a JSON body to an arbitrary v2
+  /// API endpoint, returning the raw JSON response, or `null` when the
+  /// response body is empty
+  pub async fn post_raw(
+    &self,
+    endpoint: &str,
+    body: serde_json::Value,
+  ) -> Result<serde_json::Value> {
+    let body = self
+      .inner
+      .request(endpoint, Method::POST, Some(body))
+      .await?
+      .bytes()
+      .await?;
+
+    match body.trim_ascii().is_empty() {
+      true => Ok(serde_json::Value::Null),
+      false => from_json(&body),
+    }
+  }
+
+  /// Metadata of the most recent response received by this client, including
+  /// error responses
+  pub fn last_response_meta(&self) -> Option<ResponseMeta> {
+    self.inner.last_response_meta.lock().ok()?.clone()
+  }
+
+  /// Run `future` to completion on a new single threaded runtime, for calling
+  /// the client from synchronous code
+  ///
+  /// This lets an application that is mostly asynchronous share one client,
+  /// and its configuration, with its few synchronous entry points instead of
+  /// keeping a separate blocking `Client` in sync. Calling it from within an
+  /// async runtime, where it would block that runtime's thread, fails with
+  /// `Error::NestedRuntime` instead, so await the future there.
+  ///
+  /// ```no_run
+  /// use readwise::async_client::AsyncClient;
+  ///
+  /// let client = AsyncClient::block_on(AsyncClient::new("token")).unwrap();
+  ///
+  /// for book in AsyncClient::block_on(client.books(1)).unwrap() {
+  ///   println!("{}", book);
+  /// }
+  /// ```
+  #[cfg(feature = "block-on")]
+  pub fn block_on<T>(
+    future: impl std::future::Future<Output = Result<T>>,
+  ) -> Result<T> {
+    if tokio::runtime::Handle::try_current().is_ok() {
+      return Err(Error::NestedRuntime);
+    }
+
+    tokio::runtime::Builder::new_current_thread()
+      .enable_all()
+      .build()?
+      .block_on(future)
+  }
+}
+
+impl Inner {
+  async fn book(&self, id: u64) -> Result<Book> {
+    from_json::<Book>(
+      &self
+        .request(&format!("/books/{}", id), Method::GET, None)
+        .await?
+        .bytes()
+        .await?,
+    )
+  }
+
+  async fn request(
+    &self,
+    endpoint: &str,
+    method: Method,
+    body: Option<serde_json::Value>,
+  ) -> Result<reqwest::Response> {
+    let url = api_url(&self.base_url, endpoint);
+
+    let mut attempt = 0;
+
+    loop {
+      if let Some(throttle) = &self.throttle {
+        self.sleeper.sleep(throttle.reserve()).await;
+      }
+
+      let request = match (&method, &body) {
+        (&Method::GET, _) => Ok(self.http.get(&url)),
+        (&Method::POST, Some(body)) => Ok(self.http.post(&url).json(body)),
+        (&Method::PATCH, Some(body)) => Ok(self.http.patch(&url).json(body)),
+        (&Method::DELETE, _) => Ok(self.http.delete(&url)),
+        (method, _) => Err(error::Error::UnsupportedRequest {
+          method: method.clone(),
+        }),
+      };
+
+      let stopwatch = Stopwatch::start();
+
+      let response = match request?.send().await {
+        Ok(response) => response,
+        Err(error) => {
+          let error = Error::from(error);
+
+          let wait = (error.is_retryable() && method != Method::POST)
+            .then(|| self.retry.wait(attempt, None))
+            .flatten();
+
+          match wait {
+            Some(wait) => {
+              attempt += 1;
+              self.sleeper.sleep(wait).await;
+              continue;
+            }
+            None => return Err(error),
+          }
+        }
+      };
+
+      let meta = ResponseMeta {
+        status: response.status(),
+        headers: response.headers().clone(),
+        url: response.url().to_string(),
+        elapsed: stopwatch.elapsed(),
+      };
+
+      if let Ok(mut last) = self.last_response_meta.lock() {
+        *last = Some(meta.clone());
+      }
+
+      let retryable = meta.status == StatusCode::TOO_MANY_REQUESTS
+        || (meta.status.is_server_error() && method != Method::POST);
+
+      let wait = retryable
+        .then(|| self.retry.wait(attempt, error::retry_after(&meta.headers)))
+        .flatten();
+
+      if let Some(wait) = wait {
+        attempt += 1;
+        self.sleeper.sleep(wait).await;
+        continue;
+      }
+
+      return match response.status().is_success() {
+        true => Ok(response),
+        false => Err(Error::from_response(meta, &response.text().await?)),
+      };
+    }
+  }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+  use {super::*, mockito::mock};
+
+  fn client() -> AsyncClient {
+    AsyncClientBuilder::new("token").build().unwrap()
+  }
+
+  #[tokio::test]
+  async fn blank_token() {
+    assert!(matches!(
+      AsyncClient::new("").await,
+      Err(Error::MissingToken)
+    ));
+  }
+
+  #[tokio::test]
+  async fn authenticate() {
+    let _m = mock("GET", "/api/v2/auth").with_status(204).create();
+
+    let result = AsyncClient::new("token").await;
+
+    assert!(result.is_ok(), "{}", result.err().unwrap().to_string());
+  }
+
+  #[tokio::test]
+  async fn builder_settings() {
+    let _m = mock("GET", "/api/v2/auth")
+      .match_header("x-builder", "async")
+      .with_status(204)
+      .create();
+
+    let result = AsyncClient::builder("token")
+      .base_url(&format!("{}/", mockito::server_url()))
+      .timeout(Duration::from_secs(5))
+      .header("X-Builder", "async")
+      .build_and_auth()
+      .await;
+
+    assert!(result.is_ok(), "{}", result.err().unwrap().to_string());
+  }
+
+  #[derive(Clone, Default)]
+  struct RecordingSleeper(Arc<Mutex<Vec<Duration>>>);
+
+  impl AsyncSleeper for RecordingSleeper {
+    fn sleep(&self, duration: Duration) -> crate::sleep::SleepFuture {
+      self.0.lock().unwrap().push(duration);
+      Box::pin(async {})
+    }
+  }
+
+  #[tokio::test]
+  async fn retries() {
+    let _unavailable = mock("GET", "/api/v2/highlights/7")
+      .with_status(503)
+      .expect(1)
+      .create();
+
+    let _found = mock("GET", "/api/v2/highlights/7")
+      .with_status(200)
+      .with_body(serde_json::to_string(&Highlight::default()).unwrap())
+      .create();
+
+    let sleeper = RecordingSleeper::default();
+
+    let result = AsyncClient::builder("token")
+      .retries(1)
+      .backoff(Duration::from_secs(10), Duration::from_secs(10))
+      .sleeper(Box::new(sleeper.clone()))
+      .build()
+      .unwrap()
+      .highlight(7)
+      .await;
+
+    assert!(result.is_ok(), "{}", result.err().unwrap().to_string());
+
+    let waits = sleeper.0.lock().unwrap().clone();
+
+    assert_eq!(waits.len(), 1);
+    assert!(waits[0] >= Duration::from_secs(5), "{:?}", waits);
+  }
+
+  #[tokio::test]
+  async fn rate_limit() {
+    let _m = mock("GET", "/api/v2/auth").with_status(204).create();
+
+    let sleeper = RecordingSleeper::default();
+
+    let client = AsyncClient::builder("token")
+      .rate_limit(1)
+      .sleeper(Box::new(sleeper.clone()))
+      .build()
+      .unwrap();
+
+    client.verify_token().await.unwrap();
+    client.verify_token().await.unwrap();
+
+    let waits = sleeper.0.lock().unwrap().clone();
+
+    assert_eq!(waits[0], Duration::ZERO);
+    assert!(waits[1] >= Duration::from_secs(50), "{:?}", waits);
+  }
+
+  #[cfg(feature = "blocking")]
+  #[tokio::test]
+  async fn shared_builder() {
+    let _m = mock("GET", "/api/v2/auth")
+      .match_header("x-builder", "shared")
+      .with_status(204)
+      .create();
+
+    let result = crate::client::ClientBuilder::new("token")
+      .header("X-Builder", "shared")
+      .build_async()
+      .unwrap()
+      .verify_token()
+      .await;
+
+    assert!(result.is_ok(), "{}", result.err().unwrap().to_string());
+  }
+
+  #[tokio::test]
+  async fn highlights() {
+    let _m = mock("GET", "/api/v2/highlights?page=1")
+      .with_status(200)
+      .with_body(format!(
+        r#" {{ "count": 1, "next": null, "previous": null, "results": [{}] }} "#,
+        serde_json::to_string(&Highlight::default()).unwrap()
+      ))
+      .create();
+
+    let result = client().highlights(1).await;
+
+    assert!(result.is_ok(), "{}", result.err().unwrap().to_string());
+  }
+
+  #[tokio::test]
+  async fn update_highlight() {
+    let _m = mock("PATCH", "/api/v2/highlights/1")
+      .match_body(r#"{"text":"hello"}"#)
+      .with_status(200)
+      .with_body(serde_json::to_string(&Highlight::default()).unwrap())
+      .create();
+
+    let result = client()
+      .update_highlight(
+        1,
+        HighlightUpdate {
+          text: Some("hello".into()),
+          ..Default::default()
+        },
+      )
+      .await;
+
+    assert!(result.is_ok(), "{}", result.err().unwrap().to_string());
+  }
+
+  #[tokio::test]
+  async fn delete_highlight() {
+    let _m = mock("DELETE", "/api/v2/highlights/1")
+      .with_status(204)
+      .create();
+
+    assert_eq!(
+      client().delete_highlight(1).await.unwrap(),
+      DeleteOutcome::Deleted
+    );
+  }
+
+  #[tokio::test]
+  async fn delete_highlight_not_found() {
+    let _m = mock("DELETE", "/api/v2/highlights/2")
+      .with_status(404)
+      .create();
+
+    assert_eq!(
+      client().delete_highlight(2).await.unwrap(),
+      DeleteOutcome::NotFound
+    );
+  }
+
+  #[tokio::test]
+  async fn book_with_highlights() {
+    let _book = mock("GET", "/api/v2/books/3")
+      .with_status(200)
+      .with_body(
+        serde_json::to_string(&Book {
+          id: 3,
+          ..Default::default()
+        })
+        .unwrap(),
+      )
+      .create();
+
+    let page = |page: u64, next: Option<&str>| {
+      mock(
+        "GET",
+        format!("/api/v2/highlights?page={}&page_size=1000&book_id=3", page)
+          .as_str(),
+      )
+      .with_status(200)
+      .with_body(
+        serde_json::to_string(&HighlightsResponse {
+          count: 2,
+          next: next.map(str::to_string),
+          previous: None,
+          results: vec![Highlight {
+            id: page,
+            ..Default::default()
+          }],
+        })
+        .unwrap(),
+      )
+      .create()
+    };
+
+    let _first = page(1, Some("next"));
+    let _second = page(2, None);
+
+    let (book, highlights) = client().book_with_highlights(3).await.unwrap();
+
+    assert_eq!(book.id, 3);
+    assert_eq!(
+      highlights.iter().map(|h| h.id).collect::<Vec<u64>>(),
+      [1, 2]
+    );
+  }
+
+  #[cfg(feature = "block-on")]
+  #[test]
+  fn block_on() {
+    let _m = mock("GET", "/api/v2/books/2")
+      .with_status(200)
+      .with_body(serde_json::to_string(&Book::default()).unwrap())
+      .create();
+
+    let result = AsyncClient::block_on(client().book(2));
+
+    assert!(result.is_ok(), "{}", result.err().unwrap().to_string());
+  }
+
+  #[cfg(feature = "block-on")]
+  #[tokio::test]
+  async fn block_on_within_runtime() {
+    assert!(matches!(
+      AsyncClient::block_on(client().book(2)),
+      Err(Error::NestedRuntime)
+    ));
+  }
+
+  #[tokio::test]
+  async fn update_highlight_without_body() {
+    let _patch = mock("PATCH", "/api/v2/highlights/2")
+      .with_status(204)
+      .create();
+
+    let _get = mock("GET", "/api/v2/highlights/2")
+      .with_status(200)
+      .with_body(
+        serde_json::to_string(&Highlight {
+          id: 2,
+          ..Default::default()
+        })
+        .unwrap(),
+      )
+      .create();
+
+    let highlight = client()
+      .update_highlight(2, HighlightUpdate::default())
+      .await
+      .unwrap();
+
+    assert_eq!(highlight.id, 2);
+  }
+
+  #[tokio::test]
+  async fn coalesce_requests() {
+    let m = mock("GET", "/api/v2/books/1")
+      .with_status(200)
+      .with_body(serde_json::to_string(&Book::default()).unwrap())
+      .expect(1)
+      .create();
+
+    let client = client().coalesce_requests();
+
+    let (first, second) =
+      futures_util::future::join(client.book(1), client.book(1)).await;
+
+    assert!(first.is_ok(), "{}", first.err().unwrap().to_string());
+    assert!(second.is_ok(), "{}", second.err().unwrap().to_string());
+
+    m.assert();
+  }
+}
@@ -0,0 +1,117 @@
+use {
+  crate::common::*,
+  std::{collections::VecDeque, hash::Hash},
+};
+
+/// A fixed-capacity cache that evicts its least recently used entry
+#[derive(Debug)]
+pub(crate) struct LruCache<K, V> {
+  capacity: usize,
+  entries: HashMap<K, V>,
+  /// Keys from least to most recently used
+  order: VecDeque<K>,
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> LruCache<K, V> {
+  pub(crate) fn new(capacity: usize) -> Self {
+    Self {
+      capacity,
+      entries: HashMap::new(),
+      order: VecDeque::new(),
+    }
+  }
+
+  pub(crate) fn get(&mut self, key: &K) -> Option<V> {
+    let value = self.entries.get(key)?.clone();
+    self.touch(key);
+    Some(value)
+  }
+
+  pub(crate) fn insert(&mut self, key: K, value: V) {
+    if self.capacity == 0 {
+      return;
+    }
+
+    if self.entries.insert(key.clone(), value).is_some() {
+      self.touch(&key);
+      return;
+    }
+
+    self.order.push_back(key);
+
+    if self.order.len() > self.capacity {
+      if let Some(oldest) = self.order.pop_front() {
+        self.entries.remove(&oldest);
+      }
+    }
+  }
+
+  pub(crate) fn remove(&mut self, key: &K) {
+    if self.entries.remove(key).is_some() {
+      self.order.retain(|other| other != key);
+    }
+  }
+
+  pub(crate) fn clear(&mut self) {
+    self.entries.clear();
+    self.order.clear();
+  }
+
+  fn touch(&mut self, key: &K) {
+    if let Some(position) = self.order.iter().position(|other| other == key) {
+      if let Some(key) = self.order.remove(position) {
+        self.order.push_back(key);
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn evicts_least_recently_used() {
+    let mut cache = LruCache::new(2);
+
+    cache.insert(1, "one");
+    cache.insert(2, "two");
+
+    assert_eq!(cache.get(&1), Some("one"));
+
+    cache.insert(3, "three");
+
+    assert_eq!(cache.get(&2), None);
+    assert_eq!(cache.get(&1), Some("one"));
+    assert_eq!(cache.get(&3), Some("three"));
+  }
+
+  #[test]
+  fn remove_and_clear() {
+    let mut cache = LruCache::new(2);
+
+    cache.insert(1, "one");
+    cache.insert(2, "two");
+    cache.remove(&1);
+
+    assert_eq!(cache.get(&1), None);
+
+    cache.insert(3, "three");
+
+    assert_eq!(cache.get(&2), Some("two"));
+
+    cache.clear();
+
+    assert_eq!(cache.get(&2), None);
+    assert_eq!(cache.get(&3), None);
+  }
+
+  #[test]
+  fn zero_capacity() {
+    let mut cache = LruCache::new(0);
+
+    cache.insert(1, "one");
+
+    assert_eq!(cache.get(&1), None);
+  }
+}
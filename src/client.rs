@@ -1,385 +1,6170 @@
 use crate::common::*;
 
+/// How many sampled highlights may turn out to be missing or duplicated
+/// before `random_highlights` gives up and returns what it has
+const RANDOM_HIGHLIGHT_RETRIES: usize = 10;
+
+/// The book Readwise files highlights under when they are created without a
+/// title
+const DEFAULT_BOOK_TITLE: &str = "Quotes";
+
+/// The most requests `all_books_parallel` and `all_highlights_parallel` send
+/// at once, keeping them under the list endpoints' rate limit
+const MAX_CONCURRENCY: usize = 4;
+
+/// A progress callback registered with `Client::on_event`
+type EventHandler = Box<dyn FnMut(Event) + Send>;
+
+/// Items with an identifier unique among items of their type, for dropping
+/// those repeated across pages
+trait Identified {
+  fn identifier(&self) -> u64;
+}
+
+impl Identified for Book {
+  fn identifier(&self) -> u64 {
+    self.id
+  }
+}
+
+impl Identified for Highlight {
+  fn identifier(&self) -> u64 {
+    self.id
+  }
+}
+
+/// The progress of an interrupted `export_to_file`, saved in its sidecar
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct ExportCheckpoint {
+  /// The cursor of the next page to fetch
+  cursor: String,
+  /// The length of the export file once the pages before `cursor` were
+  /// written, beyond which any bytes are from an unfinished page
+  length: u64,
+  updated_after: Option<String>,
+}
+
+/// Parse a timestamp bound passed by the caller, see `parse_timestamp`
+fn timestamp_bound(value: &str) -> Result<(i64, u32)> {
+  parse_timestamp(value).ok_or_else(|| Error::InvalidTimestamp {
+    value: value.to_string(),
+  })
+}
+
+/// The endpoint below `/api/v2` of `url`, a page of the list at `endpoint`
+/// on readwise.io
+fn page_endpoint(url: &str, endpoint: &'static str) -> Result<String> {
+  let invalid = || Error::InvalidPageUrl {
+    url: url.to_string(),
+    endpoint,
+  };
+
+  let rest = url
+    .strip_prefix(READWISE_URL)
+    .and_then(|rest| rest.strip_prefix("/api/v2"))
+    .ok_or_else(invalid)?;
+
+  let (path, query) = rest.split_once('?').unwrap_or((rest, ""));
+
+  if path.strip_suffix('/').unwrap_or(path) != endpoint {
+    return Err(invalid());
+  }
+
+  Ok(format!("{}?{}", endpoint, query))
+}
+
+/// Configures and builds a `Client`
+///
+/// ```no_run
+/// use {readwise::client::ClientBuilder, std::time::Duration};
+///
+/// let client = ClientBuilder::new("token")
+///   .timeout(Duration::from_secs(30))
+///   .retries(3)
+///   .rate_limit(20)
+///   .cache_books(100)
+///   .header("X-Correlation-Id", "abc123")
+///   .build_and_auth()
+///   .unwrap();
+/// ```
+pub struct ClientBuilder {
+  settings: HttpSettings,
+  dry_run: bool,
+  dedup_pagination: bool,
+  create_batch_size: usize,
+  cancel_flag: Option<Arc<AtomicBool>>,
+  book_cache: Option<usize>,
+  on_event: Option<EventHandler>,
+  observer: Option<Box<dyn RequestObserver>>,
+  response_cache: Option<Box<dyn CacheStore>>,
+  transport: Option<Box<dyn Transport>>,
+  sleeper: Box<dyn Sleeper>,
+}
+
+impl ClientBuilder {
+  /// Start configuring a client for the specified access token
+  pub fn new(access_token: &str) -> Self {
+    Self {
+      settings: HttpSettings::new(access_token),
+      dry_run: false,
+      dedup_pagination: false,
+      create_batch_size: MAX_CREATE_BATCH_SIZE,
+      cancel_flag: None,
+      book_cache: None,
+      on_event: None,
+      observer: None,
+      response_cache: None,
+      transport: None,
+      sleeper: Box::new(ThreadSleeper),
+    }
+  }
+
+  /// Send requests to `base_url` instead of `https://readwise.io`, for
+  /// example to go through a recording proxy or to a mock server in tests
+  ///
+  /// ```
+  /// use readwise::client::ClientBuilder;
+  ///
+  /// let _auth = mockito::mock("GET", "/api/v2/auth")
+  ///   .match_header("Authorization", "Token token")
+  ///   .with_status(204)
+  ///   .create();
+  ///
+  /// let client = ClientBuilder::new("token")
+  ///   .base_url(&mockito::server_url())
+  ///   .build_and_auth()
+  ///   .unwrap();
+  /// ```
+  pub fn base_url(mut self, base_url: &str) -> Self {
+    self.settings.base_url = base_url.trim_end_matches('/').to_string();
+    self
+  }
+
+  /// Fail requests that take longer than `timeout` with `Error::Timeout`
+  pub fn timeout(mut self, timeout: Duration) -> Self {
+    self.settings.timeout = Some(timeout);
+    self
+  }
+
+  /// See `Client::retries`
+  pub fn retries(mut self, retries: u32) -> Self {
+    self.settings.retries = retries;
+    self
+  }
+
+  /// See `Client::retry_budget`
+  pub fn retry_budget(mut self, budget: RetryBudget) -> Self {
+    self.settings.retry_budget = Some(budget);
+    self
+  }
+
+  /// Wait between `base / 2` and `base` before the first retry of a request
+  /// whose response has no `Retry-After` header, doubling with every further
+  /// retry up to `cap`
+  ///
+  /// The wait is randomized so that many clients retrying at once spread
+  /// their requests out. Defaults to a base of one second and a cap of one
+  /// minute.
+  pub fn backoff(mut self, base: Duration, cap: Duration) -> Self {
+    self.settings.backoff_base = base;
+    self.settings.backoff_cap = cap;
+    self
+  }
+
+  /// Send at most `requests_per_minute` requests a minute, waiting before a
+  /// request as needed to space them evenly
+  ///
+  /// Readwise limits most endpoints to 240 requests a minute, and the list
+  /// endpoints to 20, so pacing long-running jobs below these avoids rate
+  /// limited responses altogether. Zero, the default, sends requests as soon
+  /// as possible.
+  pub fn rate_limit(mut self, requests_per_minute: u32) -> Self {
+    self.settings.rate_limit = requests_per_minute;
+    self
+  }
+
+  /// Skip every `POST`, `PATCH` and `DELETE` request, reporting it as an
+  /// `Event::DryRun` instead of sending it
+  ///
+  /// `GET` requests are still sent. Skipped requests succeed as if the API
+  /// had responded with `204 No Content`, so deletes report
+  /// `DeleteOutcome::Deleted` and other writes return an empty or default
+  /// value, which makes it safe to rehearse a bulk update of a library.
+  ///
+  /// ```no_run
+  /// use readwise::client::ClientBuilder;
+  ///
+  /// let client = ClientBuilder::new("token")
+  ///   .dry_run(true)
+  ///   .on_event(|event| println!("{:?}", event))
+  ///   .build_and_auth()
+  ///   .unwrap();
+  ///
+  /// for highlight in client.favorites().unwrap() {
+  ///   client.delete_highlight(highlight.id).unwrap();
+  /// }
+  /// ```
+  pub fn dry_run(mut self, dry_run: bool) -> Self {
+    self.dry_run = dry_run;
+    self
+  }
+
+  /// Drop items that reappear on a later page while fetching every page of a
+  /// list, keeping their first occurrence
+  ///
+  /// Pages are not a consistent snapshot, so a highlight created or deleted
+  /// mid-fetch shifts the items after it, repeating one at the start of the
+  /// next page or skipping one. Deduplication prevents the repeats, though
+  /// not the skips. It applies to the methods that collect every page, such
+  /// as `all_highlights_from`, `all_highlights_parallel` and
+  /// `export_highlights_jsonl`, and tracks the identifiers seen during each
+  /// call.
+  pub fn dedup_pagination(mut self, dedup_pagination: bool) -> Self {
+    self.dedup_pagination = dedup_pagination;
+    self
+  }
+
+  /// Send at most `size` highlights per create request instead of
+  /// `model::MAX_CREATE_BATCH_SIZE`, clamped to between one and that maximum
+  pub fn create_batch_size(mut self, size: usize) -> Self {
+    self.create_batch_size = size.clamp(1, MAX_CREATE_BATCH_SIZE);
+    self
+  }
+
+  /// Stop sending requests once `flag` is set, failing each later request
+  /// with `Error::Cancelled`
+  ///
+  /// The flag is checked before every request, including retries, so a
+  /// long-running operation such as `export` or `all_highlights_from` stops
+  /// at the next page once another thread sets it. Operations that keep
+  /// partial results, such as the `PartialFetch` of `all_highlights_from`
+  /// and the cursor of `export_to_file`, keep them as for any other error.
+  ///
+  /// ```no_run
+  /// use {
+  ///   readwise::client::ClientBuilder,
+  ///   std::sync::{
+  ///     atomic::{AtomicBool, Ordering},
+  ///     Arc,
+  ///   },
+  /// };
+  ///
+  /// let cancelled = Arc::new(AtomicBool::new(false));
+  ///
+  /// let client = ClientBuilder::new("token")
+  ///   .cancel_flag(cancelled.clone())
+  ///   .build()
+  ///   .unwrap();
+  ///
+  /// let export = std::thread::spawn(move || client.export(None, None));
+  ///
+  /// // On shutdown
+  /// cancelled.store(true, Ordering::Relaxed);
+  ///
+  /// export.join().unwrap().ok();
+  /// ```
+  pub fn cancel_flag(mut self, flag: Arc<AtomicBool>) -> Self {
+    self.cancel_flag = Some(flag);
+    self
+  }
+
+  /// Send `user_agent` as the `User-Agent` header of every request instead of
+  /// `readwise/<VERSION>`
+  pub fn user_agent(mut self, user_agent: &str) -> Self {
+    self.settings.user_agent = Some(user_agent.to_string());
+    self
+  }
+
+  /// Send every request through the proxy at `url`
+  pub fn proxy(mut self, url: &str) -> Self {
+    self.settings.proxy = Some(url.to_string());
+    self
+  }
+
+  /// Send the header `name: value` with every request, in addition to the
+  /// access token, which takes precedence over an `Authorization` header set
+  /// here
+  ///
+  /// Headers are checked by `build`, which fails with
+  /// `Error::InvalidHeader` for an invalid name or value.
+  pub fn header(mut self, name: &str, value: &str) -> Self {
+    self
+      .settings
+      .headers
+      .push((name.to_string(), value.to_string()));
+    self
+  }
+
+  /// See `Client::cache_books`
+  pub fn cache_books(mut self, capacity: usize) -> Self {
+    self.book_cache = Some(capacity);
+    self
+  }
+
+  /// See `Client::on_event`
+  pub fn on_event(
+    mut self,
+    handler: impl FnMut(Event) + Send + 'static,
+  ) -> Self {
+    self.on_event = Some(Box::new(handler));
+    self
+  }
+
+  /// Report every request, response and failed request to `observer`, for
+  /// example to record metrics
+  pub fn observer(mut self, observer: Box<dyn RequestObserver>) -> Self {
+    self.observer = Some(observer);
+    self
+  }
+
+  /// Revalidate `GET` responses kept in `store` instead of downloading them
+  /// again, for polling endpoints such as `/books`
+  ///
+  /// Responses carrying an `ETag` or `Last-Modified` header are stored, and
+  /// later requests to the same endpoint send them back as `If-None-Match`
+  /// and `If-Modified-Since`. A `304 Not Modified` answer is then served from
+  /// the stored body. Endpoints that send neither header are unaffected.
+  ///
+  /// ```no_run
+  /// use readwise::{client::ClientBuilder, response_cache::MemoryCacheStore};
+  ///
+  /// let client = ClientBuilder::new("token")
+  ///   .response_cache(Box::new(MemoryCacheStore::new()))
+  ///   .build()
+  ///   .unwrap();
+  /// ```
+  pub fn response_cache(mut self, store: Box<dyn CacheStore>) -> Self {
+    self.response_cache = Some(store);
+    self
+  }
+
+  /// Send requests through `transport` instead of over HTTP, in which case
+  /// the base URL, timeout, user agent and proxy are ignored
+  pub fn transport(mut self, transport: Box<dyn Transport>) -> Self {
+    self.transport = Some(transport);
+    self
+  }
+
+  /// Wait before throttled and retried requests with `sleeper` instead of
+  /// `std::thread::sleep`
+  ///
+  /// ```
+  /// use {
+  ///   readwise::{client::ClientBuilder, sleep::Sleeper},
+  ///   std::time::Duration,
+  /// };
+  ///
+  /// struct NoSleep;
+  ///
+  /// impl Sleeper for NoSleep {
+  ///   fn sleep(&self, _duration: Duration) {}
+  /// }
+  ///
+  /// let builder = ClientBuilder::new("token").sleeper(Box::new(NoSleep));
+  /// ```
+  pub fn sleeper(mut self, sleeper: Box<dyn Sleeper>) -> Self {
+    self.sleeper = sleeper;
+    self
+  }
+
+  /// Build the client without checking the access token against the API
+  ///
+  /// A blank token fails with `Error::MissingToken`.
+  pub fn build(self) -> Result<Client> {
+    let headers = self.settings.default_headers()?;
+    let retry = self.settings.retry_policy();
+    let throttle = self.settings.throttle();
+
+    let transport = match self.transport {
+      Some(transport) => transport,
+      None => Box::new(ReqwestTransport::new(
+        self.settings.blocking_client()?,
+        &self.settings.base_url,
+      )),
+    };
+
+    Ok(Client {
+      access_token: self.settings.access_token,
+      headers,
+      transport: Arc::from(transport),
+      on_event: self.on_event.map(|handler| Arc::new(Mutex::new(handler))),
+      observer: self.observer.map(Arc::from),
+      response_cache: self.response_cache.map(Arc::from),
+      last_response_meta: Arc::new(Mutex::new(None)),
+      rate_limit_status: Arc::new(Mutex::new(None)),
+      book_cache: self
+        .book_cache
+        .map(|capacity| Arc::new(Mutex::new(LruCache::new(capacity)))),
+      retry,
+      throttle,
+      sleeper: Arc::from(self.sleeper),
+      dry_run: self.dry_run,
+      dedup_pagination: self.dedup_pagination,
+      create_batch_size: self.create_batch_size,
+      cancel_flag: self.cancel_flag,
+    })
+  }
+
+  /// Build the client and check the access token against the API
+  pub fn build_and_auth(self) -> Result<Client> {
+    let client = self.build()?;
+    client.verify_token()?;
+    Ok(client)
+  }
+
+  /// Build an `AsyncClient` with the same access token, base URL, timeout,
+  /// user agent, proxy and headers, without checking the token
+  ///
+  /// ```no_run
+  /// use {readwise::client::ClientBuilder, std::time::Duration};
+  ///
+  /// let builder = ClientBuilder::new("token").timeout(Duration::from_secs(30));
+  ///
+  /// let async_client = builder.build_async().unwrap();
+  /// let client = builder.build_and_auth().unwrap();
+  /// ```
+  #[cfg(feature = "async")]
+  pub fn build_async(&self) -> Result<crate::async_client::AsyncClient> {
+    crate::async_client::AsyncClientBuilder::with_settings(
+      self.settings.clone(),
+    )
+    .build()
+  }
+}
+
+/// A blocking Readwise client
+///
+/// Cloning a client is cheap: clones share its connection pool, event
+/// handler, observer, book and response caches, rate limit and last response
+/// metadata, so a client authenticated once can be handed to many worker
+/// threads.
+///
+/// `Client` is `Send + Sync`, which is checked at compile time, so it may
+/// equally be shared behind an `Arc` or borrowed by scoped threads. Every
+/// method takes `&self`; shared state is behind locks, and the transport,
+/// observer and cache store are required to be `Send + Sync` themselves.
+/// The event handler is only required to be `Send`, as calls to it are
+/// serialized.
+///
+/// ```no_run
+/// use {readwise::client::Client, std::thread};
+///
+/// let client = Client::new("token").unwrap();
+///
+/// let workers = (1..=4)
+///   .map(|page| {
+///     let client = client.clone();
+///     thread::spawn(move || client.books(page).unwrap())
+///   })
+///   .collect::<Vec<_>>();
+///
+/// for worker in workers {
+///   println!("{} books", worker.join().unwrap().len());
+/// }
+/// ```
+#[derive(Clone)]
 pub struct Client {
   /// A readwise access token
-  access_token: String,
+  access_token: SecretToken,
+  /// Headers sent with every request, including the access token
+  headers: header::HeaderMap,
+  /// Sends requests to the API
+  transport: Arc<dyn Transport>,
+  /// Receives progress events from long-running operations
+  on_event: Option<Arc<Mutex<EventHandler>>>,
+  /// Observes every request, when registered with `ClientBuilder::observer`
+  observer: Option<Arc<dyn RequestObserver>>,
+  /// Stores responses to revalidate, see `ClientBuilder::response_cache`
+  response_cache: Option<Arc<dyn CacheStore>>,
+  /// Metadata of the most recently received response
+  last_response_meta: Arc<Mutex<Option<ResponseMeta>>>,
+  /// The rate limit state reported by the most recent response to carry one
+  rate_limit_status: Arc<Mutex<Option<RateLimitStatus>>>,
+  /// Books fetched by `book`, when enabled with `Client::cache_books`
+  book_cache: Option<Arc<Mutex<LruCache<u64, Book>>>>,
+  /// How rate limited or failed requests are retried
+  retry: RetryPolicy,
+  /// Spaces requests, when enabled with `ClientBuilder::rate_limit`
+  throttle: Option<Arc<Throttle>>,
+  /// Waits before throttled and retried requests, see
+  /// `ClientBuilder::sleeper`
+  sleeper: Arc<dyn Sleeper>,
+  /// Whether write requests are skipped, see `ClientBuilder::dry_run`
+  dry_run: bool,
+  /// Whether items repeated across pages are dropped, see
+  /// `ClientBuilder::dedup_pagination`
+  dedup_pagination: bool,
+  /// The most highlights sent per create request, see
+  /// `ClientBuilder::create_batch_size`
+  create_batch_size: usize,
+  /// Cancels requests once set, see `ClientBuilder::cancel_flag`
+  cancel_flag: Option<Arc<AtomicBool>>,
+}
+
+impl fmt::Debug for Client {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    f.debug_struct("Client")
+      .field("access_token", &self.access_token)
+      .field("retries", &self.retry.retries)
+      .field("retry_budget", &self.retry.allowance.is_some())
+      .field("rate_limit", &self.throttle.is_some())
+      .field("dry_run", &self.dry_run)
+      .field("dedup_pagination", &self.dedup_pagination)
+      .field("cache_books", &self.book_cache.is_some())
+      .field("on_event", &self.on_event.is_some())
+      .field("observer", &self.observer.is_some())
+      .field("response_cache", &self.response_cache.is_some())
+      .field("cancel_flag", &self.cancel_flag.is_some())
+      .finish_non_exhaustive()
+  }
 }
 
 impl Client {
   /// Create and authenticate a new Readwise client from a specified access token
   ///
+  /// The token is checked against the API, so a bad token fails here rather
+  /// than on the first request. Use `ClientBuilder::build` to skip the check.
+  ///
   /// ```no_run
   /// use readwise::client::Client;
   ///
   /// let client = Client::new("token").unwrap();
   /// ```
   pub fn new(access_token: &str) -> Result<Self> {
-    let url = format!("{}/api/v2{}", &request_url(), "/auth");
+    ClientBuilder::new(access_token).build_and_auth()
+  }
+
+  /// Start configuring a client for the specified access token
+  ///
+  /// ```no_run
+  /// use readwise::client::Client;
+  ///
+  /// let client = Client::builder("token")
+  ///   .user_agent("my-app/1.0")
+  ///   .build_and_auth()
+  ///   .unwrap();
+  /// ```
+  pub fn builder(access_token: &str) -> ClientBuilder {
+    ClientBuilder::new(access_token)
+  }
+
+  /// Create a client that sends its requests through `transport`, without
+  /// checking the access token
+  ///
+  /// ```
+  /// use readwise::{client::Client, testing::FakeTransport};
+  ///
+  /// let client =
+  ///   Client::with_transport("token", Box::new(FakeTransport::new())).unwrap();
+  /// ```
+  pub fn with_transport(
+    access_token: &str,
+    transport: Box<dyn Transport>,
+  ) -> Result<Self> {
+    ClientBuilder::new(access_token)
+      .transport(transport)
+      .build()
+  }
+
+  /// Create and authenticate a new Readwise client from the access token in
+  /// the `READWISE_TOKEN` environment variable
+  ///
+  /// ```no_run
+  /// use readwise::client::Client;
+  ///
+  /// let client = Client::from_env().unwrap();
+  /// ```
+  pub fn from_env() -> Result<Self> {
+    Self::from_env_var(TOKEN_ENV_VAR)
+  }
+
+  /// Create and authenticate a new Readwise client from the access token in
+  /// the specified environment variable
+  ///
+  /// ```no_run
+  /// use readwise::client::Client;
+  ///
+  /// let client = Client::from_env_var("ACCESS_TOKEN").unwrap();
+  /// ```
+  pub fn from_env_var(name: &str) -> Result<Self> {
+    Self::new(
+      &env::var(name).context(error::EnvironmentVariableSnafu { name })?,
+    )
+  }
+
+  /// Register a callback that receives progress events from long-running
+  /// operations such as exports, copies and bulk creates
+  ///
+  /// ```no_run
+  /// use readwise::{client::Client, event::Event};
+  ///
+  /// let client = Client::new("token").unwrap().on_event(|event| {
+  ///   if let Event::PageFetched { page, items } = event {
+  ///     println!("page {}: {} items", page, items);
+  ///   }
+  /// });
+  /// ```
+  pub fn on_event(
+    mut self,
+    handler: impl FnMut(Event) + Send + 'static,
+  ) -> Self {
+    self.on_event = Some(Arc::new(Mutex::new(Box::new(handler))));
+    self
+  }
+
+  /// Retry requests rejected with `429 Too Many Requests` or a server error,
+  /// or that time out, up to `retries` times, waiting as long as the
+  /// `Retry-After` header asks
+  ///
+  /// Without a `Retry-After` header the wait grows exponentially with random
+  /// jitter, as configured by `ClientBuilder::backoff`. `POST` requests are
+  /// only retried when rate limited, since a server error may come after the
+  /// request took effect. Once the retries are exhausted a rate limited
+  /// request fails with `Error::RateLimited`, which carries the last
+  /// requested wait.
+  ///
+  /// ```no_run
+  /// use readwise::client::Client;
+  ///
+  /// let client = Client::new("token").unwrap().retries(3);
+  /// ```
+  pub fn retries(mut self, retries: u32) -> Self {
+    self.retry.retries = retries;
+    self
+  }
+
+  /// Limit the retries spent across every request of this client and its
+  /// clones, on top of the per-request limit of `retries`
+  ///
+  /// Once the budget is spent, requests fail on their first rate limited or
+  /// failed response instead of retrying, which bounds how long a scheduled
+  /// job can spend retrying across thousands of requests. The budget starts
+  /// afresh when set, so setting it on a clone scopes it to that clone, such
+  /// as for a single sync run.
+  ///
+  /// ```no_run
+  /// use {
+  ///   readwise::{client::Client, retry::RetryBudget},
+  ///   std::time::Duration,
+  /// };
+  ///
+  /// let client = Client::new("token").unwrap().retries(5);
+  ///
+  /// let run = client.clone().retry_budget(
+  ///   RetryBudget::new()
+  ///     .retries(50)
+  ///     .backoff(Duration::from_secs(5 * 60)),
+  /// );
+  ///
+  /// let books = run.export(None, None).unwrap();
+  /// ```
+  pub fn retry_budget(mut self, budget: RetryBudget) -> Self {
+    self.retry.allowance = Some(Arc::new(RetryAllowance::new(budget)));
+    self
+  }
+
+  /// Keep up to `capacity` books fetched by `book` in memory, evicting the
+  /// least recently used
+  ///
+  /// Cached books are dropped when a write through this client may have
+  /// changed them: updating the book, or creating, updating or deleting
+  /// highlights.
+  ///
+  /// ```no_run
+  /// use readwise::client::Client;
+  ///
+  /// let client = Client::new("token").unwrap().cache_books(100);
+  ///
+  /// // Only the first lookup reaches the network
+  /// let book = client.book(1).unwrap();
+  /// let book = client.book(1).unwrap();
+  /// ```
+  pub fn cache_books(mut self, capacity: usize) -> Self {
+    self.book_cache = Some(Arc::new(Mutex::new(LruCache::new(capacity))));
+    self
+  }
+
+  /// Check that the access token is still valid, failing with
+  /// `Error::Unauthorized` once it has been revoked
+  ///
+  /// ```no_run
+  /// use readwise::{client::Client, error::Error};
+  ///
+  /// let client = Client::new("token").unwrap();
+  ///
+  /// if let Err(Error::Unauthorized { .. }) = client.verify_token() {
+  ///   println!("please sign in again");
+  /// }
+  /// ```
+  pub fn verify_token(&self) -> Result {
+    self.request("/auth", Method::GET, None)?;
+    Ok(())
+  }
+
+  /// Fetch all books from a specified page
+  ///
+  /// ```
+  /// # let _m = mockito::mock("GET", "/api/v2/books?page=1")
+  /// #   .with_body(
+  /// #     r#"{ "count": 1, "next": null, "previous": null, "results": [{
+  /// #       "id": 1, "title": "Meditations", "author": "Marcus Aurelius",
+  /// #       "category": "books", "num_highlights": 3,
+  /// #       "last_highlight_at": null, "updated": "", "cover_image_url": "",
+  /// #       "highlights_url": "", "source_url": null
+  /// #     }] }"#,
+  /// #   )
+  /// #   .create();
+  /// # let client = readwise::client::Client::builder("token")
+  /// #   .base_url(&mockito::server_url())
+  /// #   .build()
+  /// #   .unwrap();
+  /// let books = client.books(1).unwrap();
+  ///
+  /// assert_eq!(books[0].to_string(), "Meditations by Marcus Aurelius");
+  /// ```
+  pub fn books(&self, page: u64) -> Result<Vec<Book>> {
+    Ok(
+      self
+        .get::<BooksResponse>(&BookFilter::new().page(page).endpoint())?
+        .results,
+    )
+  }
+
+  /// Fetch all highlights from a specified page
+  ///
+  /// ```no_run
+  /// use readwise::client::Client;
+  ///
+  /// let client = Client::new("token").unwrap();
+  /// let highlights = client.highlights(1).unwrap();
+  /// ```
+  pub fn highlights(&self, page: u64) -> Result<Vec<Highlight>> {
+    Ok(
+      self
+        .get::<HighlightsResponse>(
+          &HighlightFilter::new().page(page).endpoint(),
+        )?
+        .results,
+    )
+  }
+
+  /// Fetch a page of books matching a filter, along with the total count and
+  /// pagination links
+  ///
+  /// ```no_run
+  /// use readwise::{client::Client, filter::BookFilter, model::Category};
+  ///
+  /// let client = Client::new("token").unwrap();
+  ///
+  /// let response = client
+  ///   .books_with(&BookFilter::new().category(Category::Articles))
+  ///   .unwrap();
+  ///
+  /// println!("{} articles", response.count);
+  /// ```
+  pub fn books_with(&self, filter: &BookFilter) -> Result<BooksResponse> {
+    self.get(&filter.endpoint())
+  }
+
+  /// Fetch a page of highlights matching a filter, along with the total count
+  /// and pagination links
+  ///
+  /// ```no_run
+  /// use readwise::{client::Client, filter::HighlightFilter};
+  ///
+  /// let client = Client::new("token").unwrap();
+  ///
+  /// let response = client
+  ///   .highlights_with(&HighlightFilter::new().book_id(1))
+  ///   .unwrap();
+  ///
+  /// println!("{} highlights", response.count);
+  /// ```
+  pub fn highlights_with(
+    &self,
+    filter: &HighlightFilter,
+  ) -> Result<HighlightsResponse> {
+    let mut response = self.get::<HighlightsResponse>(&filter.endpoint())?;
+
+    response
+      .results
+      .retain(|highlight| filter.matches(highlight));
+
+    Ok(response)
+  }
+
+  /// Fetch the page of highlights at `url`, the `next` or `previous` link of
+  /// an earlier page, for resuming pagination from a stored link
+  ///
+  /// Unlike a page number, a stored link keeps the page size and filters of
+  /// the page it came from. A URL that is not a highlights list URL on
+  /// readwise.io fails with `Error::InvalidPageUrl` before any request is
+  /// sent, so the access token is never sent elsewhere.
+  ///
+  /// ```no_run
+  /// use readwise::client::Client;
+  ///
+  /// let client = Client::new("token").unwrap();
+  ///
+  /// let mut page = client
+  ///   .highlights_from_url("https://readwise.io/api/v2/highlights/?page=2")
+  ///   .unwrap();
+  ///
+  /// while let Some(next) = page.next {
+  ///   page = client.highlights_from_url(&next).unwrap();
+  /// }
+  /// ```
+  pub fn highlights_from_url(&self, url: &str) -> Result<HighlightsResponse> {
+    self.get(&page_endpoint(url, "/highlights")?)
+  }
+
+  /// Fetch every favorited highlight, following pagination
+  ///
+  /// ```no_run
+  /// use readwise::client::Client;
+  ///
+  /// let client = Client::new("token").unwrap();
+  ///
+  /// for highlight in client.favorites().unwrap() {
+  ///   println!("{}", highlight);
+  /// }
+  /// ```
+  pub fn favorites(&self) -> Result<Vec<Highlight>> {
+    let mut highlights = self.collect_highlights(None)?;
+    highlights.retain(|highlight| highlight.is_favorite);
+    Ok(highlights)
+  }
+
+  /// Fetch a single book by identifier
+  ///
+  /// ```
+  /// # let _m = mockito::mock("GET", "/api/v2/books/1")
+  /// #   .with_body(
+  /// #     r#"{
+  /// #       "id": 1, "title": "Meditations", "author": "",
+  /// #       "category": "books", "num_highlights": 3,
+  /// #       "last_highlight_at": null, "updated": "", "cover_image_url": "",
+  /// #       "highlights_url": "https://readwise.io/bookreview/1",
+  /// #       "source_url": null
+  /// #     }"#,
+  /// #   )
+  /// #   .create();
+  /// # let client = readwise::client::Client::builder("token")
+  /// #   .base_url(&mockito::server_url())
+  /// #   .build()
+  /// #   .unwrap();
+  /// let book = client.book(1).unwrap();
+  ///
+  /// assert_eq!(book.author, None);
+  /// assert_eq!(book.readwise_url(), "https://readwise.io/bookreview/1");
+  /// ```
+  pub fn book(&self, id: u64) -> Result<Book> {
+    if let Some(book) = self.with_book_cache(|cache| cache.get(&id)).flatten() {
+      return Ok(book);
+    }
+
+    self.refresh_book(id)
+  }
+
+  /// Fetch a single book by identifier from the API, bypassing and then
+  /// updating the book cache
+  ///
+  /// A `Book` is a snapshot: its `num_highlights` and `last_highlighted_at`
+  /// are not updated when highlights are later created or deleted, so a
+  /// book fetched before such a write shows stale counts. Fetch it again
+  /// with this method to get fresh ones.
+  ///
+  /// ```no_run
+  /// use {readwise::client::Client, std::collections::HashMap};
+  ///
+  /// let client = Client::new("token").unwrap();
+  ///
+  /// let book = client.book(1).unwrap();
+  ///
+  /// client
+  ///   .create_highlights(vec![HashMap::from([
+  ///     ("text", "hello world!"),
+  ///     ("title", book.title.as_str()),
+  ///   ])])
+  ///   .unwrap();
+  ///
+  /// let book = client.refresh_book(1).unwrap();
+  ///
+  /// println!("{} highlights", book.num_highlights);
+  /// ```
+  pub fn refresh_book(&self, id: u64) -> Result<Book> {
+    let book = self.get::<Book>(&format!("/books/{}", id))?;
+
+    self.with_book_cache(|cache| cache.insert(id, book.clone()));
+
+    Ok(book)
+  }
+
+  /// Fetch a single highlight by identifier
+  ///
+  /// ```no_run
+  /// use readwise::client::Client;
+  ///
+  /// let client = Client::new("token").unwrap();
+  /// let highlight = client.highlight(1).unwrap();
+  /// ```
+  pub fn highlight(&self, id: u64) -> Result<Highlight> {
+    self.get(&format!("/highlights/{}", id))
+  }
+
+  /// Fetch `n` random highlights, optionally restricted to a single book
+  ///
+  /// Only the pages containing the sampled highlights are fetched, so this
+  /// issues roughly `n + 1` requests regardless of library size. Passing a
+  /// `seed` makes the selection deterministic.
+  ///
+  /// ```no_run
+  /// use readwise::client::Client;
+  ///
+  /// let client = Client::new("token").unwrap();
+  ///
+  /// for highlight in client.random_highlights(3, None, None).unwrap() {
+  ///   println!("{}", highlight.text);
+  /// }
+  /// ```
+  pub fn random_highlights(
+    &self,
+    n: usize,
+    book_id: Option<u64>,
+    seed: Option<u64>,
+  ) -> Result<Vec<Highlight>> {
+    let mut rng = match seed {
+      Some(seed) => StdRng::seed_from_u64(seed),
+      None => StdRng::from_entropy(),
+    };
+
+    let mut count = self.highlights_page(1, 1, book_id)?.count;
+
+    let mut highlights = Vec::new();
+    let mut ids = HashSet::new();
+    let mut sampled = HashSet::new();
+    let mut misses = 0;
+
+    while highlights.len() < n
+      && (sampled.len() as u64) < count
+      && misses <= RANDOM_HIGHLIGHT_RETRIES
+    {
+      let index = rng.gen_range(0..count);
+
+      if !sampled.insert(index) {
+        continue;
+      }
+
+      let highlight = match self.highlights_page(index + 1, 1, book_id) {
+        Ok(response) => {
+          count = response.count;
+          response.results.into_iter().next()
+        }
+        Err(Error::BadRequest { status, .. })
+          if status == StatusCode::NOT_FOUND =>
+        {
+          None
+        }
+        Err(error) => return Err(error),
+      };
+
+      match highlight {
+        Some(highlight) if ids.insert(highlight.id) => {
+          highlights.push(highlight)
+        }
+        _ => misses += 1,
+      }
+    }
+
+    Ok(highlights)
+  }
+
+  /// Create and return one or more highlights
+  ///
+  /// Highlights are sent in batches of at most
+  /// `model::MAX_CREATE_BATCH_SIZE`, one request after another, so a create of
+  /// any size stays under the API's limit. If a batch fails, those before it
+  /// have already been created. A highlight whose `category` is not one of
+  /// `model::Category::CREATABLE` fails with `Error::UnknownValue` before any
+  /// request is sent.
+  ///
+  /// Each returned highlight has its `highlight_url` set, linking to it on
+  /// readwise.io; `CreateReport::highlight_urls` gives the same links without
+  /// fetching the highlights.
+  ///
+  /// Highlights that Readwise matches to existing ones are not returned; use
+  /// `create_highlights_with` to find out how many there were. Books fetched
+  /// earlier still report their old `num_highlights` afterwards; see
+  /// `refresh_book`.
+  ///
+  /// ```no_run
+  /// use {
+  ///   std::collections::HashMap,
+  ///   readwise::client::Client
+  /// };
+  ///
+  /// let client = Client::new("token").unwrap();
+  ///
+  /// let mut new_highlight = HashMap::new();
+  ///
+  /// new_highlight.insert("text", "hello world!");
+  ///
+  /// for highlight in client.create_highlights(vec![new_highlight]).unwrap() {
+  ///   println!("{}", highlight.text);
+  /// }
+  /// ```
+  pub fn create_highlights<T: Serialize>(
+    &self,
+    highlights: Vec<T>,
+  ) -> Result<Vec<Highlight>> {
+    let identifiers = self.create_highlight_ids(highlights)?;
+
+    identifiers
+      .iter()
+      .for_each(|id| self.emit(Event::HighlightCreated { id: *id }));
+
+    self.emit(Event::Done {
+      total: identifiers.len() as u64,
+    });
+
+    identifiers
+      .iter()
+      .map(|identifier| {
+        let mut highlight = self.highlight(*identifier)?;
+        highlight.highlight_url = Some(highlight.readwise_url());
+        Ok(highlight)
+      })
+      .collect()
+  }
+
+  /// Create highlights according to `options`, reporting which were created
+  /// and how many were skipped
+  ///
+  /// With `skip_existing`, the existing highlights of each target book
+  /// (matched by title, and author when given) are fetched first and any new
+  /// highlight whose normalized text is already present is left out, which
+  /// makes retrying a failed import safe. Highlights without a title target
+  /// Readwise's default "Quotes" book.
+  ///
+  /// ```no_run
+  /// use readwise::{
+  ///   client::Client,
+  ///   model::{CreateOptions, HighlightCreate},
+  /// };
+  ///
+  /// let client = Client::new("token").unwrap();
+  ///
+  /// let report = client
+  ///   .create_highlights_with(
+  ///     vec![HighlightCreate {
+  ///       text: "hello world!".into(),
+  ///       ..Default::default()
+  ///     }],
+  ///     CreateOptions { skip_existing: true },
+  ///   )
+  ///   .unwrap();
+  ///
+  /// println!("{} created, {} skipped", report.created.len(), report.skipped);
+  /// ```
+  pub fn create_highlights_with(
+    &self,
+    highlights: Vec<HighlightCreate>,
+    options: CreateOptions,
+  ) -> Result<CreateReport> {
+    let total = highlights.len();
+
+    let highlights = match options.skip_existing {
+      true => self.without_existing(highlights)?,
+      false => highlights,
+    };
+
+    let skipped = total - highlights.len();
+
+    let sent = highlights.len();
+
+    let created = match highlights.is_empty() {
+      true => Vec::new(),
+      false => self.create_highlight_ids(highlights)?,
+    };
+
+    created
+      .iter()
+      .for_each(|id| self.emit(Event::HighlightCreated { id: *id }));
+
+    self.emit(Event::Done {
+      total: created.len() as u64,
+    });
+
+    Ok(CreateReport {
+      existing: sent.saturating_sub(created.len()),
+      created,
+      skipped,
+    })
+  }
+
+  /// Copy every highlight of a book on this account to another account
+  ///
+  /// The copies keep the book's title and author so that they are grouped
+  /// under the same book on the other account. Returns the identifiers of the
+  /// highlights created on `other`.
+  ///
+  /// ```no_run
+  /// use readwise::client::Client;
+  ///
+  /// let work = Client::new("work token").unwrap();
+  /// let personal = Client::new("personal token").unwrap();
+  ///
+  /// work.copy_highlights_to(&personal, 1).unwrap();
+  /// ```
+  pub fn copy_highlights_to(
+    &self,
+    other: &Client,
+    book_id: u64,
+  ) -> Result<Vec<u64>> {
+    let book = self.book(book_id)?;
+
+    let highlights = self
+      .collect_highlights(Some(book_id))?
+      .iter()
+      .map(|highlight| HighlightCreate {
+        title: Some(book.title.clone()),
+        author: book.author.clone(),
+        ..HighlightCreate::from(highlight)
+      })
+      .collect::<Vec<HighlightCreate>>();
+
+    let identifiers = match highlights.is_empty() {
+      true => Vec::new(),
+      false => other.create_highlight_ids(highlights)?,
+    };
+
+    identifiers
+      .iter()
+      .for_each(|id| self.emit(Event::HighlightCreated { id: *id }));
+
+    self.emit(Event::Done {
+      total: identifiers.len() as u64,
+    });
+
+    Ok(identifiers)
+  }
+
+  /// Update a single highlight by identifier
+  ///
+  /// When the response has an empty body, such as a `204 No Content`, the
+  /// updated highlight is fetched instead.
+  ///
+  /// ```no_run
+  /// use readwise::{client::Client, model::HighlightUpdate};
+  ///
+  /// let client = Client::new("token").unwrap();
+  ///
+  /// client
+  ///   .update_highlight(
+  ///     1,
+  ///     HighlightUpdate {
+  ///       text: Some("hello, world!".into()),
+  ///       ..Default::default()
+  ///     },
+  ///   )
+  ///   .unwrap();
+  /// ```
+  pub fn update_highlight(
+    &self,
+    id: u64,
+    update: HighlightUpdate,
+  ) -> Result<Highlight> {
+    self.with_book_cache(LruCache::clear);
+
+    match self.send_optional(
+      Method::PATCH,
+      &format!("/highlights/{}", id),
+      Some(serde_json::to_value(update)?),
+    )? {
+      Some(highlight) => Ok(highlight),
+      None => self.highlight(id),
+    }
+  }
+
+  /// Recolor a single highlight by identifier, leaving its other fields
+  /// unchanged
+  ///
+  /// ```no_run
+  /// use readwise::{client::Client, model::Color};
+  ///
+  /// let client = Client::new("token").unwrap();
+  ///
+  /// client.set_highlight_color(1, Color::Blue).unwrap();
+  /// ```
+  pub fn set_highlight_color(
+    &self,
+    id: u64,
+    color: Color,
+  ) -> Result<Highlight> {
+    self.update_highlight(
+      id,
+      HighlightUpdate {
+        color: Some(color),
+        ..Default::default()
+      },
+    )
+  }
+
+  /// Set a highlight's note and reconcile its tags with `tags`, adding the
+  /// missing ones and removing the others
+  ///
+  /// Tags live on a separate endpoint from the note, so this takes several
+  /// requests. The note is updated first; if that fails, nothing has changed
+  /// and the error is returned. Tags that then fail to be added or removed
+  /// are reported in `HighlightUpdateReport::failed` without stopping the
+  /// remaining ones, so saving again retries only what is left.
+  ///
+  /// ```no_run
+  /// use readwise::client::Client;
+  ///
+  /// let client = Client::new("token").unwrap();
+  ///
+  /// let report = client
+  ///   .update_highlight_full(1, "revisit", &["philosophy", "to-review"])
+  ///   .unwrap();
+  ///
+  /// for (tag, error) in report.failed {
+  ///   println!("tag {} was not saved: {}", tag, error);
+  /// }
+  /// ```
+  pub fn update_highlight_full(
+    &self,
+    id: u64,
+    note: &str,
+    tags: &[&str],
+  ) -> Result<HighlightUpdateReport> {
+    let mut highlight = self.update_highlight(
+      id,
+      HighlightUpdate {
+        note: Some(note.to_string()),
+        ..Default::default()
+      },
+    )?;
+
+    let mut report = HighlightUpdateReport::default();
+
+    for tag in &highlight.tags {
+      if tags.contains(&tag.name.as_str()) {
+        continue;
+      }
+
+      match self.delete_highlight_tag(id, tag.id) {
+        Ok(_) => report.removed.push(tag.clone()),
+        Err(error) => report.failed.push((tag.name.clone(), error)),
+      }
+    }
+
+    let mut requested = HashSet::new();
+
+    for name in tags {
+      if !requested.insert(*name)
+        || highlight.tags.iter().any(|tag| tag.name == *name)
+      {
+        continue;
+      }
+
+      match self.create_highlight_tag(id, name) {
+        Ok(tag) => report.added.push(tag),
+        Err(error) => report.failed.push((name.to_string(), error)),
+      }
+    }
+
+    highlight
+      .tags
+      .retain(|tag| !report.removed.iter().any(|removed| removed.id == tag.id));
+
+    highlight.tags.extend(report.added.iter().cloned());
+
+    report.highlight = highlight;
+
+    Ok(report)
+  }
+
+  /// Update a single book's metadata by identifier
+  ///
+  /// Not every field is editable for every source; when Readwise rejects an
+  /// update its message is returned as `Error::Api`. When the response has an
+  /// empty body, the updated book is fetched instead.
+  ///
+  /// ```no_run
+  /// use readwise::{client::Client, model::BookUpdate};
+  ///
+  /// let client = Client::new("token").unwrap();
+  ///
+  /// client
+  ///   .update_book(
+  ///     1,
+  ///     BookUpdate {
+  ///       author: Some("John Smith".into()),
+  ///       ..Default::default()
+  ///     },
+  ///   )
+  ///   .unwrap();
+  /// ```
+  pub fn update_book(&self, id: u64, update: BookUpdate) -> Result<Book> {
+    self.with_book_cache(|cache| cache.remove(&id));
+
+    match self.send_optional(
+      Method::PATCH,
+      &format!("/books/{}", id),
+      Some(serde_json::to_value(update)?),
+    )? {
+      Some(book) => Ok(book),
+      None => self.book(id),
+    }
+  }
+
+  /// Delete a single highlight by identifier
+  ///
+  /// A highlight that does not exist is reported as `DeleteOutcome::NotFound`
+  /// rather than an error, which makes repeated deletes safe.
+  ///
+  /// ```
+  /// use readwise::model::DeleteOutcome;
+  ///
+  /// # let _m = mockito::mock("DELETE", "/api/v2/highlights/1")
+  /// #   .with_status(404)
+  /// #   .create();
+  /// # let client = readwise::client::Client::builder("token")
+  /// #   .base_url(&mockito::server_url())
+  /// #   .build()
+  /// #   .unwrap();
+  /// assert_eq!(client.delete_highlight(1).unwrap(), DeleteOutcome::NotFound);
+  /// ```
+  pub fn delete_highlight(&self, id: u64) -> Result<DeleteOutcome> {
+    self.with_book_cache(LruCache::clear);
+
+    self.delete_path(&format!("/highlights/{}", id))
+  }
+
+  /// Delete every highlight of a book, returning how many were deleted
+  ///
+  /// The API has no endpoint for deleting a book itself, so this is the way
+  /// to clear one out. All highlights are listed before any is deleted, so
+  /// pagination is unaffected by the deletions, and highlights that are
+  /// already gone are skipped. The first failed deletion is returned.
+  ///
+  /// ```no_run
+  /// use readwise::client::Client;
+  ///
+  /// let client = Client::new("token").unwrap();
+  ///
+  /// let deleted = client.delete_book_highlights(1).unwrap();
+  ///
+  /// println!("deleted {} highlights", deleted);
+  /// ```
+  pub fn delete_book_highlights(&self, id: u64) -> Result<u64> {
+    let mut deleted = 0;
+
+    for highlight in self.collect_highlights(Some(id))? {
+      if self.delete_highlight(highlight.id)? == DeleteOutcome::Deleted {
+        deleted += 1;
+      }
+    }
+
+    self.emit(Event::Done { total: deleted });
+
+    Ok(deleted)
+  }
+
+  /// Add a tag to a single highlight
+  ///
+  /// ```no_run
+  /// use readwise::client::Client;
+  ///
+  /// let client = Client::new("token").unwrap();
+  /// let tag = client.create_highlight_tag(1, "favorite").unwrap();
+  /// ```
+  pub fn create_highlight_tag(&self, id: u64, name: &str) -> Result<Tag> {
+    self.send(
+      Method::POST,
+      &format!("/highlights/{}/tags", id),
+      Some(serde_json::json!({ "name": name })),
+    )
+  }
+
+  /// Remove a tag from a single highlight, reporting a tag that does not
+  /// exist as `DeleteOutcome::NotFound`
+  ///
+  /// ```no_run
+  /// use readwise::client::Client;
+  ///
+  /// let client = Client::new("token").unwrap();
+  /// client.delete_highlight_tag(1, 2).unwrap();
+  /// ```
+  pub fn delete_highlight_tag(
+    &self,
+    id: u64,
+    tag_id: u64,
+  ) -> Result<DeleteOutcome> {
+    self.delete_path(&format!("/highlights/{}/tags/{}", id, tag_id))
+  }
+
+  /// Add the same tag to many highlights, continuing past failures
+  ///
+  /// ```no_run
+  /// use readwise::client::Client;
+  ///
+  /// let client = Client::new("token").unwrap();
+  ///
+  /// let report = client.tag_highlights(&[1, 2, 3], "project");
+  ///
+  /// for (id, error) in report.failed {
+  ///   println!("highlight {} was not tagged: {}", id, error);
+  /// }
+  /// ```
+  pub fn tag_highlights(&self, ids: &[u64], tag: &str) -> TagReport {
+    let mut report = TagReport::default();
+
+    for id in ids {
+      match self.create_highlight_tag(*id, tag) {
+        Ok(tag) => report.tagged.push((*id, tag)),
+        Err(error) => report.failed.push((*id, error)),
+      }
+    }
+
+    self.emit(Event::Done {
+      total: report.tagged.len() as u64,
+    });
+
+    report
+  }
+
+  /// Fetch every book and its highlights from the export endpoint
+  ///
+  /// `updated_after` is an ISO 8601 timestamp restricting the export to
+  /// highlights updated after it, and `category` restricts it to books of a
+  /// single category. Together they allow incremental sync of one category.
+  ///
+  /// ```no_run
+  /// use readwise::{client::Client, model::Category};
+  ///
+  /// let client = Client::new("token").unwrap();
+  ///
+  /// for book in client
+  ///   .export(Some("2022-10-01T00:00:00Z"), Some(Category::Articles))
+  ///   .unwrap()
+  /// {
+  ///   println!("{}: {} highlights", book.title, book.highlights.len());
+  /// }
+  /// ```
+  pub fn export(
+    &self,
+    updated_after: Option<&str>,
+    category: Option<Category>,
+  ) -> Result<Vec<ExportBook>> {
+    let mut books = Vec::new();
+
+    let mut cursor = None;
+
+    for page in 1.. {
+      let response = self.export_page(updated_after, category, cursor)?;
+
+      self.emit(Event::PageFetched {
+        page,
+        items: response.results.len() as u64,
+      });
+
+      books.extend(response.results);
+
+      cursor = match response.next_page_cursor {
+        Some(next) => Some(next),
+        None => break,
+      };
+    }
+
+    Ok(books)
+  }
+
+  /// Fetch every book with only its highlights highlighted strictly between
+  /// `after` and `before`, exporting the whole library to filter them
+  ///
+  /// ```no_run
+  /// use readwise::client::Client;
+  ///
+  /// let client = Client::new("token").unwrap();
+  ///
+  /// let quarter = client
+  ///   .export_highlighted(Some("2022-07-01"), Some("2022-10-01"))
+  ///   .unwrap();
+  /// ```
+  pub fn export_highlighted(
+    &self,
+    after: Option<&str>,
+    before: Option<&str>,
+  ) -> Result<Vec<ExportBook>> {
+    let (after, before) = (
+      after.map(timestamp_bound).transpose()?,
+      before.map(timestamp_bound).transpose()?,
+    );
+
+    let mut books = self.export(None, None)?;
+
+    for book in &mut books {
+      book.highlights.retain(|highlight| {
+        highlight
+          .highlighted_at
+          .as_deref()
+          .and_then(parse_timestamp)
+          .is_some_and(|at| {
+            after.is_none_or(|after| at > after)
+              && before.is_none_or(|before| at < before)
+          })
+      });
+    }
+
+    books.retain(|book| !book.highlights.is_empty());
+
+    Ok(books)
+  }
+
+  /// Fetch a page of the highlights of a single book
+  ///
+  /// A book without highlights, or one that does not exist, yields an empty
+  /// page.
+  ///
+  /// ```no_run
+  /// use readwise::client::Client;
+  ///
+  /// let client = Client::new("token").unwrap();
+  /// let highlights = client.book_highlights(1, 1).unwrap();
+  /// ```
+  pub fn book_highlights(
+    &self,
+    book_id: u64,
+    page: u64,
+  ) -> Result<HighlightsResponse> {
+    self.highlights_with(&HighlightFilter::new().book_id(book_id).page(page))
+  }
+
+  /// Fetch every highlight of a single book, following pagination
+  ///
+  /// A book without highlights, or one that does not exist, yields an empty
+  /// vector.
+  ///
+  /// ```no_run
+  /// use readwise::client::Client;
+  ///
+  /// let client = Client::new("token").unwrap();
+  ///
+  /// for highlight in client.all_book_highlights(1).unwrap() {
+  ///   println!("{}", highlight);
+  /// }
+  /// ```
+  pub fn all_book_highlights(&self, book_id: u64) -> Result<Vec<Highlight>> {
+    self.collect_highlights(Some(book_id))
+  }
+
+  /// Fetch every highlight of several books, fetching up to `concurrency`
+  /// books at once
+  ///
+  /// The API filters on a single `book_id`, so each book is fetched
+  /// separately, following pagination. Highlights are returned in the order
+  /// of `book_ids`, each book's in the order the API lists them, and a
+  /// highlight or book that appears more than once is only included the first
+  /// time. The first failed book is returned.
+  ///
+  /// ```no_run
+  /// use readwise::client::Client;
+  ///
+  /// let client = Client::new("token").unwrap();
+  ///
+  /// for highlight in client.highlights_for_books(&[1, 2, 3], 4).unwrap() {
+  ///   println!("{}", highlight);
+  /// }
+  /// ```
+  pub fn highlights_for_books(
+    &self,
+    book_ids: &[u64],
+    concurrency: usize,
+  ) -> Result<Vec<Highlight>> {
+    let mut seen = HashSet::new();
+
+    let book_ids = book_ids
+      .iter()
+      .copied()
+      .filter(|id| seen.insert(*id))
+      .collect::<Vec<u64>>();
+
+    let books =
+      self.in_parallel(concurrency, book_ids.len() as u64, |index| {
+        self.collect_highlights(Some(book_ids[index as usize]))
+      })?;
+
+    let mut seen = HashSet::new();
+
+    Ok(
+      books
+        .into_iter()
+        .flatten()
+        .filter(|highlight| seen.insert(highlight.id))
+        .collect(),
+    )
+  }
+
+  /// Fetch a book along with all of its highlights, following pagination
+  ///
+  /// ```no_run
+  /// use readwise::client::Client;
+  ///
+  /// let client = Client::new("token").unwrap();
+  ///
+  /// let (book, highlights) = client.book_with_highlights(1).unwrap();
+  ///
+  /// println!("{}: {} highlights", book.title, highlights.len());
+  /// ```
+  pub fn book_with_highlights(
+    &self,
+    id: u64,
+  ) -> Result<(Book, Vec<Highlight>)> {
+    Ok((self.book(id)?, self.collect_highlights(Some(id))?))
+  }
+
+  /// Fetch every book with its highlights, keyed by book identifier, for
+  /// rendering highlights by book
+  ///
+  /// Built on the export endpoint, which already nests highlights under their
+  /// books, so no book titles need resolving; see `ExportBook::split` for how
+  /// the books are filled in.
+  ///
+  /// ```no_run
+  /// use readwise::client::Client;
+  ///
+  /// let client = Client::new("token").unwrap();
+  ///
+  /// for (book, highlights) in client.highlights_grouped_by_book().unwrap().values() {
+  ///   println!("{}: {} highlights", book.title, highlights.len());
+  /// }
+  /// ```
+  pub fn highlights_grouped_by_book(
+    &self,
+  ) -> Result<HashMap<u64, (Book, Vec<Highlight>)>> {
+    Ok(
+      self
+        .export(None, None)?
+        .into_iter()
+        .map(|book| {
+          let (book, highlights) = book.split();
+          (book.id, (book, highlights))
+        })
+        .collect(),
+    )
+  }
+
+  /// Fetch every highlight of a book in reading order, as defined by
+  /// `Highlight::sort_key`
+  ///
+  /// ```no_run
+  /// use readwise::client::Client;
+  ///
+  /// let client = Client::new("token").unwrap();
+  ///
+  /// for highlight in client.book_highlights_ordered(1).unwrap() {
+  ///   println!("{}", highlight);
+  /// }
+  /// ```
+  pub fn book_highlights_ordered(
+    &self,
+    book_id: u64,
+  ) -> Result<Vec<Highlight>> {
+    let mut highlights = self.collect_highlights(Some(book_id))?;
+    sort_highlights(&mut highlights);
+    Ok(highlights)
+  }
+
+  /// Fetch every book, requesting up to `concurrency` pages at once
+  ///
+  /// The first page is fetched alone to learn how many pages there are.
+  /// `concurrency` is capped at 4 to stay under the rate limit, and books are
+  /// returned in page order. The first failed request stops the remaining
+  /// pages from being requested and is returned.
+  ///
+  /// ```no_run
+  /// use readwise::client::Client;
+  ///
+  /// let client = Client::new("token").unwrap();
+  /// let books = client.all_books_parallel(4).unwrap();
+  /// ```
+  pub fn all_books_parallel(&self, concurrency: usize) -> Result<Vec<Book>> {
+    self.collect_parallel(concurrency, |page| {
+      let response = self.books_page(page, MAX_PAGE_SIZE)?;
+      Ok((response.results, response.count))
+    })
+  }
+
+  /// Fetch every highlight, requesting up to `concurrency` pages at once
+  ///
+  /// See `all_books_parallel` for how pages are fetched.
+  ///
+  /// ```no_run
+  /// use readwise::client::Client;
+  ///
+  /// let client = Client::new("token").unwrap();
+  /// let highlights = client.all_highlights_parallel(4).unwrap();
+  /// ```
+  pub fn all_highlights_parallel(
+    &self,
+    concurrency: usize,
+  ) -> Result<Vec<Highlight>> {
+    self.collect_parallel(concurrency, |page| {
+      let response = self.highlights_page(page, MAX_PAGE_SIZE, None)?;
+      Ok((response.results, response.count))
+    })
+  }
+
+  /// Fetch every book on the pages from `page` onwards, keeping the books
+  /// already fetched if a page fails
+  ///
+  /// See `all_highlights_from` for how to resume.
+  ///
+  /// ```no_run
+  /// use readwise::client::Client;
+  ///
+  /// let client = Client::new("token").unwrap();
+  /// let books = client.all_books_from(1).unwrap();
+  /// ```
+  pub fn all_books_from(
+    &self,
+    page: u64,
+  ) -> Result<Vec<Book>, PartialFetch<Book>> {
+    self.collect_pages(page, |page| self.books_page(page, MAX_PAGE_SIZE))
+  }
+
+  /// Fetch every highlight on the pages from `page` onwards, keeping the
+  /// highlights already fetched if a page fails
+  ///
+  /// On failure the `PartialFetch` holds the highlights fetched so far and
+  /// the page to resume from, so a long sync over a flaky connection need
+  /// not start over:
+  ///
+  /// ```no_run
+  /// use readwise::client::Client;
+  ///
+  /// let client = Client::new("token").unwrap();
+  ///
+  /// let mut highlights = Vec::new();
+  /// let mut page = 1;
+  ///
+  /// loop {
+  ///   match client.all_highlights_from(page) {
+  ///     Ok(rest) => {
+  ///       highlights.extend(rest);
+  ///       break;
+  ///     }
+  ///     Err(partial) => {
+  ///       eprintln!("{}, resuming from page {}", partial.error, partial.next_page);
+  ///       highlights.extend(partial.items);
+  ///       page = partial.next_page;
+  ///     }
+  ///   }
+  /// }
+  /// ```
+  ///
+  /// Pages hold 1000 highlights each, and their contents shift when
+  /// highlights are added or deleted during the sync.
+  pub fn all_highlights_from(
+    &self,
+    page: u64,
+  ) -> Result<Vec<Highlight>, PartialFetch<Highlight>> {
+    self.collect_pages(page, |page| {
+      self.highlights_page(page, MAX_PAGE_SIZE, None)
+    })
+  }
+
+  /// Iterate over every page of highlights, fetching each one lazily as the
+  /// previous one is consumed
+  ///
+  /// Each page keeps its `count` and `next` link, and its boundary makes a
+  /// natural batch for a transactional write or a checkpoint. The iterator
+  /// ends after the last page, or after yielding the first error.
+  ///
+  /// ```no_run
+  /// use readwise::client::Client;
+  ///
+  /// let client = Client::new("token").unwrap();
+  ///
+  /// for page in client.highlight_pages() {
+  ///   let page = page.unwrap();
+  ///
+  ///   println!("{} of {} highlights", page.results.len(), page.count);
+  ///
+  ///   if let Some(next) = &page.next {
+  ///     println!("checkpoint: {}", next);
+  ///   }
+  /// }
+  /// ```
+  pub fn highlight_pages(
+    &self,
+  ) -> impl Iterator<Item = Result<HighlightsResponse>> + '_ {
+    let mut next = Some(1);
+
+    std::iter::from_fn(move || {
+      let page = next.take()?;
+
+      let response = self.highlights_page(page, MAX_PAGE_SIZE, None);
+
+      if let Ok(response) = &response {
+        self.emit(Event::PageFetched {
+          page,
+          items: response.results.len() as u64,
+        });
+
+        next = response.next.as_ref().map(|_| page + 1);
+      }
+
+      Some(response)
+    })
+  }
+
+  /// Fetch the highlights with an identifier greater than `last_id`, newest
+  /// first, for polling a feed of new highlights
+  ///
+  /// Pages are fetched in the list endpoint's newest first order, stopping at
+  /// the first page without a new highlight.
+  ///
+  /// ```no_run
+  /// use readwise::client::Client;
+  ///
+  /// let client = Client::new("token").unwrap();
+  ///
+  /// let mut last_id = 0;
+  ///
+  /// for highlight in client.highlights_since_id(last_id).unwrap().iter().rev() {
+  ///   println!("{}", highlight);
+  ///   last_id = highlight.id;
+  /// }
+  /// ```
+  pub fn highlights_since_id(&self, last_id: u64) -> Result<Vec<Highlight>> {
+    let mut highlights = Vec::new();
+
+    for page in 1.. {
+      let response = self.highlights_page(page, MAX_PAGE_SIZE, None)?;
+
+      let before = highlights.len();
+
+      highlights.extend(
+        response
+          .results
+          .into_iter()
+          .filter(|highlight| highlight.id > last_id),
+      );
+
+      if highlights.len() == before || response.next.is_none() {
+        break;
+      }
+    }
+
+    highlights.sort_by_key(|highlight| std::cmp::Reverse(highlight.id));
+
+    Ok(highlights)
+  }
+
+  /// Fetch every highlight highlighted between `start` and `end` inclusive,
+  /// keeping undated highlights only when `include_undated` is set
+  ///
+  /// ```no_run
+  /// use readwise::client::Client;
+  ///
+  /// let client = Client::new("token").unwrap();
+  ///
+  /// let september = client
+  ///   .highlighted_between("2022-09-01", "2022-09-30T23:59:59Z", false)
+  ///   .unwrap();
+  /// ```
+  pub fn highlighted_between(
+    &self,
+    start: &str,
+    end: &str,
+    include_undated: bool,
+  ) -> Result<Vec<Highlight>> {
+    let (start, end) = (timestamp_bound(start)?, timestamp_bound(end)?);
+
+    let mut highlights = self.collect_highlights(None)?;
+
+    highlights.retain(|highlight| {
+      match highlight
+        .highlighted_at
+        .as_deref()
+        .and_then(parse_timestamp)
+      {
+        Some(at) => (start..=end).contains(&at),
+        None => include_undated,
+      }
+    });
+
+    Ok(highlights)
+  }
+
+  /// Fetch every highlight updated at or after `start` and before `end`,
+  /// sorted by update time, for resuming a sync from a checkpoint
+  ///
+  /// ```no_run
+  /// use readwise::client::Client;
+  ///
+  /// let client = Client::new("token").unwrap();
+  ///
+  /// let checkpoint = "2022-10-01T00:00:00Z";
+  /// let now = "2022-10-02T00:00:00Z";
+  ///
+  /// for highlight in client.highlights_updated_between(checkpoint, now).unwrap() {
+  ///   println!("{}", highlight);
+  /// }
+  ///
+  /// // Store `now` as the next checkpoint
+  /// ```
+  pub fn highlights_updated_between(
+    &self,
+    start: &str,
+    end: &str,
+  ) -> Result<Vec<Highlight>> {
+    let (start, end) = (timestamp_bound(start)?, timestamp_bound(end)?);
+
+    let filter = HighlightFilter::new()
+      .page_size(MAX_PAGE_SIZE)
+      .updated_after(&format_timestamp(start.0 - 1))
+      .updated_before(&format_timestamp(end.0 + 1));
+
+    let mut highlights = self
+      .collect_pages(1, |page| {
+        self.highlights_with(&filter.clone().page(page))
+      })?
+      .into_iter()
+      .filter_map(|highlight| {
+        parse_timestamp(&highlight.updated)
+          .filter(|updated| (start..end).contains(updated))
+          .map(|updated| (updated, highlight))
+      })
+      .collect::<Vec<((i64, u32), Highlight)>>();
+
+    highlights.sort_by_key(|(updated, highlight)| (*updated, highlight.id));
+
+    Ok(
+      highlights
+        .into_iter()
+        .map(|(_, highlight)| highlight)
+        .collect(),
+    )
+  }
+
+  /// Fetch every book highlighted after `since`, most recently highlighted
+  /// first
+  ///
+  /// ```no_run
+  /// use readwise::client::Client;
+  ///
+  /// let client = Client::new("token").unwrap();
+  ///
+  /// for book in client
+  ///   .books_with_new_highlights("2022-10-01T00:00:00Z")
+  ///   .unwrap()
+  /// {
+  ///   println!("{} ({} highlights)", book, book.num_highlights);
+  /// }
+  /// ```
+  pub fn books_with_new_highlights(&self, since: &str) -> Result<Vec<Book>> {
+    let since = timestamp_bound(since)?;
+
+    let filter = BookFilter::new()
+      .page_size(MAX_PAGE_SIZE)
+      .last_highlighted_after(&format_timestamp(since.0));
+
+    let mut books = self
+      .collect_pages(1, |page| self.books_with(&filter.clone().page(page)))?
+      .into_iter()
+      .filter_map(|book| {
+        book
+          .last_highlighted_at
+          .as_deref()
+          .and_then(parse_timestamp)
+          .filter(|at| *at > since)
+          .map(|at| (at, book))
+      })
+      .collect::<Vec<((i64, u32), Book)>>();
+
+    books.sort_by_key(|(at, book)| (std::cmp::Reverse(*at), book.id));
+
+    Ok(books.into_iter().map(|(_, book)| book).collect())
+  }
+
+  /// Fetch the highlights updated after `updated_after` along with which of
+  /// the `known` highlight identifiers have been deleted
+  ///
+  /// Updates come from the export endpoint, while deletions are found by
+  /// listing the identifiers of every current highlight, so the cost of a call
+  /// grows with the size of the library rather than the size of the change.
+  ///
+  /// ```no_run
+  /// use {readwise::client::Client, std::collections::HashSet};
+  ///
+  /// let client = Client::new("token").unwrap();
+  ///
+  /// let known = HashSet::from([1, 2, 3]);
+  ///
+  /// let delta = client
+  ///   .highlights_modified_since("2022-10-01T00:00:00Z", &known)
+  ///   .unwrap();
+  ///
+  /// println!("{} updated, {} deleted", delta.updated.len(), delta.deleted.len());
+  /// ```
+  pub fn highlights_modified_since(
+    &self,
+    updated_after: &str,
+    known: &HashSet<u64>,
+  ) -> Result<SyncDelta> {
+    let updated = self
+      .export(Some(updated_after), None)?
+      .into_iter()
+      .flat_map(|book| book.highlights)
+      .map(Highlight::from)
+      .collect::<Vec<Highlight>>();
+
+    let current = self
+      .collect_highlights(None)?
+      .into_iter()
+      .map(|highlight| highlight.id)
+      .collect::<HashSet<u64>>();
+
+    let mut deleted = known.difference(&current).copied().collect::<Vec<u64>>();
+
+    deleted.sort_unstable();
+
+    Ok(SyncDelta { updated, deleted })
+  }
+
+  /// Fetch every tag in the account, sorted by name
+  ///
+  /// Readwise has no endpoint that lists tags, so they are collected from
+  /// the books and highlights of a full export with `model::unique_tags`.
+  ///
+  /// ```no_run
+  /// use readwise::client::Client;
+  ///
+  /// let client = Client::new("token").unwrap();
+  ///
+  /// for tag in client.tags().unwrap() {
+  ///   println!("{}", tag.name);
+  /// }
+  /// ```
+  pub fn tags(&self) -> Result<Vec<Tag>> {
+    Ok(unique_tags(&self.export(None, None)?))
+  }
+
+  /// Fetch today's daily review
+  ///
+  /// When no highlights are due the review is empty rather than an error, so
+  /// `DailyReview::is_empty` tells "nothing to review today" apart from a
+  /// failed request. A response without a body, which Readwise may send in
+  /// that case, is an empty review too.
+  ///
+  /// ```no_run
+  /// use readwise::client::Client;
+  ///
+  /// let client = Client::new("token").unwrap();
+  ///
+  /// let review = client.daily_review().unwrap();
+  ///
+  /// match review.is_empty() {
+  ///   true => println!("Nothing to review today"),
+  ///   false => println!("{} highlights at {}", review.highlights.len(), review.review_url),
+  /// }
+  /// ```
+  pub fn daily_review(&self) -> Result<DailyReview> {
+    let response = self.request("/review", Method::GET, None)?;
+
+    match response.body.trim_ascii().is_empty() {
+      true => Ok(DailyReview::default()),
+      false => from_json(&response.body),
+    }
+  }
+
+  /// Fetch the whole library and compute its `LibraryStats`
+  ///
+  /// ```no_run
+  /// use readwise::client::Client;
+  ///
+  /// let client = Client::new("token").unwrap();
+  ///
+  /// for (book, highlights) in client.stats().unwrap().top_books {
+  ///   println!("{}: {} highlights", book, highlights);
+  /// }
+  /// ```
+  pub fn stats(&self) -> Result<LibraryStats> {
+    Ok(LibraryStats::compute(
+      &self.collect_books()?,
+      &self.collect_highlights(None)?,
+    ))
+  }
+
+  /// Group the highlights of each book whose text is at least `threshold`
+  /// similar, as measured by `text::similarity`
+  ///
+  /// Highlights are only compared with others from the same book, and only
+  /// groups with more than one highlight are returned.
+  ///
+  /// ```no_run
+  /// use readwise::client::Client;
+  ///
+  /// let client = Client::new("token").unwrap();
+  ///
+  /// for group in client.find_similar_highlights(0.9).unwrap() {
+  ///   println!("{} similar highlights", group.len());
+  /// }
+  /// ```
+  pub fn find_similar_highlights(
+    &self,
+    threshold: f64,
+  ) -> Result<Vec<Vec<Highlight>>> {
+    Ok(
+      self
+        .export(None, None)?
+        .into_iter()
+        .flat_map(|book| {
+          text::group_similar(
+            book.highlights.into_iter().map(Highlight::from).collect(),
+            threshold,
+          )
+        })
+        .collect(),
+    )
+  }
+
+  /// Group the highlights of each book whose text is identical once
+  /// normalized with `options`, for finding duplicate imports
+  ///
+  /// Normalization always composes Unicode characters, and `options` chooses
+  /// whether whitespace is collapsed and trimmed, case is folded, and so on.
+  /// Highlights are only compared with others from the same book, nothing is
+  /// deleted, and only groups with more than one highlight are returned.
+  ///
+  /// ```no_run
+  /// use readwise::{client::Client, text::NormalizeOptions};
+  ///
+  /// let client = Client::new("token").unwrap();
+  ///
+  /// let options = NormalizeOptions {
+  ///   lowercase: true,
+  ///   ..NormalizeOptions::default()
+  /// };
+  ///
+  /// for group in client.find_duplicate_highlights(options).unwrap() {
+  ///   println!("{} copies of {}", group.len(), group[0]);
+  /// }
+  /// ```
+  pub fn find_duplicate_highlights(
+    &self,
+    options: NormalizeOptions,
+  ) -> Result<Vec<Vec<Highlight>>> {
+    Ok(
+      self
+        .export(None, None)?
+        .into_iter()
+        .flat_map(|book| {
+          text::group_identical(
+            book.highlights.into_iter().map(Highlight::from).collect(),
+            options,
+          )
+        })
+        .collect(),
+    )
+  }
+
+  /// Write every highlight to `writer` as JSON Lines, one page at a time
+  ///
+  /// If a request or write fails part way through, the returned
+  /// `Error::Export` records how many lines were written before the failure.
+  ///
+  /// ```no_run
+  /// use {readwise::client::Client, std::fs::File};
+  ///
+  /// let client = Client::new("token").unwrap();
+  ///
+  /// let stats = client
+  ///   .export_highlights_jsonl(File::create("highlights.jsonl").unwrap())
+  ///   .unwrap();
+  ///
+  /// println!("{} highlights written", stats.lines);
+  /// ```
+  pub fn export_highlights_jsonl(
+    &self,
+    writer: impl Write,
+  ) -> Result<ExportStats> {
+    self.export_jsonl(writer, |page| {
+      let response = self.highlights_page(page, MAX_PAGE_SIZE, None)?;
+      Ok((response.results, response.next))
+    })
+  }
+
+  /// Write every book to `writer` as JSON Lines, one page at a time
+  ///
+  /// ```no_run
+  /// use {readwise::client::Client, std::fs::File};
+  ///
+  /// let client = Client::new("token").unwrap();
+  ///
+  /// client
+  ///   .export_books_jsonl(File::create("books.jsonl").unwrap())
+  ///   .unwrap();
+  /// ```
+  pub fn export_books_jsonl(&self, writer: impl Write) -> Result<ExportStats> {
+    self.export_jsonl(writer, |page| {
+      let response = self.books_page(page, MAX_PAGE_SIZE)?;
+      Ok((response.results, response.next))
+    })
+  }
+
+  /// Export every book and its highlights to a JSON Lines file, one book per
+  /// line, resuming an interrupted export of the same file
+  ///
+  /// After each page is written, the cursor of the next page and the length
+  /// of the file so far are saved to a sidecar file next to `path` with
+  /// `.cursor` appended to its name. If that sidecar exists when the export
+  /// starts, the file is cut back to the saved length, dropping anything
+  /// written of an unfinished page, and continued from the saved cursor
+  /// instead of being rewritten from the first page. The sidecar is removed
+  /// once the export completes.
+  ///
+  /// Resuming with an `updated_after` other than the one the export started
+  /// with fails with `Error::ExportResumeMismatch`, since the saved cursor
+  /// belongs to the original query. If the file is missing or shorter than
+  /// the saved length, the export starts over.
+  ///
+  /// ```no_run
+  /// use {readwise::client::Client, std::path::Path};
+  ///
+  /// let client = Client::new("token").unwrap();
+  ///
+  /// let summary = client
+  ///   .export_to_file(Path::new("export.jsonl"), None)
+  ///   .unwrap();
+  ///
+  /// println!("{} books in {} pages", summary.items, summary.pages);
+  /// ```
+  pub fn export_to_file(
+    &self,
+    path: &Path,
+    updated_after: Option<&str>,
+  ) -> Result<ExportSummary> {
+    let sidecar = Self::cursor_path(path);
+
+    let checkpoint = match fs::read(&sidecar) {
+      Ok(checkpoint) => Some(from_json::<ExportCheckpoint>(&checkpoint)?),
+      Err(error) if error.kind() == std::io::ErrorKind::NotFound => None,
+      Err(error) => return Err(error.into()),
+    };
+
+    if let Some(checkpoint) = &checkpoint {
+      if checkpoint.updated_after.as_deref() != updated_after {
+        return Err(Error::ExportResumeMismatch {
+          path: path.display().to_string(),
+          started: checkpoint.updated_after.clone(),
+        });
+      }
+    }
+
+    let checkpoint = checkpoint.filter(|checkpoint| {
+      fs::metadata(path).is_ok_and(|file| file.len() >= checkpoint.length)
+    });
+
+    let mut summary = ExportSummary {
+      resumed: checkpoint.is_some(),
+      ..Default::default()
+    };
+
+    let (mut cursor, length) = match checkpoint {
+      Some(checkpoint) => (Some(checkpoint.cursor), checkpoint.length),
+      None => (None, 0),
+    };
+
+    let mut export = || -> Result {
+      let mut file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(false)
+        .open(path)?;
+
+      file.set_len(length)?;
+      file.seek(SeekFrom::End(0))?;
+
+      let mut writer = BufWriter::new(file);
+
+      loop {
+        let response = self.export_page(updated_after, None, cursor.take())?;
+
+        summary.pages += 1;
+
+        self.emit(Event::PageFetched {
+          page: summary.pages,
+          items: response.results.len() as u64,
+        });
+
+        for book in response.results {
+          serde_json::to_writer(&mut writer, &book)?;
+          writer.write_all(b"\n")?;
+          summary.items += 1;
+        }
+
+        writer.flush()?;
+
+        match response.next_page_cursor {
+          Some(next) => {
+            Self::save_checkpoint(
+              &sidecar,
+              &ExportCheckpoint {
+                cursor: next.clone(),
+                length: writer.get_ref().metadata()?.len(),
+                updated_after: updated_after.map(String::from),
+              },
+            )?;
+            cursor = Some(next);
+          }
+          None => break,
+        }
+      }
+
+      match fs::remove_file(&sidecar) {
+        Err(error) if error.kind() != std::io::ErrorKind::NotFound => {
+          Err(error.into())
+        }
+        _ => Ok(()),
+      }
+    };
+
+    match export() {
+      Ok(()) => {
+        self.emit(Event::Done {
+          total: summary.items,
+        });
+        Ok(summary)
+      }
+      Err(source) => Err(Error::Export {
+        lines: summary.items,
+        source: Box::new(source),
+      }),
+    }
+  }
+
+  fn cursor_path(path: &Path) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(".cursor");
+    PathBuf::from(name)
+  }
+
+  /// Replace the checkpoint at `sidecar` by renaming a new one over it, so a
+  /// crash never leaves it half written
+  fn save_checkpoint(sidecar: &Path, checkpoint: &ExportCheckpoint) -> Result {
+    let mut name = OsString::from(sidecar.as_os_str());
+    name.push(".tmp");
+    let temporary = PathBuf::from(name);
+
+    fs::write(&temporary, serde_json::to_vec(checkpoint)?)?;
+    fs::rename(&temporary, sidecar)?;
+
+    Ok(())
+  }
+
+  fn export_jsonl<T: Serialize + Identified>(
+    &self,
+    mut writer: impl Write,
+    fetch: impl Fn(u64) -> Result<(Vec<T>, Option<String>)>,
+  ) -> Result<ExportStats> {
+    let mut stats = ExportStats::default();
+
+    let mut unseen = self.unseen();
+
+    let mut export = || -> Result {
+      let mut page = 1;
+
+      loop {
+        let (results, next) = fetch(page)?;
+
+        stats.pages += 1;
+
+        self.emit(Event::PageFetched {
+          page,
+          items: results.len() as u64,
+        });
+
+        for item in results.into_iter().filter(&mut unseen) {
+          let mut line = serde_json::to_vec(&item)?;
+          line.push(b'\n');
+
+          writer.write_all(&line)?;
+
+          stats.lines += 1;
+          stats.bytes += line.len() as u64;
+        }
+
+        if next.is_none() {
+          break;
+        }
+
+        page += 1;
+      }
+
+      Ok(writer.flush()?)
+    };
+
+    match export() {
+      Ok(()) => {
+        self.emit(Event::Done { total: stats.lines });
+        Ok(stats)
+      }
+      Err(source) => Err(Error::Export {
+        lines: stats.lines,
+        source: Box::new(source),
+      }),
+    }
+  }
+
+  fn create_highlight_ids<T: Serialize>(
+    &self,
+    highlights: Vec<T>,
+  ) -> Result<Vec<u64>> {
+    let mut identifiers = Vec::new();
+
+    for batch in create_payloads(highlights)?.chunks(self.create_batch_size) {
+      let books = self.send::<Vec<HighlightCreateResponse>>(
+        Method::POST,
+        "/highlights",
+        Some(serde_json::json!({ "highlights": batch })),
+      )?;
+
+      self.with_book_cache(|cache| {
+        books.iter().for_each(|book| cache.remove(&book.id))
+      });
+
+      identifiers
+        .extend(books.into_iter().flat_map(|item| item.modified_highlights));
+    }
+
+    Ok(identifiers)
+  }
+
+  fn collect_parallel<T: Send + Identified>(
+    &self,
+    concurrency: usize,
+    fetch: impl Fn(u64) -> Result<(Vec<T>, u64)> + Sync,
+  ) -> Result<Vec<T>> {
+    let (first, count) = fetch(1)?;
+
+    self.emit(Event::PageFetched {
+      page: 1,
+      items: first.len() as u64,
+    });
+
+    let pages = count.div_ceil(MAX_PAGE_SIZE).max(1);
+
+    let rest = self.in_parallel(concurrency, pages - 1, |index| {
+      let page = index + 2;
+
+      let (items, _) = fetch(page)?;
+
+      self.emit(Event::PageFetched {
+        page,
+        items: items.len() as u64,
+      });
+
+      Ok(items)
+    })?;
+
+    let items = std::iter::once(first)
+      .chain(rest)
+      .flatten()
+      .filter(self.unseen())
+      .collect::<Vec<T>>();
+
+    self.emit(Event::Done {
+      total: items.len() as u64,
+    });
+
+    Ok(items)
+  }
+
+  /// Call `task` with every index below `count` on up to `concurrency`
+  /// threads, returning the results in index order and stopping at the first
+  /// failure
+  fn in_parallel<R: Send>(
+    &self,
+    concurrency: usize,
+    count: u64,
+    task: impl Fn(u64) -> Result<R> + Sync,
+  ) -> Result<Vec<R>> {
+    let next = AtomicU64::new(0);
+    let failed = AtomicBool::new(false);
+
+    let workers = concurrency.clamp(1, MAX_CONCURRENCY).min(count as usize);
+
+    let results = thread::scope(|scope| {
+      (0..workers)
+        .map(|_| {
+          scope.spawn(|| -> Result<Vec<(u64, R)>> {
+            let mut done = Vec::new();
+
+            while !failed.load(Ordering::Relaxed) {
+              let index = next.fetch_add(1, Ordering::Relaxed);
+
+              if index >= count {
+                break;
+              }
+
+              match task(index) {
+                Ok(result) => done.push((index, result)),
+                Err(error) => {
+                  failed.store(true, Ordering::Relaxed);
+                  return Err(error);
+                }
+              }
+            }
+
+            Ok(done)
+          })
+        })
+        .collect::<Vec<_>>()
+        .into_iter()
+        .map(|worker| worker.join().expect("worker panicked"))
+        .collect::<Vec<_>>()
+    });
+
+    let mut done = Vec::new();
+
+    for result in results {
+      done.extend(result?);
+    }
+
+    done.sort_by_key(|(index, _)| *index);
+
+    Ok(done.into_iter().map(|(_, result)| result).collect())
+  }
+
+  fn collect_books(&self) -> Result<Vec<Book>> {
+    Ok(self.all_books_from(1)?)
+  }
+
+  /// Fetch the pages from `start` until the last one, keeping the items
+  /// fetched so far if a page fails
+  fn collect_pages<T: Identified>(
+    &self,
+    start: u64,
+    fetch: impl Fn(u64) -> Result<Page<T>>,
+  ) -> Result<Vec<T>, PartialFetch<T>> {
+    let mut items = Vec::new();
+
+    let mut unseen = self.unseen();
+
+    for page in start.. {
+      let response = match fetch(page) {
+        Ok(response) => response,
+        Err(error) => {
+          return Err(PartialFetch {
+            items,
+            next_page: page,
+            error,
+          })
+        }
+      };
+
+      self.emit(Event::PageFetched {
+        page,
+        items: response.results.len() as u64,
+      });
+
+      items.extend(response.results.into_iter().filter(&mut unseen));
+
+      if response.next.is_none() {
+        break;
+      }
+    }
+
+    Ok(items)
+  }
+
+  fn without_existing(
+    &self,
+    highlights: Vec<HighlightCreate>,
+  ) -> Result<Vec<HighlightCreate>> {
+    let books = self.collect_books()?;
+
+    let mut existing = HashMap::new();
+
+    let mut remaining = Vec::new();
+
+    for highlight in highlights {
+      let title = highlight.title.as_deref().unwrap_or(DEFAULT_BOOK_TITLE);
+
+      let book = books.iter().find(|book| {
+        book.title == title
+          && (highlight.author.is_none() || book.author == highlight.author)
+      });
+
+      let book_id = match book {
+        Some(book) => book.id,
+        None => {
+          remaining.push(highlight);
+          continue;
+        }
+      };
+
+      let texts = match existing.entry(book_id) {
+        Entry::Occupied(entry) => entry.into_mut(),
+        Entry::Vacant(entry) => entry.insert(
+          self
+            .collect_highlights(Some(book_id))?
+            .iter()
+            .map(|highlight| {
+              text::normalize(&highlight.text, NormalizeOptions::comparison())
+            })
+            .collect::<HashSet<String>>(),
+        ),
+      };
+
+      if texts.insert(text::normalize(
+        &highlight.text,
+        NormalizeOptions::comparison(),
+      )) {
+        remaining.push(highlight);
+      }
+    }
+
+    Ok(remaining)
+  }
+
+  fn collect_highlights(&self, book_id: Option<u64>) -> Result<Vec<Highlight>> {
+    Ok(self.collect_pages(1, |page| {
+      self.highlights_page(page, MAX_PAGE_SIZE, book_id)
+    })?)
+  }
+
+  fn export_page(
+    &self,
+    updated_after: Option<&str>,
+    category: Option<Category>,
+    cursor: Option<String>,
+  ) -> Result<ExportResponse> {
+    self.get(&export_endpoint(updated_after, category, cursor.as_deref()))
+  }
+
+  fn books_page(&self, page: u64, page_size: u64) -> Result<BooksResponse> {
+    self.books_with(&BookFilter::new().page(page).page_size(page_size))
+  }
+
+  fn highlights_page(
+    &self,
+    page: u64,
+    page_size: u64,
+    book_id: Option<u64>,
+  ) -> Result<HighlightsResponse> {
+    let filter = HighlightFilter::new().page(page).page_size(page_size);
+
+    self.highlights_with(&match book_id {
+      Some(book_id) => filter.book_id(book_id),
+      None => filter,
+    })
+  }
+
+  /// Send an authenticated `GET` request to an arbitrary v2 API endpoint,
+  /// returning the raw JSON response
+  ///
+  /// This is an escape hatch for endpoints and fields not yet modeled by this
+  /// crate.
+  ///
+  /// ```no_run
+  /// use readwise::client::Client;
+  ///
+  /// let client = Client::new("token").unwrap();
+  ///
+  /// let review = client.get_raw("/review").unwrap();
+  ///
+  /// println!("{}", review["review_url"]);
+  /// ```
+  pub fn get_raw(&self, endpoint: &str) -> Result<serde_json::Value> {
+    self.get_json(endpoint, &[])
+  }
+
+  /// Send an authenticated `POST` request with a JSON body to an arbitrary v2
+  /// API endpoint, returning the raw JSON response
+  ///
+  /// ```no_run
+  /// use {readwise::client::Client, serde_json::json};
+  ///
+  /// let client = Client::new("token").unwrap();
+  ///
+  /// client
+  ///   .post_raw("/highlights", json!({ "highlights": [{ "text": "hello" }] }))
+  ///   .unwrap();
+  /// ```
+  pub fn post_raw(
+    &self,
+    endpoint: &str,
+    body: serde_json::Value,
+  ) -> Result<serde_json::Value> {
+    self.post_json(endpoint, &body)
+  }
+
+  /// Send an authenticated `GET` request to the v2 API endpoint at `path`
+  /// with the query parameters `params`, returning the raw JSON response
+  ///
+  /// Like every method of the client, this retries rate limited requests and
+  /// reports failed ones as an `Error`.
+  ///
+  /// ```no_run
+  /// use readwise::client::Client;
+  ///
+  /// let client = Client::new("token").unwrap();
+  ///
+  /// let books = client.get_json("/books", &[("category", "books")]).unwrap();
+  ///
+  /// println!("{} books", books["count"]);
+  /// ```
+  pub fn get_json(
+    &self,
+    path: &str,
+    params: &[(&str, &str)],
+  ) -> Result<serde_json::Value> {
+    self.get(&with_query(path, params))
+  }
+
+  /// Fetch a page of a paginated v2 API endpoint at `path`, deserializing
+  /// its results as `T`
+  ///
+  /// ```no_run
+  /// use {readwise::client::Client, serde::Deserialize};
+  ///
+  /// #[derive(Deserialize)]
+  /// struct Document {
+  ///   id: u64,
+  ///   title: String,
+  /// }
+  ///
+  /// let client = Client::new("token").unwrap();
+  ///
+  /// let page = client
+  ///   .get_paged::<Document>("/documents", &[("page", "1")])
+  ///   .unwrap();
+  ///
+  /// for document in page.results {
+  ///   println!("{}: {}", document.id, document.title);
+  /// }
+  /// ```
+  pub fn get_paged<T: DeserializeOwned>(
+    &self,
+    path: &str,
+    params: &[(&str, &str)],
+  ) -> Result<Page<T>> {
+    self.get(&with_query(path, params))
+  }
+
+  /// Send an authenticated `POST` request with `body` serialized as JSON to
+  /// the v2 API endpoint at `path`, returning the raw JSON response, or
+  /// `null` when the response body is empty
+  ///
+  /// ```no_run
+  /// use {readwise::client::Client, serde_json::json};
+  ///
+  /// let client = Client::new("token").unwrap();
+  ///
+  /// client
+  ///   .post_json("/highlights/1/tags", &json!({ "name": "favorite" }))
+  ///   .unwrap();
+  /// ```
+  pub fn post_json(
+    &self,
+    path: &str,
+    body: &impl Serialize,
+  ) -> Result<serde_json::Value> {
+    self.send(Method::POST, path, Some(serde_json::to_value(body)?))
+  }
+
+  /// Send an authenticated `PATCH` request with `body` serialized as JSON to
+  /// the v2 API endpoint at `path`, returning the raw JSON response, or
+  /// `null` when the response body is empty
+  ///
+  /// ```no_run
+  /// use {readwise::client::Client, serde_json::json};
+  ///
+  /// let client = Client::new("token").unwrap();
+  ///
+  /// client
+  ///   .patch_json("/highlights/1", &json!({ "note": "revisit" }))
+  ///   .unwrap();
+  /// ```
+  pub fn patch_json(
+    &self,
+    path: &str,
+    body: &impl Serialize,
+  ) -> Result<serde_json::Value> {
+    self.send(Method::PATCH, path, Some(serde_json::to_value(body)?))
+  }
+
+  /// Send an authenticated `DELETE` request to the v2 API endpoint at `path`,
+  /// reporting a `404 Not Found` as `DeleteOutcome::NotFound`
+  ///
+  /// ```no_run
+  /// use readwise::client::Client;
+  ///
+  /// let client = Client::new("token").unwrap();
+  /// client.delete_path("/highlights/1/tags/2").unwrap();
+  /// ```
+  pub fn delete_path(&self, path: &str) -> Result<DeleteOutcome> {
+    DeleteOutcome::from_result(self.request(path, Method::DELETE, None))
+  }
+
+  /// Metadata of the most recent response received by this client, including
+  /// error responses
+  ///
+  /// ```no_run
+  /// use readwise::client::Client;
+  ///
+  /// let client = Client::new("token").unwrap();
+  ///
+  /// client.books(1).unwrap();
+  ///
+  /// if let Some(meta) = client.last_response_meta() {
+  ///   println!("{} in {:?}", meta.status, meta.elapsed);
+  /// }
+  /// ```
+  pub fn last_response_meta(&self) -> Option<ResponseMeta> {
+    self.last_response_meta.lock().ok()?.clone()
+  }
+
+  /// The rate limit state reported by the most recent response that carried
+  /// `X-RateLimit-*` headers or was rate limited, for pacing requests before
+  /// the API starts rejecting them
+  ///
+  /// ```no_run
+  /// use {readwise::client::Client, std::time::SystemTime};
+  ///
+  /// let client = Client::new("token").unwrap();
+  ///
+  /// client.books(1).unwrap();
+  ///
+  /// if let Some(status) = client.rate_limit_status() {
+  ///   if status.remaining == 0 {
+  ///     if let Some(reset_at) = status.reset_at {
+  ///       let wait = reset_at.duration_since(SystemTime::now());
+  ///       std::thread::sleep(wait.unwrap_or_default());
+  ///     }
+  ///   }
+  /// }
+  /// ```
+  pub fn rate_limit_status(&self) -> Option<RateLimitStatus> {
+    self.rate_limit_status.lock().ok()?.clone()
+  }
+
+  fn get<T: DeserializeOwned>(&self, endpoint: &str) -> Result<T> {
+    from_json(&self.request(endpoint, Method::GET, None)?.body)
+  }
+
+  /// Send a write request, yielding `T::default()` in place of the response
+  /// in dry-run mode
+  fn send<T: DeserializeOwned + Default>(
+    &self,
+    method: Method,
+    endpoint: &str,
+    body: Option<serde_json::Value>,
+  ) -> Result<T> {
+    Ok(
+      self
+        .send_optional(method, endpoint, body)?
+        .unwrap_or_default(),
+    )
+  }
+
+  /// Like `send`, but `None` when a successful response has an empty body,
+  /// such as a `204 No Content`, instead of failing to deserialize it
+  fn send_optional<T: DeserializeOwned + Default>(
+    &self,
+    method: Method,
+    endpoint: &str,
+    body: Option<serde_json::Value>,
+  ) -> Result<Option<T>> {
+    let response = self.request(endpoint, method, body)?;
+
+    match self.dry_run {
+      true => Ok(Some(T::default())),
+      false if response.body.trim_ascii().is_empty() => Ok(None),
+      false => from_json(&response.body).map(Some),
+    }
+  }
+
+  /// A filter passing items not seen before, or every item unless
+  /// `ClientBuilder::dedup_pagination` is enabled
+  fn unseen<T: Identified>(&self) -> impl FnMut(&T) -> bool {
+    let mut seen = self.dedup_pagination.then(HashSet::new);
+
+    move |item| {
+      seen
+        .as_mut()
+        .is_none_or(|seen| seen.insert(item.identifier()))
+    }
+  }
+
+  fn with_book_cache<T>(
+    &self,
+    f: impl FnOnce(&mut LruCache<u64, Book>) -> T,
+  ) -> Option<T> {
+    self
+      .book_cache
+      .as_ref()
+      .and_then(|cache| cache.lock().ok())
+      .map(|mut cache| f(&mut cache))
+  }
+
+  fn emit(&self, event: Event) {
+    if let Some(handler) = &self.on_event {
+      if let Ok(mut handler) = handler.lock() {
+        handler(event);
+      }
+    }
+  }
+
+  fn request(
+    &self,
+    endpoint: &str,
+    method: Method,
+    body: Option<serde_json::Value>,
+  ) -> Result<ApiResponse> {
+    if self.dry_run && method != Method::GET {
+      self.emit(Event::DryRun {
+        method,
+        endpoint: endpoint.to_string(),
+        body: body.map(|body| body.to_string()),
+      });
+
+      return Ok(ApiResponse {
+        status: StatusCode::NO_CONTENT,
+        headers: header::HeaderMap::new(),
+        url: endpoint.to_string(),
+        body: Vec::new(),
+      });
+    }
+
+    let store = self
+      .response_cache
+      .as_ref()
+      .filter(|_| method == Method::GET);
+
+    let cached = store.and_then(|store| store.get(endpoint));
+
+    let mut headers = self.headers.clone();
+
+    if let Some(cached) = &cached {
+      cached.validate(&mut headers);
+    }
+
+    let mut attempt = 0;
+
+    loop {
+      if self
+        .cancel_flag
+        .as_ref()
+        .is_some_and(|flag| flag.load(Ordering::Relaxed))
+      {
+        return Err(Error::Cancelled);
+      }
+
+      if let Some(throttle) = &self.throttle {
+        self.sleeper.sleep(throttle.reserve());
+      }
+
+      let request = ApiRequest::new(
+        method.clone(),
+        endpoint,
+        headers.clone(),
+        body.clone(),
+      );
+
+      let observed = self.observer.as_ref().map(|observer| {
+        observer.on_request(&request);
+        (observer, request.clone())
+      });
+
+      let stopwatch = Stopwatch::start();
+
+      let result = self.transport.execute(request);
+
+      let elapsed = stopwatch.elapsed();
+
+      if let Some((observer, request)) = observed {
+        match &result {
+          Ok(response) => {
+            observer.on_response(&request, response.status, elapsed)
+          }
+          Err(error) => observer.on_error(&request, error, elapsed),
+        }
+      }
+
+      let response = match result {
+        Ok(response) => response,
+        Err(error) => {
+          let wait = (error.is_retryable() && method != Method::POST)
+            .then(|| self.retry.wait(attempt, None))
+            .flatten();
+
+          match wait {
+            Some(wait) => {
+              attempt += 1;
+
+              self.emit(Event::Retrying {
+                attempt,
+                after: wait,
+              });
+
+              self.sleeper.sleep(wait);
+
+              continue;
+            }
+            None => return Err(error),
+          }
+        }
+      };
+
+      let meta = ResponseMeta {
+        status: response.status,
+        headers: response.headers.clone(),
+        url: response.url.clone(),
+        elapsed,
+      };
+
+      if let Ok(mut last) = self.last_response_meta.lock() {
+        *last = Some(meta.clone());
+      }
+
+      if let Some(status) =
+        RateLimitStatus::from_response(&meta, std::time::SystemTime::now())
+      {
+        if let Ok(mut last) = self.rate_limit_status.lock() {
+          *last = Some(status);
+        }
+      }
+
+      let rate_limited = meta.status == StatusCode::TOO_MANY_REQUESTS;
+
+      let retryable = rate_limited
+        || (meta.status.is_server_error() && method != Method::POST);
+
+      let wait = retryable
+        .then(|| self.retry.wait(attempt, error::retry_after(&meta.headers)))
+        .flatten();
+
+      if let Some(wait) = wait {
+        attempt += 1;
+
+        if rate_limited {
+          self.emit(Event::RateLimited { wait });
+        }
+
+        self.emit(Event::Retrying {
+          attempt,
+          after: wait,
+        });
+
+        self.sleeper.sleep(wait);
+
+        continue;
+      }
+
+      if let Some(cached) =
+        cached.filter(|_| response.status == StatusCode::NOT_MODIFIED)
+      {
+        return Ok(ApiResponse {
+          status: StatusCode::OK,
+          body: cached.body,
+          ..response
+        });
+      }
+
+      if let Some((store, cached)) = store
+        .filter(|_| response.status.is_success())
+        .zip(CachedResponse::from_response(&response))
+      {
+        store.put(endpoint, cached);
+      }
+
+      return match response.status.is_success() {
+        true => Ok(response),
+        false => Err(Error::from_response(
+          meta,
+          &String::from_utf8_lossy(&response.body),
+        )),
+      };
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use {
+    super::*,
+    crate::{
+      model::ExportHighlight, response_cache::MemoryCacheStore,
+      testing::FakeTransport,
+    },
+    mockito::{mock, Matcher},
+  };
+
+  fn client() -> Client {
+    ClientBuilder::new("token").build().unwrap()
+  }
+
+  fn get_book_as_string() -> String {
+    serde_json::to_string(&Book::default()).unwrap()
+  }
+
+  fn get_highlight_as_string() -> String {
+    serde_json::to_string(&Highlight::default()).unwrap()
+  }
+
+  #[test]
+  fn authenticate() {
+    let _m = mock("GET", "/api/v2/auth")
+      .match_header("Authorization", "Token token")
+      .with_status(204)
+      .create();
+
+    let result = Client::new("token");
+
+    assert!(result.is_ok(), "{}", result.err().unwrap().to_string());
+  }
+
+  #[test]
+  fn builder() {
+    let m = mock("GET", "/api/v2/auth")
+      .match_header("Authorization", "Token builder")
+      .match_header("User-Agent", "readwise-test/1.0")
+      .with_status(204)
+      .create();
+
+    let result = ClientBuilder::new("builder")
+      .base_url(&format!("{}/", mockito::server_url()))
+      .user_agent("readwise-test/1.0")
+      .build_and_auth();
+
+    assert!(result.is_ok(), "{}", result.err().unwrap().to_string());
+
+    m.assert();
+  }
+
+  #[test]
+  fn default_user_agent() {
+    let m = mock("GET", "/api/v2/auth")
+      .match_header("Authorization", "Token default-agent")
+      .match_header(
+        "User-Agent",
+        format!("readwise/{}", crate::VERSION).as_str(),
+      )
+      .with_status(204)
+      .create();
+
+    let result = ClientBuilder::new("default-agent")
+      .base_url(&format!("{}/", mockito::server_url()))
+      .build_and_auth();
+
+    assert!(result.is_ok(), "{}", result.err().unwrap().to_string());
+
+    m.assert();
+  }
+
+  #[test]
+  fn authenticate_bad_token() {
+    let _m = mock("GET", "/api/v2/auth").with_status(401).create();
+
+    let result = Client::new("token");
+
+    assert!(
+      matches!(result, Err(Error::Unauthorized { .. })),
+      "{:?}",
+      result
+    );
+  }
+
+  #[test]
+  fn blank_token() {
+    let transport = FakeTransport::new();
+
+    let result = ClientBuilder::new(" ")
+      .transport(Box::new(transport.clone()))
+      .build_and_auth();
+
+    assert!(matches!(result, Err(Error::MissingToken)), "{:?}", result);
+
+    assert!(transport.requests().is_empty());
+  }
+
+  #[test]
+  fn verify_token() {
+    let transport = FakeTransport::new()
+      .respond("GET", "/auth", 204, "")
+      .respond("GET", "/auth", 401, r#"{ "detail": "Invalid token." }"#);
+
+    let client =
+      Client::with_transport("token", Box::new(transport.clone())).unwrap();
+
+    assert!(client.verify_token().is_ok());
+
+    let error = client.verify_token().unwrap_err();
+
+    assert!(matches!(error, Error::Unauthorized { .. }), "{:?}", error);
+    assert_eq!(
+      error.to_string(),
+      "Unauthorized: the access token is invalid or revoked"
+    );
+  }
+
+  #[test]
+  fn books() {
+    let _m = mock("GET", "/api/v2/books?page=1")
+      .with_status(200)
+      .with_body(format!(
+        r#" {{ "count": 1, "next": null, "previous": null, "results": [{}] }} "#,
+        &get_book_as_string()
+      ))
+      .create();
+
+    let result = client().books(1);
+
+    assert!(result.is_ok(), "{}", result.err().unwrap().to_string());
+  }
+
+  #[test]
+  fn highlights() {
+    let _m = mock("GET", "/api/v2/highlights?page=1")
+      .with_status(200)
+      .with_body(format!(
+        r#" {{ "count": 1, "next": null, "previous": null, "results": [{}] }} "#,
+        get_highlight_as_string()
+      ))
+      .create();
+
+    let result = client().highlights(1);
+
+    assert!(result.is_ok(), "{}", result.err().unwrap().to_string());
+  }
+
+  #[test]
+  fn custom_headers() {
+    let transport = FakeTransport::new();
+
+    let client = ClientBuilder::new("token")
+      .header("X-Correlation-Id", "abc123")
+      .header("Authorization", "Bearer gateway")
+      .transport(Box::new(transport.clone()))
+      .build()
+      .unwrap();
+
+    client.get_raw("/books/2").ok();
+
+    let requests = transport.requests();
+
+    assert_eq!(requests[0].headers["x-correlation-id"], "abc123");
+    assert_eq!(requests[0].headers[header::AUTHORIZATION], "Token token");
+    assert_eq!(requests[0].headers.len(), 2);
+  }
+
+  #[test]
+  fn rate_limit() {
+    let transport = FakeTransport::new().respond("GET", "/books/3", 200, "{}");
+
+    let client = ClientBuilder::new("token")
+      .rate_limit(1200)
+      .transport(Box::new(transport.clone()))
+      .build()
+      .unwrap();
+
+    let stopwatch = Stopwatch::start();
+
+    for _ in 0..3 {
+      client.get_raw("/books/3").unwrap();
+    }
+
+    assert!(stopwatch.elapsed() >= Duration::from_millis(100));
+    assert_eq!(transport.requests().len(), 3);
+  }
+
+  #[derive(Clone, Default)]
+  struct RecordingSleeper(std::sync::Arc<Mutex<Vec<Duration>>>);
+
+  impl Sleeper for RecordingSleeper {
+    fn sleep(&self, duration: Duration) {
+      self.0.lock().unwrap().push(duration);
+    }
+  }
+
+  #[test]
+  fn custom_sleeper() {
+    let transport = FakeTransport::new()
+      .respond("GET", "/books/3", 503, "{}")
+      .respond("GET", "/books/3", 200, "{}");
+
+    let sleeper = RecordingSleeper::default();
+
+    let client = ClientBuilder::new("token")
+      .rate_limit(1)
+      .retries(1)
+      .backoff(Duration::from_secs(10), Duration::from_secs(10))
+      .sleeper(Box::new(sleeper.clone()))
+      .transport(Box::new(transport.clone()))
+      .build()
+      .unwrap();
+
+    let stopwatch = Stopwatch::start();
+
+    client.get_raw("/books/3").unwrap();
+
+    assert!(stopwatch.elapsed() < Duration::from_secs(5));
+    assert_eq!(transport.requests().len(), 2);
+
+    let waits = sleeper.0.lock().unwrap().clone();
+
+    assert_eq!(waits.len(), 3);
+    assert_eq!(waits[0], Duration::ZERO);
+    assert!(waits[1] >= Duration::from_secs(5), "{:?}", waits);
+    assert!(waits[2] >= Duration::from_secs(50), "{:?}", waits);
+  }
+
+  #[test]
+  fn invalid_custom_headers() {
+    for (name, value) in [("bad header", "value"), ("X-Name", "bad\nvalue")] {
+      let error = ClientBuilder::new("token")
+        .header(name, value)
+        .transport(Box::new(FakeTransport::new()))
+        .build()
+        .unwrap_err();
+
+      assert!(
+        matches!(&error, Error::InvalidHeader { name: n, .. } if n == name),
+        "{:?}",
+        error
+      );
+      assert!(error
+        .to_string()
+        .starts_with(&format!("Invalid header `{}`: ", name)));
+    }
+  }
+
+  #[test]
+  fn single_book() {
+    let transport = FakeTransport::new().respond(
+      "GET",
+      "/books/1",
+      200,
+      &get_book_as_string(),
+    );
+
+    let client =
+      Client::with_transport("token", Box::new(transport.clone())).unwrap();
+
+    let result = client.book(1);
+
+    assert!(result.is_ok(), "{}", result.err().unwrap().to_string());
+
+    let requests = transport.requests();
+
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].method, Method::GET);
+    assert_eq!(requests[0].headers[header::AUTHORIZATION], "Token token");
+  }
+
+  #[test]
+  fn single_highlight() {
+    let _m = mock("GET", "/api/v2/highlights/1")
+      .with_status(200)
+      .with_body(get_highlight_as_string())
+      .create();
+
+    let result = client().highlight(1);
+
+    assert!(result.is_ok(), "{}", result.err().unwrap().to_string());
+  }
+
+  #[test]
+  fn create_highlights() {
+    let _m = mock("POST", "/api/v2/highlights")
+      .with_status(200)
+      .with_body(
+        r#"
+        [ { "id": 1,
+          "title": "Quotes",
+          "author": null,
+          "category": "books",
+          "num_highlights": 5,
+          "last_highlight_at": "2021-02-20T16:28:53.900414Z",
+          "updated": "2021-02-20T16:35:41.793746Z",
+          "cover_image_url": "https://readwise-assets.s3.amazonaws.com/static/images/default-book-icon-7.09749d3efd49.png",
+          "highlights_url": "https://readwise.io/bookreview/7843339",
+          "source_url": null,
+          "modified_highlights": [] }
+        ]"#,
+      )
+      .create();
+
+    let result = client().create_highlights(Vec::<HighlightCreate>::new());
+
+    assert!(result.is_ok(), "{}", result.err().unwrap().to_string());
+  }
+
+  #[test]
+  fn update_highlight() {
+    let _m = mock("PATCH", "/api/v2/highlights/0")
+      .match_body(Matcher::Json(serde_json::json!({
+        "note": "revisit",
+        "color": "blue"
+      })))
+      .with_status(200)
+      .with_body(get_highlight_as_string())
+      .create();
+
+    let result = client().update_highlight(
+      0,
+      HighlightUpdate {
+        note: Some("revisit".into()),
+        color: Some(Color::Blue),
+        ..Default::default()
+      },
+    );
+
+    assert!(result.is_ok(), "{}", result.err().unwrap().to_string());
+  }
+
+  #[test]
+  fn delete_highlight() {
+    let _m = mock("DELETE", "/api/v2/highlights/1")
+      .with_status(204)
+      .create();
+
+    assert_eq!(
+      client().delete_highlight(1).unwrap(),
+      DeleteOutcome::Deleted
+    );
+  }
+
+  #[test]
+  fn delete_highlight_not_found() {
+    let transport = FakeTransport::new().respond(
+      "DELETE",
+      "/highlights/2",
+      404,
+      r#"{"detail": "Not found."}"#,
+    );
+
+    let client = Client::with_transport("token", Box::new(transport)).unwrap();
+
+    assert_eq!(client.delete_highlight(2).unwrap(), DeleteOutcome::NotFound);
+  }
+
+  #[test]
+  fn delete_highlight_forbidden() {
+    let _m = mock("DELETE", "/api/v2/highlights/3")
+      .with_status(403)
+      .create();
+
+    assert!(matches!(
+      client().delete_highlight(3),
+      Err(Error::BadRequest {
+        status: StatusCode::FORBIDDEN,
+        ..
+      })
+    ));
+  }
+
+  fn highlights_page_body(count: u64, id: u64) -> String {
+    format!(
+      r#" {{ "count": {}, "next": null, "previous": null, "results": [{}] }} "#,
+      count,
+      serde_json::to_string(&Highlight {
+        id,
+        ..Default::default()
+      })
+      .unwrap()
+    )
+  }
+
+  #[test]
+  fn random_highlights() {
+    let mocks = (1..=3)
+      .map(|page| {
+        mock(
+          "GET",
+          format!("/api/v2/highlights?page={}&page_size=1", page).as_str(),
+        )
+        .with_status(200)
+        .with_body(highlights_page_body(3, page))
+        .expect_at_least(1)
+        .create()
+      })
+      .collect::<Vec<_>>();
+
+    let first = client().random_highlights(3, None, Some(42)).unwrap();
+    let second = client().random_highlights(3, None, Some(42)).unwrap();
+
+    assert_eq!(first.len(), 3);
+
+    assert_eq!(
+      first.iter().map(|h| h.id).collect::<Vec<_>>(),
+      second.iter().map(|h| h.id).collect::<Vec<_>>()
+    );
+
+    mocks.iter().for_each(|mock| mock.assert());
+  }
+
+  #[test]
+  fn random_highlights_skips_deleted() {
+    let _count = mock("GET", "/api/v2/highlights?page=1&page_size=1&book_id=1")
+      .with_status(200)
+      .with_body(highlights_page_body(3, 1))
+      .create();
+
+    let _deleted =
+      mock("GET", "/api/v2/highlights?page=2&page_size=1&book_id=1")
+        .with_status(404)
+        .create();
+
+    let _last = mock("GET", "/api/v2/highlights?page=3&page_size=1&book_id=1")
+      .with_status(200)
+      .with_body(highlights_page_body(3, 3))
+      .create();
+
+    let result = client().random_highlights(3, Some(1), Some(7)).unwrap();
+
+    let mut ids = result.iter().map(|h| h.id).collect::<Vec<_>>();
+
+    ids.sort_unstable();
+
+    assert_eq!(ids, vec![1, 3]);
+  }
+
+  #[test]
+  fn export_highlights_jsonl() {
+    let _first = mock("GET", "/api/v2/highlights?page=1&page_size=1000")
+      .with_status(200)
+      .with_body(format!(
+        r#" {{ "count": 3, "next": "next", "previous": null, "results": [{}, {}] }} "#,
+        get_highlight_as_string(),
+        get_highlight_as_string()
+      ))
+      .create();
+
+    let _second = mock("GET", "/api/v2/highlights?page=2&page_size=1000")
+      .with_status(200)
+      .with_body(highlights_page_body(3, 1))
+      .create();
+
+    let mut output = Vec::new();
+
+    let stats = client().export_highlights_jsonl(&mut output).unwrap();
+
+    let lines = String::from_utf8(output).unwrap();
+
+    assert_eq!(stats.pages, 2);
+    assert_eq!(stats.lines, 3);
+    assert_eq!(stats.bytes, lines.len() as u64);
+
+    assert_eq!(lines.lines().count(), 3);
+
+    for line in lines.lines() {
+      assert!(serde_json::from_str::<Highlight>(line).is_ok());
+    }
+  }
+
+  #[test]
+  fn export_books_jsonl_interrupted() {
+    let _first = mock("GET", "/api/v2/books?page=1&page_size=1000")
+      .with_status(200)
+      .with_body(format!(
+        r#" {{ "count": 2, "next": "next", "previous": null, "results": [{}] }} "#,
+        get_book_as_string()
+      ))
+      .create();
+
+    let _second = mock("GET", "/api/v2/books?page=2&page_size=1000")
+      .with_status(500)
+      .create();
+
+    let mut output = Vec::new();
+
+    let result = client().export_books_jsonl(&mut output);
+
+    assert!(matches!(result, Err(Error::Export { lines: 1, .. })));
+    assert_eq!(String::from_utf8(output).unwrap().lines().count(), 1);
+  }
+
+  #[test]
+  fn timeout() {
+    let _m = mock("GET", "/api/v2/books/1")
+      .with_status(200)
+      .with_body_from_fn(|_| {
+        std::thread::sleep(std::time::Duration::from_millis(200));
+        Ok(())
+      })
+      .create();
+
+    let error = ClientBuilder::new("token")
+      .timeout(Duration::from_millis(10))
+      .build()
+      .unwrap()
+      .book(1)
+      .unwrap_err();
+
+    assert!(matches!(error, Error::Timeout { .. }), "{}", error);
+  }
+
+  #[test]
+  fn copy_highlights_to() {
+    let _book = mock("GET", "/api/v2/books/1")
+      .with_status(200)
+      .with_body(
+        serde_json::to_string(&Book {
+          id: 1,
+          title: "Quotes".into(),
+          author: Some("Anonymous".into()),
+          ..Default::default()
+        })
+        .unwrap(),
+      )
+      .create();
+
+    let _highlights = mock(
+      "GET",
+      "/api/v2/highlights?page=1&page_size=1000&book_id=1",
+    )
+    .with_status(200)
+    .with_body(format!(
+      r#" {{ "count": 1, "next": null, "previous": null, "results": [{}] }} "#,
+      serde_json::to_string(&Highlight {
+        text: "hello world!".into(),
+        ..Default::default()
+      })
+      .unwrap()
+    ))
+    .create();
+
+    let create = mock("POST", "/api/v2/highlights")
+      .match_body(Matcher::Json(serde_json::json!({
+        "highlights": [{
+          "text": "hello world!",
+          "title": "Quotes",
+          "author": "Anonymous"
+        }]
+      })))
+      .with_status(200)
+      .with_body(
+        serde_json::to_string(&vec![HighlightCreateResponse {
+          modified_highlights: vec![5],
+          ..Default::default()
+        }])
+        .unwrap(),
+      )
+      .create();
+
+    let result = client().copy_highlights_to(&client(), 1).unwrap();
+
+    assert_eq!(result, vec![5]);
+
+    create.assert();
+  }
+
+  #[test]
+  fn page_fetched_events() {
+    let mocks = (1..=3)
+      .map(|page| {
+        mock(
+          "GET",
+          format!("/api/v2/highlights?page={}&page_size=1000", page).as_str(),
+        )
+        .with_status(200)
+        .with_body(format!(
+          r#" {{ "count": 3, "next": {}, "previous": null, "results": [{}] }} "#,
+          if page < 3 { r#""next""# } else { "null" },
+          get_highlight_as_string()
+        ))
+        .create()
+      })
+      .collect::<Vec<_>>();
+
+    let events = std::sync::Arc::new(Mutex::new(Vec::new()));
+
+    let collector = events.clone();
+
+    client()
+      .on_event(move |event| collector.lock().unwrap().push(event))
+      .export_highlights_jsonl(Vec::new())
+      .unwrap();
+
+    assert_eq!(
+      *events.lock().unwrap(),
+      vec![
+        Event::PageFetched { page: 1, items: 1 },
+        Event::PageFetched { page: 2, items: 1 },
+        Event::PageFetched { page: 3, items: 1 },
+        Event::Done { total: 3 },
+      ]
+    );
+
+    mocks.iter().for_each(|mock| mock.assert());
+  }
+
+  #[test]
+  fn export() {
+    let _first = mock(
+      "GET",
+      "/api/v2/export?updatedAfter=2022-10-01T00%3A00%3A00Z&category=articles",
+    )
+    .with_status(200)
+    .with_body(format!(
+      r#" {{ "count": 2, "nextPageCursor": "abc", "results": [{}] }} "#,
+      serde_json::to_string(&ExportBook::default()).unwrap()
+    ))
+    .create();
+
+    let _second = mock(
+      "GET",
+      "/api/v2/export?updatedAfter=2022-10-01T00%3A00%3A00Z&category=articles&pageCursor=abc",
+    )
+    .with_status(200)
+    .with_body(format!(
+      r#" {{ "count": 2, "nextPageCursor": null, "results": [{}] }} "#,
+      serde_json::to_string(&ExportBook::default()).unwrap()
+    ))
+    .create();
+
+    let result = client()
+      .export(Some("2022-10-01T00:00:00Z"), Some(Category::Articles))
+      .unwrap();
+
+    assert_eq!(result.len(), 2);
+  }
+
+  #[test]
+  fn daily_review() {
+    let transport = FakeTransport::new()
+      .respond(
+        "GET",
+        "/review",
+        200,
+        r#"{
+          "review_id": 3,
+          "review_url": "https://readwise.io/reviews/3",
+          "review_completed": true,
+          "highlights": []
+        }"#,
+      )
+      .respond("GET", "/review", 204, "")
+      .respond("GET", "/review", 500, "");
+
+    let client =
+      Client::with_transport("token", Box::new(transport.clone())).unwrap();
+
+    let review = client.daily_review().unwrap();
+
+    assert!(review.is_empty());
+    assert!(review.review_completed);
+    assert_eq!(review.review_id, 3);
+
+    let review = client.daily_review().unwrap();
+
+    assert!(review.is_empty());
+    assert!(!review.review_completed);
+
+    assert!(matches!(
+      client.daily_review(),
+      Err(Error::BadRequest { status, .. })
+        if status == StatusCode::INTERNAL_SERVER_ERROR
+    ));
+  }
+
+  #[test]
+  fn set_highlight_color() {
+    let transport = FakeTransport::new().respond(
+      "PATCH",
+      "/highlights/1",
+      200,
+      &serde_json::to_string(&Highlight {
+        id: 1,
+        color: "blue".into(),
+        ..Default::default()
+      })
+      .unwrap(),
+    );
+
+    let highlight =
+      Client::with_transport("token", Box::new(transport.clone()))
+        .unwrap()
+        .set_highlight_color(1, Color::Blue)
+        .unwrap();
+
+    assert_eq!(highlight.color, "blue");
+
+    assert_eq!(
+      transport.requests()[0].body,
+      Some(serde_json::json!({ "color": "blue" }))
+    );
+  }
+
+  #[test]
+  fn empty_write_responses() {
+    let highlight = serde_json::to_string(&Highlight {
+      id: 1,
+      note: "revisit".into(),
+      ..Default::default()
+    })
+    .unwrap();
+
+    let transport = FakeTransport::new()
+      .respond("PATCH", "/highlights/1", 204, "")
+      .respond("GET", "/highlights/1", 200, &highlight)
+      .respond("POST", "/highlights/1/tags", 204, "");
+
+    let client =
+      Client::with_transport("token", Box::new(transport.clone())).unwrap();
+
+    let highlight = client
+      .update_highlight(
+        1,
+        HighlightUpdate {
+          note: Some("revisit".into()),
+          ..Default::default()
+        },
+      )
+      .unwrap();
+
+    assert_eq!(highlight.note, "revisit");
+
+    assert_eq!(
+      client
+        .post_json("/highlights/1/tags", &serde_json::json!({ "name": "a" }))
+        .unwrap(),
+      serde_json::Value::Null
+    );
+
+    assert_eq!(
+      transport
+        .requests()
+        .iter()
+        .map(|request| request.method.as_str())
+        .collect::<Vec<&str>>(),
+      ["PATCH", "GET", "POST"]
+    );
+  }
+
+  #[test]
+  fn highlight_pages() {
+    let page = |id, next: Option<&str>| {
+      serde_json::to_string(&HighlightsResponse {
+        count: 3,
+        next: next.map(String::from),
+        previous: None,
+        results: vec![Highlight {
+          id,
+          ..Default::default()
+        }],
+      })
+      .unwrap()
+    };
+
+    let transport = FakeTransport::new()
+      .respond("GET", "/highlights", 200, &page(1, Some("page 2")))
+      .respond("GET", "/highlights", 200, &page(2, None))
+      .respond("GET", "/highlights", 200, &page(1, Some("page 2")))
+      .respond("GET", "/highlights", 500, "");
+
+    let client =
+      Client::with_transport("token", Box::new(transport.clone())).unwrap();
+
+    let pages = client
+      .highlight_pages()
+      .map(|page| page.unwrap().results[0].id)
+      .collect::<Vec<u64>>();
+
+    assert_eq!(pages, [1, 2]);
+
+    let mut pages = client.highlight_pages();
+
+    assert!(pages.next().unwrap().is_ok());
+    assert!(pages.next().unwrap().is_err());
+    assert!(pages.next().is_none());
+
+    assert_eq!(
+      transport
+        .requests()
+        .iter()
+        .map(|request| request.query[0].1.as_str())
+        .collect::<Vec<&str>>(),
+      ["1", "2", "1", "2"]
+    );
+  }
+
+  #[test]
+  fn highlights_from_url() {
+    let transport = FakeTransport::new().respond(
+      "GET",
+      "/highlights",
+      200,
+      &highlights_page_body(3, 3),
+    );
+
+    let client =
+      Client::with_transport("token", Box::new(transport.clone())).unwrap();
+
+    let page = client
+      .highlights_from_url(
+        "https://readwise.io/api/v2/highlights/?page=3&page_size=1",
+      )
+      .unwrap();
+
+    assert_eq!(page.results[0].id, 3);
+
+    assert_eq!(
+      transport.requests()[0].query,
+      [
+        ("page".to_string(), "3".to_string()),
+        ("page_size".to_string(), "1".to_string()),
+      ]
+    );
+
+    for url in [
+      "https://readwise.io.example.com/api/v2/highlights/?page=2",
+      "https://example.com/api/v2/highlights/?page=2",
+      "http://readwise.io/api/v2/highlights/?page=2",
+      "https://readwise.io/api/v2/books/?page=2",
+      "/highlights?page=2",
+    ] {
+      assert!(
+        matches!(
+          client.highlights_from_url(url),
+          Err(Error::InvalidPageUrl { url: invalid, .. }) if invalid == url
+        ),
+        "{}",
+        url
+      );
+    }
+
+    assert_eq!(transport.requests().len(), 1);
+  }
+
+  #[test]
+  fn highlights_updated_between() {
+    let highlight = |id, updated: &str| Highlight {
+      id,
+      updated: updated.into(),
+      ..Default::default()
+    };
+
+    let transport = FakeTransport::new().respond(
+      "GET",
+      "/highlights",
+      200,
+      &serde_json::to_string(&HighlightsResponse {
+        count: 6,
+        next: None,
+        previous: None,
+        results: vec![
+          highlight(1, "2022-09-30T23:59:59.999Z"),
+          highlight(2, "2022-10-01T12:00:00Z"),
+          highlight(3, "2022-10-01T00:00:00Z"),
+          highlight(4, "2022-10-01T02:00:00+02:00"),
+          highlight(5, "2022-10-02T00:00:00Z"),
+          highlight(6, ""),
+        ],
+      })
+      .unwrap(),
+    );
+
+    let client =
+      Client::with_transport("token", Box::new(transport.clone())).unwrap();
+
+    let ids = |start, end| {
+      client
+        .highlights_updated_between(start, end)
+        .unwrap()
+        .into_iter()
+        .map(|highlight| highlight.id)
+        .collect::<Vec<u64>>()
+    };
+
+    assert_eq!(ids("2022-10-01", "2022-10-02"), [3, 4, 2]);
+    assert_eq!(ids("2022-10-01", "2022-10-02"), [3, 4, 2]);
+    assert_eq!(ids("2022-10-02", "2022-10-03"), [5]);
+    assert_eq!(ids("2022-09-30", "2022-10-01"), [1]);
+
+    assert_eq!(
+      transport.requests()[0].query,
+      [
+        ("page".to_string(), "1".to_string()),
+        ("page_size".to_string(), "1000".to_string()),
+        (
+          "updated__gt".to_string(),
+          "2022-09-30T23:59:59Z".to_string()
+        ),
+        (
+          "updated__lt".to_string(),
+          "2022-10-02T00:00:01Z".to_string()
+        ),
+      ]
+    );
+
+    assert!(matches!(
+      client.highlights_updated_between("2022-10-01", "tomorrow"),
+      Err(Error::InvalidTimestamp { value }) if value == "tomorrow"
+    ));
+  }
+
+  #[test]
+  fn books_with_new_highlights() {
+    let transport = FakeTransport::new()
+      .respond(
+        "GET",
+        "/books",
+        200,
+        include_str!("../tests/fixtures/books.json"),
+      )
+      .respond(
+        "GET",
+        "/books",
+        200,
+        &serde_json::json!({
+          "count": 4,
+          "next": null,
+          "previous": null,
+          "results": [
+            { "id": 1, "title": "", "category": "books",
+              "last_highlight_at": "2022-10-01T08:00:00Z" },
+            { "id": 3, "title": "", "category": "books",
+              "last_highlight_at": "2022-10-02T00:00:00Z" },
+            { "id": 4, "title": "", "category": "books",
+              "last_highlight_at": "2022-10-01T10:00:00+02:00" },
+            { "id": 5, "title": "", "category": "books",
+              "last_highlight_at": "2022-10-01T00:00:00.500Z" },
+          ],
+        })
+        .to_string(),
+      );
+
+    let client =
+      Client::with_transport("token", Box::new(transport.clone())).unwrap();
+
+    let ids = |since| {
+      client
+        .books_with_new_highlights(since)
+        .unwrap()
+        .into_iter()
+        .map(|book| book.id)
+        .collect::<Vec<u64>>()
+    };
+
+    assert_eq!(ids("2022-09-01T00:00:00Z"), [12824517]);
+    assert_eq!(ids("2022-10-01T00:00:00.500Z"), [3, 1, 4]);
+
+    assert_eq!(
+      transport.requests()[1].query,
+      [
+        ("page".to_string(), "1".to_string()),
+        ("page_size".to_string(), "1000".to_string()),
+        (
+          "last_highlight_at__gt".to_string(),
+          "2022-10-01T00:00:00Z".to_string()
+        ),
+      ]
+    );
+
+    assert!(matches!(
+      client.books_with_new_highlights("yesterday"),
+      Err(Error::InvalidTimestamp { value }) if value == "yesterday"
+    ));
+  }
+
+  #[test]
+  fn highlighted_between() {
+    let highlight = |id, highlighted_at: Option<&str>| Highlight {
+      id,
+      highlighted_at: highlighted_at.map(String::from),
+      ..Default::default()
+    };
+
+    let transport = FakeTransport::new().respond(
+      "GET",
+      "/highlights",
+      200,
+      &serde_json::to_string(&HighlightsResponse {
+        count: 5,
+        next: None,
+        previous: None,
+        results: vec![
+          highlight(1, Some("2022-08-31T23:59:59Z")),
+          highlight(2, Some("2022-09-01T00:00:00Z")),
+          highlight(3, Some("2022-09-30T20:00:00-04:00")),
+          highlight(4, Some("2022-10-01T00:00:00Z")),
+          highlight(5, None),
+        ],
+      })
+      .unwrap(),
+    );
+
+    let client =
+      Client::with_transport("token", Box::new(transport.clone())).unwrap();
+
+    let ids = |include_undated| {
+      client
+        .highlighted_between("2022-09-01", "2022-10-01", include_undated)
+        .unwrap()
+        .into_iter()
+        .map(|highlight| highlight.id)
+        .collect::<Vec<u64>>()
+    };
+
+    assert_eq!(ids(false), [2, 3, 4]);
+    assert_eq!(ids(true), [2, 3, 4, 5]);
+
+    assert!(matches!(
+      client.highlighted_between("yesterday", "2022-10-01", false),
+      Err(Error::InvalidTimestamp { value }) if value == "yesterday"
+    ));
+
+    assert_eq!(transport.requests().len(), 2);
+  }
+
+  #[test]
+  fn export_highlighted() {
+    let highlight = |id, highlighted_at: Option<&str>| ExportHighlight {
+      id,
+      highlighted_at: highlighted_at.map(String::from),
+      ..Default::default()
+    };
+
+    let transport = FakeTransport::new().respond(
+      "GET",
+      "/export",
+      200,
+      &serde_json::to_string(&ExportResponse {
+        count: 2,
+        next_page_cursor: None,
+        results: vec![
+          ExportBook {
+            user_book_id: 1,
+            highlights: vec![
+              highlight(1, Some("2022-06-30T23:59:59Z")),
+              highlight(2, Some("2022-07-01T00:00:00.5Z")),
+              highlight(3, Some("2022-09-30T20:00:00-05:00")),
+              highlight(4, None),
+            ],
+            ..Default::default()
+          },
+          ExportBook {
+            user_book_id: 2,
+            highlights: vec![highlight(5, Some("2021-01-01T00:00:00Z"))],
+            ..Default::default()
+          },
+        ],
+      })
+      .unwrap(),
+    );
+
+    let client =
+      Client::with_transport("token", Box::new(transport.clone())).unwrap();
+
+    let books = client
+      .export_highlighted(Some("2022-07-01"), Some("2022-10-01"))
+      .unwrap();
+
+    assert_eq!(books.len(), 1);
+
+    assert_eq!(
+      books[0]
+        .highlights
+        .iter()
+        .map(|highlight| highlight.id)
+        .collect::<Vec<u64>>(),
+      [2]
+    );
+
+    assert_eq!(transport.requests()[0].query, []);
+
+    assert!(matches!(
+      client.export_highlighted(Some("last week"), None),
+      Err(Error::InvalidTimestamp { value }) if value == "last week"
+    ));
+
+    assert_eq!(transport.requests().len(), 1);
+  }
+
+  #[test]
+  fn create_highlights_in_batches() {
+    let created = |ids: &[u64]| {
+      serde_json::json!([{
+        "id": 1,
+        "title": "Quotes",
+        "category": "books",
+        "num_highlights": ids.len(),
+        "updated": "",
+        "cover_image_url": "",
+        "highlights_url": "",
+        "modified_highlights": ids,
+      }])
+      .to_string()
+    };
+
+    let transport = FakeTransport::new()
+      .respond("POST", "/highlights", 200, &created(&[1, 2]))
+      .respond("POST", "/highlights", 200, &created(&[3, 4]))
+      .respond("POST", "/highlights", 200, &created(&[5]));
+
+    let client = ClientBuilder::new("token")
+      .create_batch_size(2)
+      .transport(Box::new(transport.clone()))
+      .build()
+      .unwrap();
+
+    let report = client
+      .create_highlights_with(
+        (1..=5)
+          .map(|i| HighlightCreate {
+            text: i.to_string(),
+            ..Default::default()
+          })
+          .collect(),
+        CreateOptions::default(),
+      )
+      .unwrap();
+
+    assert_eq!(report.created, [1, 2, 3, 4, 5]);
+
+    assert_eq!(
+      transport
+        .requests()
+        .iter()
+        .map(|request| request.body.as_ref().unwrap()["highlights"]
+          .as_array()
+          .unwrap()
+          .len())
+        .collect::<Vec<usize>>(),
+      [2, 2, 1]
+    );
+
+    assert_eq!(
+      ClientBuilder::new("token")
+        .create_batch_size(0)
+        .create_batch_size,
+      1
+    );
+  }
+
+  #[test]
+  fn created_highlights_have_urls() {
+    let transport = FakeTransport::new()
+      .respond(
+        "POST",
+        "/highlights",
+        200,
+        &serde_json::json!([{
+          "id": 1,
+          "title": "Quotes",
+          "category": "books",
+          "num_highlights": 1,
+          "updated": "",
+          "cover_image_url": "",
+          "highlights_url": "",
+          "modified_highlights": [5],
+        }])
+        .to_string(),
+      )
+      .respond(
+        "GET",
+        "/highlights/5",
+        200,
+        &serde_json::to_string(&Highlight {
+          id: 5,
+          ..Default::default()
+        })
+        .unwrap(),
+      );
+
+    let highlights = Client::with_transport("token", Box::new(transport))
+      .unwrap()
+      .create_highlights(vec![HighlightCreate {
+        text: "hello world!".into(),
+        ..Default::default()
+      }])
+      .unwrap();
+
+    assert_eq!(
+      highlights[0].highlight_url.as_deref(),
+      Some("https://readwise.io/open/5")
+    );
+  }
+
+  #[test]
+  fn create_highlights_sends_highlight_url() {
+    let create = mock("POST", "/api/v2/highlights")
+      .match_body(Matcher::Json(serde_json::json!({
+        "highlights": [{
+          "text": "hello world!",
+          "highlight_url": "https://example.com/highlights/1"
+        }]
+      })))
+      .with_status(200)
+      .with_body("[]")
+      .create();
+
+    let result = client().create_highlights(vec![HighlightCreate {
+      text: "hello world!".into(),
+      highlight_url: Some("https://example.com/highlights/1".into()),
+      ..Default::default()
+    }]);
+
+    assert!(result.is_ok(), "{}", result.err().unwrap().to_string());
+
+    create.assert();
+  }
+
+  #[test]
+  fn response_meta() {
+    let _m = mock("GET", "/api/v2/books/1")
+      .with_status(403)
+      .with_header("x-request-id", "abc")
+      .create();
+
+    let client = client();
+
+    let error = client.book(1).unwrap_err();
+
+    let meta = match error {
+      Error::BadRequest { meta, .. } => meta,
+      error => panic!("unexpected error: {}", error),
+    };
+
+    assert_eq!(meta.status, StatusCode::FORBIDDEN);
+    assert_eq!(meta.headers["x-request-id"], "abc");
+    assert!(meta.url.ends_with("/api/v2/books/1"));
+
+    assert_eq!(
+      client.last_response_meta().unwrap().status,
+      StatusCode::FORBIDDEN
+    );
+  }
+
+  #[test]
+  fn from_env_var() {
+    let m = mock("GET", "/api/v2/auth")
+      .match_header("Authorization", "Token environment")
+      .with_status(204)
+      .create();
+
+    env::set_var("READWISE_CLIENT_TEST_TOKEN", "environment");
+
+    assert!(Client::from_env_var("READWISE_CLIENT_TEST_TOKEN").is_ok());
+
+    m.assert();
+  }
+
+  #[test]
+  fn from_env_var_missing() {
+    let result = Client::from_env_var("READWISE_CLIENT_TEST_MISSING");
+
+    assert!(matches!(
+      result,
+      Err(Error::EnvironmentVariable { name, .. })
+        if name == "READWISE_CLIENT_TEST_MISSING"
+    ));
+  }
+
+  #[test]
+  fn create_highlights_skip_existing() {
+    let _books = mock("GET", "/api/v2/books?page=1&page_size=1000")
+      .with_status(200)
+      .with_body(format!(
+        r#" {{ "count": 1, "next": null, "previous": null, "results": [{}] }} "#,
+        serde_json::to_string(&Book {
+          id: 1,
+          title: "Quotes".into(),
+          ..Default::default()
+        })
+        .unwrap()
+      ))
+      .create();
+
+    let _highlights = mock(
+      "GET",
+      "/api/v2/highlights?page=1&page_size=1000&book_id=1",
+    )
+    .with_status(200)
+    .with_body(format!(
+      r#" {{ "count": 1, "next": null, "previous": null, "results": [{}] }} "#,
+      serde_json::to_string(&Highlight {
+        text: "Hello  world!".into(),
+        ..Default::default()
+      })
+      .unwrap()
+    ))
+    .create();
+
+    let create = mock("POST", "/api/v2/highlights")
+      .match_body(Matcher::Json(serde_json::json!({
+        "highlights": [{ "text": "something new" }]
+      })))
+      .with_status(200)
+      .with_body(
+        serde_json::to_string(&vec![HighlightCreateResponse {
+          modified_highlights: vec![2],
+          ..Default::default()
+        }])
+        .unwrap(),
+      )
+      .create();
+
+    let report = client()
+      .create_highlights_with(
+        vec![
+          HighlightCreate {
+            text: "hello world".into(),
+            ..Default::default()
+          },
+          HighlightCreate {
+            text: "something new".into(),
+            ..Default::default()
+          },
+        ],
+        CreateOptions {
+          skip_existing: true,
+        },
+      )
+      .unwrap();
+
+    assert_eq!(report.created, vec![2]);
+    assert_eq!(report.skipped, 1);
+    assert_eq!(report.existing, 0);
+
+    create.assert();
+  }
+
+  #[test]
+  fn create_highlights_with_all_existing() {
+    let transport = FakeTransport::new().respond(
+      "POST",
+      "/highlights",
+      200,
+      &serde_json::to_string(&vec![HighlightCreateResponse::default()])
+        .unwrap(),
+    );
+
+    let client = Client::with_transport("token", Box::new(transport)).unwrap();
+
+    let report = client
+      .create_highlights_with(
+        vec![
+          HighlightCreate {
+            text: "one".into(),
+            ..Default::default()
+          },
+          HighlightCreate {
+            text: "two".into(),
+            ..Default::default()
+          },
+        ],
+        CreateOptions::default(),
+      )
+      .unwrap();
+
+    assert_eq!(
+      report,
+      CreateReport {
+        created: Vec::new(),
+        skipped: 0,
+        existing: 2,
+      }
+    );
+  }
+
+  #[cfg(feature = "gzip")]
+  #[test]
+  fn gzip_responses() {
+    use flate2::{write::GzEncoder, Compression};
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+
+    encoder.write_all(br#"{ "review_id": 2 }"#).unwrap();
+
+    let _m = mock("GET", "/api/v2/gzip")
+      .match_header("Accept-Encoding", Matcher::Regex("gzip".into()))
+      .with_status(200)
+      .with_header("Content-Encoding", "gzip")
+      .with_body(encoder.finish().unwrap())
+      .create();
+
+    assert_eq!(client().get_raw("/gzip").unwrap()["review_id"], 2);
+  }
+
+  #[test]
+  fn get_raw() {
+    let _m = mock("GET", "/api/v2/review")
+      .with_status(200)
+      .with_body(r#"{ "review_id": 1, "highlights": [] }"#)
+      .create();
+
+    let result = client().get_raw("/review").unwrap();
+
+    assert_eq!(result["review_id"], 1);
+  }
+
+  #[test]
+  fn post_raw() {
+    let _m = mock("POST", "/api/v2/unmodeled")
+      .match_body(Matcher::Json(serde_json::json!({ "key": "value" })))
+      .with_status(200)
+      .with_body(r#"{ "ok": true }"#)
+      .create();
+
+    let result = client()
+      .post_raw("/unmodeled", serde_json::json!({ "key": "value" }))
+      .unwrap();
+
+    assert_eq!(result["ok"], true);
+  }
+
+  #[test]
+  fn get_paged() {
+    #[derive(Deserialize)]
+    struct Document {
+      id: u64,
+      title: String,
+    }
+
+    let transport = FakeTransport::new().respond(
+      "GET",
+      "/documents",
+      200,
+      r#"{
+        "count": 2,
+        "next": "https://readwise.io/api/v2/documents?page=2",
+        "previous": null,
+        "results": [{ "id": 7, "title": "Beta", "extra": true }]
+      }"#,
+    );
+
+    let client =
+      Client::with_transport("token", Box::new(transport.clone())).unwrap();
+
+    let page = client
+      .get_paged::<Document>("/documents", &[("page", "1"), ("q", "a&b")])
+      .unwrap();
+
+    assert_eq!(page.count, 2);
+    assert!(page.next.is_some());
+    assert_eq!(page.results[0].id, 7);
+    assert_eq!(page.results[0].title, "Beta");
+
+    let requests = transport.requests();
+
+    assert_eq!(requests[0].endpoint(), "/documents?page=1&q=a%26b");
+    assert_eq!(requests[0].headers[header::AUTHORIZATION], "Token token");
+  }
+
+  #[test]
+  fn get_json_appends_params() {
+    let transport = FakeTransport::new().respond("GET", "/beta", 200, "{}");
+
+    let client =
+      Client::with_transport("token", Box::new(transport.clone())).unwrap();
+
+    client.get_json("/beta?a=1", &[("b", "2")]).unwrap();
+
+    assert_eq!(transport.requests()[0].endpoint(), "/beta?a=1&b=2");
+  }
+
+  #[test]
+  fn patch_json_and_delete_path() {
+    let transport = FakeTransport::new()
+      .respond("PATCH", "/beta/1", 200, r#"{ "id": 1 }"#)
+      .respond("DELETE", "/beta/1", 204, "")
+      .respond("DELETE", "/beta/2", 404, "");
+
+    let client =
+      Client::with_transport("token", Box::new(transport.clone())).unwrap();
+
+    let result = client
+      .patch_json("/beta/1", &serde_json::json!({ "note": "revisit" }))
+      .unwrap();
+
+    assert_eq!(result["id"], 1);
+    assert_eq!(
+      transport.requests()[0].body,
+      Some(serde_json::json!({ "note": "revisit" }))
+    );
+
+    assert_eq!(
+      client.delete_path("/beta/1").unwrap(),
+      DeleteOutcome::Deleted
+    );
+    assert_eq!(
+      client.delete_path("/beta/2").unwrap(),
+      DeleteOutcome::NotFound
+    );
+  }
+
+  #[test]
+  fn update_book() {
+    let _m = mock("PATCH", "/api/v2/books/1")
+      .match_body(Matcher::Json(serde_json::json!({ "author": "John Smith" })))
+      .with_status(200)
+      .with_body(get_book_as_string())
+      .create();
+
+    let result = client().update_book(
+      1,
+      BookUpdate {
+        author: Some("John Smith".into()),
+        ..Default::default()
+      },
+    );
+
+    assert!(result.is_ok(), "{}", result.err().unwrap().to_string());
+  }
+
+  #[test]
+  fn update_book_not_editable() {
+    let _m = mock("PATCH", "/api/v2/books/1")
+      .with_status(400)
+      .with_body(r#"{ "cover_image_url": ["This field cannot be edited."] }"#)
+      .create();
+
+    let result = client().update_book(
+      1,
+      BookUpdate {
+        cover_image_url: Some("https://example.com/cover.png".into()),
+        ..Default::default()
+      },
+    );
+
+    match result {
+      Err(Error::Api {
+        status, message, ..
+      }) => {
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(message, "cover_image_url: This field cannot be edited.");
+      }
+      result => panic!("unexpected result: {:?}", result),
+    }
+  }
+
+  #[test]
+  fn books_with() {
+    let _m = mock("GET", "/api/v2/books?page=2&category=articles")
+      .with_status(200)
+      .with_body(format!(
+        r#" {{ "count": 1, "next": null, "previous": null, "results": [{}] }} "#,
+        &get_book_as_string()
+      ))
+      .create();
+
+    let result = client()
+      .books_with(&BookFilter::new().page(2).category(Category::Articles));
+
+    assert_eq!(result.unwrap().count, 1);
+  }
+
+  #[test]
+  fn cache_books() {
+    let m = mock("GET", "/api/v2/books/7")
+      .with_status(200)
+      .with_body(get_book_as_string())
+      .expect(2)
+      .create();
+
+    let _update = mock("PATCH", "/api/v2/books/7")
+      .with_status(200)
+      .with_body(get_book_as_string())
+      .create();
+
+    let client = client().cache_books(1);
+
+    client.book(7).unwrap();
+    client.book(7).unwrap();
+    client.update_book(7, BookUpdate::default()).unwrap();
+    client.book(7).unwrap();
+
+    m.assert();
+  }
+
+  #[test]
+  fn refresh_book() {
+    let book = |num_highlights| {
+      serde_json::to_string(&Book {
+        id: 1,
+        num_highlights,
+        ..Default::default()
+      })
+      .unwrap()
+    };
+
+    let transport = FakeTransport::new()
+      .respond("GET", "/books/1", 200, &book(1))
+      .respond("GET", "/books/1", 200, &book(2));
+
+    let client = Client::with_transport("token", Box::new(transport.clone()))
+      .unwrap()
+      .cache_books(1);
+
+    assert_eq!(client.book(1).unwrap().num_highlights, 1);
+    assert_eq!(client.book(1).unwrap().num_highlights, 1);
+    assert_eq!(client.refresh_book(1).unwrap().num_highlights, 2);
+    assert_eq!(client.book(1).unwrap().num_highlights, 2);
+
+    assert_eq!(transport.requests().len(), 2);
+  }
+
+  #[test]
+  fn export_to_file_resumes() {
+    let page = |cursor: Option<&str>, next: Option<&str>| {
+      let path = match cursor {
+        Some(cursor) => format!("/api/v2/export?pageCursor={}", cursor),
+        None => "/api/v2/export".into(),
+      };
+
+      mock("GET", path.as_str())
+        .with_status(200)
+        .with_body(
+          serde_json::to_string(&ExportResponse {
+            count: 4,
+            next_page_cursor: next.map(String::from),
+            results: vec![ExportBook {
+              title: cursor.unwrap_or("1").into(),
+              ..Default::default()
+            }],
+          })
+          .unwrap(),
+        )
+        .expect(1)
+        .create()
+    };
+
+    let directory = tempfile::tempdir().unwrap();
+    let path = directory.path().join("export.jsonl");
+    let sidecar = directory.path().join("export.jsonl.cursor");
+
+    let first = page(None, Some("2"));
+    let second = page(Some("2"), Some("3"));
+
+    let failure = mock("GET", "/api/v2/export?pageCursor=3")
+      .with_status(500)
+      .create();
+
+    let result = client().export_to_file(&path, None);
+
+    assert!(matches!(result, Err(Error::Export { lines: 2, .. })));
+
+    let checkpoint =
+      serde_json::from_slice::<ExportCheckpoint>(&fs::read(&sidecar).unwrap())
+        .unwrap();
+
+    assert_eq!(
+      checkpoint,
+      ExportCheckpoint {
+        cursor: "3".into(),
+        length: fs::metadata(&path).unwrap().len(),
+        updated_after: None,
+      }
+    );
+
+    drop(failure);
+
+    // Part of the next page, as left by a crash while it was being written
+    OpenOptions::new()
+      .append(true)
+      .open(&path)
+      .unwrap()
+      .write_all(br#"{"user_book_id":0,"title":"3","#)
+      .unwrap();
+
+    assert!(matches!(
+      client().export_to_file(&path, Some("2022-10-01")),
+      Err(Error::ExportResumeMismatch { started: None, .. })
+    ));
+
+    let third = page(Some("3"), Some("4"));
+    let fourth = page(Some("4"), None);
+
+    let summary = client().export_to_file(&path, None).unwrap();
+
+    assert_eq!(
+      summary,
+      ExportSummary {
+        pages: 2,
+        items: 2,
+        resumed: true,
+      }
+    );
+
+    let titles = fs::read_to_string(&path)
+      .unwrap()
+      .lines()
+      .map(|line| serde_json::from_str::<ExportBook>(line).unwrap().title)
+      .collect::<Vec<String>>();
+
+    assert_eq!(titles, ["1", "2", "3", "4"]);
+    assert!(!sidecar.exists());
+
+    for mock in [first, second, third, fourth] {
+      mock.assert();
+    }
+  }
+
+  #[test]
+  fn rate_limited_retries() {
+    let m = mock("GET", "/api/v2/highlights/429")
+      .with_status(429)
+      .with_header("Retry-After", "0")
+      .expect(3)
+      .create();
+
+    let events = std::sync::Arc::new(Mutex::new(Vec::new()));
+
+    let client = client().retries(2).on_event({
+      let events = events.clone();
+      move |event| events.lock().unwrap().push(event)
+    });
+
+    assert!(matches!(
+      client.highlight(429),
+      Err(Error::RateLimited {
+        retry_after: Some(Duration::ZERO),
+        ..
+      })
+    ));
+
+    m.assert();
+
+    assert_eq!(
+      events.lock().unwrap().last(),
+      Some(&Event::Retrying {
+        attempt: 2,
+        after: Duration::ZERO,
+      })
+    );
+  }
+
+  #[test]
+  fn retry_budget() {
+    let transport =
+      FakeTransport::new().respond("GET", "/highlights/500", 500, "");
+
+    let client = ClientBuilder::new("token")
+      .retries(2)
+      .backoff(Duration::ZERO, Duration::ZERO)
+      .retry_budget(RetryBudget::new().retries(3))
+      .transport(Box::new(transport.clone()))
+      .build()
+      .unwrap();
+
+    let requests = |client: &Client| {
+      let before = transport.requests().len();
+      assert!(client.highlight(500).is_err());
+      transport.requests().len() - before
+    };
+
+    assert_eq!(requests(&client), 3);
+    assert_eq!(requests(&client.clone()), 2);
+    assert_eq!(requests(&client), 1);
+
+    let run = client.clone().retry_budget(RetryBudget::new().retries(1));
+
+    assert_eq!(requests(&run), 2);
+    assert_eq!(requests(&run), 1);
+  }
+
+  /// Times out on its first request, against a listener that never answers,
+  /// and returns a highlight afterwards
+  #[derive(Clone, Default)]
+  struct TimeoutOnceTransport {
+    requests: std::sync::Arc<AtomicU64>,
+  }
+
+  impl Transport for TimeoutOnceTransport {
+    fn execute(&self, request: ApiRequest) -> Result<ApiResponse> {
+      if self.requests.fetch_add(1, Ordering::Relaxed) == 0 {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+
+        return Err(
+          blocking::Client::builder()
+            .timeout(Duration::from_millis(50))
+            .build()?
+            .get(format!("http://{}", listener.local_addr().unwrap()))
+            .send()
+            .unwrap_err()
+            .into(),
+        );
+      }
+
+      Ok(ApiResponse {
+        status: StatusCode::OK,
+        headers: header::HeaderMap::new(),
+        url: request.path,
+        body: get_highlight_as_string().into_bytes(),
+      })
+    }
+  }
+
+  #[test]
+  fn timeouts_retried() {
+    let transport = TimeoutOnceTransport::default();
+
+    let events = std::sync::Arc::new(Mutex::new(Vec::new()));
+
+    let client = ClientBuilder::new("token")
+      .retries(1)
+      .backoff(Duration::ZERO, Duration::ZERO)
+      .transport(Box::new(transport.clone()))
+      .on_event({
+        let events = events.clone();
+        move |event| events.lock().unwrap().push(event)
+      })
+      .build()
+      .unwrap();
+
+    assert!(client.highlight(1).is_ok());
+    assert_eq!(transport.requests.load(Ordering::Relaxed), 2);
+
+    assert_eq!(
+      *events.lock().unwrap(),
+      [Event::Retrying {
+        attempt: 1,
+        after: Duration::ZERO,
+      }]
+    );
+
+    transport.requests.store(0, Ordering::Relaxed);
+
+    let client = ClientBuilder::new("token")
+      .transport(Box::new(transport))
+      .build()
+      .unwrap();
+
+    assert!(matches!(client.highlight(1), Err(Error::Timeout { .. })));
+  }
+
+  #[test]
+  fn server_errors_retried_with_backoff() {
+    let transport = FakeTransport::new()
+      .respond("GET", "/highlights/500", 500, "")
+      .respond("GET", "/highlights/500", 503, "")
+      .respond("GET", "/highlights/500", 200, &get_highlight_as_string())
+      .respond("POST", "/highlights", 500, "");
+
+    let events = std::sync::Arc::new(Mutex::new(Vec::new()));
+
+    let client = ClientBuilder::new("token")
+      .retries(3)
+      .backoff(Duration::from_millis(2), Duration::from_millis(3))
+      .transport(Box::new(transport.clone()))
+      .on_event({
+        let events = events.clone();
+        move |event| events.lock().unwrap().push(event)
+      })
+      .build()
+      .unwrap();
+
+    assert!(client.highlight(500).is_ok());
+
+    let waits = events
+      .lock()
+      .unwrap()
+      .iter()
+      .map(|event| match event {
+        Event::Retrying { after, .. } => *after,
+        event => panic!("unexpected event: {:?}", event),
+      })
+      .collect::<Vec<Duration>>();
+
+    assert_eq!(waits.len(), 2);
+    assert!(waits[0] >= Duration::from_millis(1));
+    assert!(waits[0] <= Duration::from_millis(2));
+    assert!(waits[1] >= Duration::from_micros(1500));
+    assert!(waits[1] <= Duration::from_millis(3));
+
+    assert!(client
+      .post_raw("/highlights", serde_json::json!({}))
+      .is_err());
+    assert_eq!(transport.requests().len(), 4);
+  }
+
+  #[test]
+  fn book_highlights_ordered() {
+    let highlights = [(1, 20), (2, 3), (3, 0), (4, 7)]
+      .into_iter()
+      .map(|(id, location)| Highlight {
+        id,
+        location: Some(location),
+        location_type: LocationType::Page,
+        ..Default::default()
+      })
+      .collect::<Vec<Highlight>>();
+
+    let _m = mock("GET", "/api/v2/highlights?page=1&page_size=1000&book_id=63")
+      .with_status(200)
+      .with_body(
+        serde_json::to_string(&HighlightsResponse {
+          count: 4,
+          next: None,
+          previous: None,
+          results: highlights,
+        })
+        .unwrap(),
+      )
+      .create();
+
+    assert_eq!(
+      client()
+        .book_highlights_ordered(63)
+        .unwrap()
+        .iter()
+        .map(|highlight| highlight.id)
+        .collect::<Vec<u64>>(),
+      [2, 4, 1, 3]
+    );
+  }
+
+  #[test]
+  fn favorites() {
+    let transport = FakeTransport::new().respond(
+      "GET",
+      "/highlights",
+      200,
+      &serde_json::to_string(&HighlightsResponse {
+        count: 3,
+        next: None,
+        previous: None,
+        results: [(1, false), (2, true), (3, true)]
+          .into_iter()
+          .map(|(id, is_favorite)| Highlight {
+            id,
+            is_favorite,
+            ..Default::default()
+          })
+          .collect(),
+      })
+      .unwrap(),
+    );
+
+    let client =
+      Client::with_transport("token", Box::new(transport.clone())).unwrap();
+
+    let ids = |highlights: Vec<Highlight>| {
+      highlights.iter().map(|h| h.id).collect::<Vec<u64>>()
+    };
+
+    assert_eq!(ids(client.favorites().unwrap()), [2, 3]);
+
+    let response = client
+      .highlights_with(&HighlightFilter::new().favorite(false))
+      .unwrap();
+
+    assert_eq!(response.count, 3);
+    assert_eq!(ids(response.results), [1]);
+  }
+
+  #[test]
+  fn find_duplicate_highlights() {
+    let book = |user_book_id, highlights: &[(u64, &str)]| ExportBook {
+      user_book_id,
+      highlights: highlights
+        .iter()
+        .map(|&(id, text)| ExportHighlight {
+          id,
+          text: text.into(),
+          book_id: user_book_id,
+          ..Default::default()
+        })
+        .collect(),
+      ..Default::default()
+    };
+
+    let transport = FakeTransport::new().respond(
+      "GET",
+      "/export",
+      200,
+      &serde_json::to_string(&ExportResponse {
+        count: 2,
+        next_page_cursor: None,
+        results: vec![
+          book(1, &[(10, "Same text"), (11, "same  text "), (12, "Other")]),
+          book(2, &[(20, "Same text")]),
+        ],
+      })
+      .unwrap(),
+    );
+
+    let groups = Client::with_transport("token", Box::new(transport))
+      .unwrap()
+      .find_duplicate_highlights(NormalizeOptions {
+        lowercase: true,
+        ..NormalizeOptions::default()
+      })
+      .unwrap();
+
+    assert_eq!(
+      groups
+        .iter()
+        .map(|group| group.iter().map(|h| h.id).collect::<Vec<u64>>())
+        .collect::<Vec<_>>(),
+      [vec![10, 11]]
+    );
+  }
+
+  #[test]
+  fn highlights_grouped_by_book() {
+    let book = |user_book_id, highlights: &[u64]| ExportBook {
+      user_book_id,
+      title: format!("Book {}", user_book_id),
+      highlights: highlights
+        .iter()
+        .map(|&id| ExportHighlight {
+          id,
+          book_id: user_book_id,
+          ..Default::default()
+        })
+        .collect(),
+      ..Default::default()
+    };
+
+    let transport = FakeTransport::new().respond(
+      "GET",
+      "/export",
+      200,
+      &serde_json::to_string(&ExportResponse {
+        count: 2,
+        next_page_cursor: None,
+        results: vec![book(1, &[10, 11]), book(2, &[20])],
+      })
+      .unwrap(),
+    );
+
+    let groups = Client::with_transport("token", Box::new(transport))
+      .unwrap()
+      .highlights_grouped_by_book()
+      .unwrap();
+
+    assert_eq!(groups.len(), 2);
+
+    let (book, highlights) = &groups[&1];
+
+    assert_eq!(book.title, "Book 1");
+    assert_eq!(book.num_highlights, 2);
+    assert_eq!(
+      highlights
+        .iter()
+        .map(|highlight| highlight.id)
+        .collect::<Vec<u64>>(),
+      [10, 11]
+    );
+
+    assert_eq!(groups[&2].1[0].book_id, Some(2));
+  }
+
+  #[test]
+  fn book_with_highlights() {
+    let transport = FakeTransport::new()
+      .respond(
+        "GET",
+        "/books/64",
+        200,
+        &serde_json::to_string(&Book {
+          id: 64,
+          ..Default::default()
+        })
+        .unwrap(),
+      )
+      .respond(
+        "GET",
+        "/highlights",
+        200,
+        &highlights_page_body(1, 5)
+          .replace(r#""next": null"#, r#""next": "2""#),
+      )
+      .respond("GET", "/highlights", 200, &highlights_page_body(1, 6));
+
+    let client =
+      Client::with_transport("token", Box::new(transport.clone())).unwrap();
+
+    let (book, highlights) = client.book_with_highlights(64).unwrap();
+
+    assert_eq!(book.id, 64);
+    assert_eq!(
+      highlights.iter().map(|h| h.id).collect::<Vec<u64>>(),
+      [5, 6]
+    );
+    assert_eq!(
+      transport.requests()[2].endpoint(),
+      "/highlights?page=2&page_size=1000&book_id=64"
+    );
+  }
+
+  #[test]
+  fn debug_redacts_token() {
+    let client = Client::with_transport(
+      "abcdefgh-secret-token-1234",
+      Box::new(FakeTransport::new()),
+    )
+    .unwrap();
+
+    let debug = format!("{:?}", client);
+
+    assert!(!debug.contains("abcdefgh-secret-token-1234"), "{}", debug);
+    assert!(debug.contains("****1234"), "{}", debug);
+  }
+
+  #[test]
+  fn book_highlights() {
+    let _m = mock("GET", "/api/v2/highlights?page=2&book_id=66")
+      .with_status(200)
+      .with_body(highlights_page_body(1, 7))
+      .create();
+
+    let response = client().book_highlights(66, 2).unwrap();
+
+    assert_eq!(response.results[0].id, 7);
+  }
+
+  #[test]
+  fn all_book_highlights() {
+    let _first =
+      mock("GET", "/api/v2/highlights?page=1&page_size=1000&book_id=67")
+        .with_status(200)
+        .with_body(format!(
+          r#" {{ "count": 2, "next": "next", "previous": null, "results": [{}] }} "#,
+          serde_json::to_string(&Highlight {
+            id: 1,
+            ..Default::default()
+          })
+          .unwrap()
+        ))
+        .create();
+
+    let _second =
+      mock("GET", "/api/v2/highlights?page=2&page_size=1000&book_id=67")
+        .with_status(200)
+        .with_body(highlights_page_body(2, 2))
+        .create();
+
+    assert_eq!(
+      client()
+        .all_book_highlights(67)
+        .unwrap()
+        .iter()
+        .map(|highlight| highlight.id)
+        .collect::<Vec<u64>>(),
+      [1, 2]
+    );
+  }
+
+  #[test]
+  fn all_book_highlights_empty() {
+    let _m = mock("GET", "/api/v2/highlights?page=1&page_size=1000&book_id=68")
+      .with_status(200)
+      .with_body(
+        r#" { "count": 0, "next": null, "previous": null, "results": [] } "#,
+      )
+      .create();
+
+    assert!(client().all_book_highlights(68).unwrap().is_empty());
+  }
+
+  #[test]
+  fn delete_book_highlights() {
+    let transport = FakeTransport::new()
+      .respond(
+        "GET",
+        "/highlights",
+        200,
+        &highlights_page_body(3, 7)
+          .replace(r#""next": null"#, r#""next": "2""#),
+      )
+      .respond(
+        "GET",
+        "/highlights",
+        200,
+        &highlights_page_body(3, 8)
+          .replace(r#""next": null"#, r#""next": "3""#),
+      )
+      .respond("GET", "/highlights", 200, &highlights_page_body(3, 9))
+      .respond("DELETE", "/highlights/7", 204, "")
+      .respond("DELETE", "/highlights/8", 404, "")
+      .respond("DELETE", "/highlights/9", 204, "");
+
+    let client =
+      Client::with_transport("token", Box::new(transport.clone())).unwrap();
+
+    assert_eq!(client.delete_book_highlights(65).unwrap(), 2);
+
+    let methods = transport
+      .requests()
+      .iter()
+      .map(|request| request.method.to_string())
+      .collect::<Vec<String>>();
+
+    assert_eq!(methods, ["GET", "GET", "GET", "DELETE", "DELETE", "DELETE"]);
+  }
+
+  #[test]
+  fn create_highlight_tag() {
+    let _m = mock("POST", "/api/v2/highlights/1/tags")
+      .match_body(Matcher::Json(serde_json::json!({ "name": "favorite" })))
+      .with_status(200)
+      .with_body(r#"{ "id": 5, "name": "favorite" }"#)
+      .create();
+
+    assert_eq!(client().create_highlight_tag(1, "favorite").unwrap().id, 5);
+  }
+
+  #[test]
+  fn update_highlight_full() {
+    let tag = |id, name: &str| Tag {
+      id,
+      name: name.into(),
+    };
+
+    let transport = FakeTransport::new()
+      .respond(
+        "PATCH",
+        "/highlights/1",
+        200,
+        &serde_json::to_string(&Highlight {
+          id: 1,
+          note: "revisit".into(),
+          tags: vec![tag(1, "a"), tag(2, "b")],
+          ..Default::default()
+        })
+        .unwrap(),
+      )
+      .respond("DELETE", "/highlights/1/tags/1", 204, "")
+      .respond(
+        "POST",
+        "/highlights/1/tags",
+        200,
+        r#"{"id": 3, "name": "c"}"#,
+      )
+      .respond("POST", "/highlights/1/tags", 400, "");
+
+    let client =
+      Client::with_transport("token", Box::new(transport.clone())).unwrap();
+
+    let report = client
+      .update_highlight_full(1, "revisit", &["b", "c", "c", "d"])
+      .unwrap();
+
+    assert_eq!(
+      transport.requests()[0].body,
+      Some(serde_json::json!({ "note": "revisit" }))
+    );
+
+    let names = |tags: &[Tag]| {
+      tags
+        .iter()
+        .map(|tag| tag.name.clone())
+        .collect::<Vec<String>>()
+    };
+
+    assert_eq!(names(&report.highlight.tags), ["b", "c"]);
+    assert_eq!(names(&report.added), ["c"]);
+    assert_eq!(names(&report.removed), ["a"]);
+
+    assert!(matches!(
+      report.failed.as_slice(),
+      [(name, Error::BadRequest { .. })] if name == "d"
+    ));
+
+    assert_eq!(transport.requests().len(), 4);
+  }
+
+  #[test]
+  fn tag_highlights() {
+    let transport = FakeTransport::new()
+      .respond(
+        "POST",
+        "/highlights/1/tags",
+        200,
+        r#"{"id": 1, "name": "a"}"#,
+      )
+      .respond(
+        "POST",
+        "/highlights/3/tags",
+        200,
+        r#"{"id": 3, "name": "a"}"#,
+      );
+
+    let client =
+      Client::with_transport("token", Box::new(transport.clone())).unwrap();
+
+    let report = client.tag_highlights(&[1, 2, 3], "a");
+
+    assert_eq!(
+      report
+        .tagged
+        .iter()
+        .map(|(id, tag)| (*id, tag.id))
+        .collect::<Vec<_>>(),
+      [(1, 1), (3, 3)]
+    );
+
+    assert!(matches!(
+      report.failed.as_slice(),
+      [(
+        2,
+        Error::BadRequest {
+          status: StatusCode::NOT_FOUND,
+          ..
+        }
+      )]
+    ));
+
+    assert!(transport
+      .requests()
+      .iter()
+      .all(|request| request.body == Some(serde_json::json!({ "name": "a" }))));
+  }
+
+  /// Answers highlight list requests after `delay`, with one highlight per
+  /// page whose identifier is the page number, failing `fail_page`
+  struct PagedTransport {
+    pages: u64,
+    delay: Duration,
+    fail_page: Option<u64>,
+  }
+
+  impl Transport for PagedTransport {
+    fn execute(&self, request: ApiRequest) -> Result<ApiResponse> {
+      let page = request
+        .query
+        .iter()
+        .find(|(key, _)| key == "page")
+        .and_then(|(_, value)| value.parse().ok())
+        .unwrap_or(1);
+
+      std::thread::sleep(self.delay);
+
+      let (status, body) = match Some(page) == self.fail_page {
+        true => (StatusCode::INTERNAL_SERVER_ERROR, Vec::new()),
+        false => (
+          StatusCode::OK,
+          serde_json::to_vec(&HighlightsResponse {
+            count: self.pages * MAX_PAGE_SIZE,
+            next: (page < self.pages).then(|| "next".into()),
+            previous: None,
+            results: vec![Highlight {
+              id: page,
+              ..Default::default()
+            }],
+          })
+          .unwrap(),
+        ),
+      };
+
+      Ok(ApiResponse {
+        status,
+        headers: header::HeaderMap::new(),
+        url: request.path,
+        body,
+      })
+    }
+  }
+
+  fn paged_client(pages: u64, fail_page: Option<u64>) -> Client {
+    Client::with_transport(
+      "token",
+      Box::new(PagedTransport {
+        pages,
+        delay: Duration::from_millis(50),
+        fail_page,
+      }),
+    )
+    .unwrap()
+  }
+
+  #[test]
+  fn all_highlights_parallel() {
+    let client = paged_client(9, None);
+
+    let start = std::time::Instant::now();
+    let serial = client.collect_highlights(None).unwrap();
+    let serial_elapsed = start.elapsed();
+
+    let start = std::time::Instant::now();
+    let parallel = client.all_highlights_parallel(4).unwrap();
+    let parallel_elapsed = start.elapsed();
+
+    let ids = |highlights: &[Highlight]| {
+      highlights.iter().map(|h| h.id).collect::<Vec<u64>>()
+    };
+
+    assert_eq!(ids(&parallel), (1..=9).collect::<Vec<u64>>());
+    assert_eq!(ids(&parallel), ids(&serial));
+
+    assert!(
+      parallel_elapsed < serial_elapsed / 2,
+      "parallel {:?}, serial {:?}",
+      parallel_elapsed,
+      serial_elapsed
+    );
+  }
+
+  /// Answers highlight list requests for book `n` with highlights `10n` and
+  /// `10n + 1` and a shared highlight `99`, sooner for later books
+  struct BookTransport;
+
+  impl Transport for BookTransport {
+    fn execute(&self, request: ApiRequest) -> Result<ApiResponse> {
+      let book_id = request
+        .query
+        .iter()
+        .find(|(key, _)| key == "book_id")
+        .and_then(|(_, value)| value.parse::<u64>().ok())
+        .unwrap();
+
+      std::thread::sleep(Duration::from_millis(10u64.saturating_sub(book_id)));
+
+      let highlight = |id| Highlight {
+        id,
+        book_id: Some(book_id),
+        ..Default::default()
+      };
 
-    let mut headers = header::HeaderMap::new();
+      Ok(ApiResponse {
+        status: StatusCode::OK,
+        headers: header::HeaderMap::new(),
+        url: request.path,
+        body: serde_json::to_vec(&HighlightsResponse {
+          count: 3,
+          next: None,
+          previous: None,
+          results: vec![
+            highlight(book_id * 10),
+            highlight(book_id * 10 + 1),
+            highlight(99),
+          ],
+        })
+        .unwrap(),
+      })
+    }
+  }
+
+  #[test]
+  fn highlights_for_books() {
+    let client =
+      Client::with_transport("token", Box::new(BookTransport)).unwrap();
 
-    headers.insert(
-      header::AUTHORIZATION,
-      header::HeaderValue::from_str(&format!("Token {}", access_token))?,
+    assert_eq!(
+      client
+        .highlights_for_books(&[3, 1, 3, 2], 4)
+        .unwrap()
+        .iter()
+        .map(|highlight| highlight.id)
+        .collect::<Vec<u64>>(),
+      [30, 31, 99, 10, 11, 20, 21]
     );
 
-    let client = blocking::Client::builder()
-      .default_headers(headers)
-      .build()?;
+    assert!(client.highlights_for_books(&[], 4).unwrap().is_empty());
+  }
 
-    let response = client.get(&url).send()?;
+  #[test]
+  fn cancel_flag() {
+    let cancelled = Arc::new(AtomicBool::new(false));
 
-    match response.status().is_success() {
-      true => Ok(()),
-      false => Err(error::Error::BadRequest {
-        status: response.status(),
-      }),
-    }?;
+    let client = ClientBuilder::new("token")
+      .transport(Box::new(PagedTransport {
+        pages: 5,
+        delay: Duration::ZERO,
+        fail_page: None,
+      }))
+      .cancel_flag(cancelled.clone())
+      .on_event({
+        let cancelled = cancelled.clone();
+        move |event| {
+          if event == (Event::PageFetched { page: 2, items: 1 }) {
+            cancelled.store(true, Ordering::Relaxed);
+          }
+        }
+      })
+      .build()
+      .unwrap();
 
-    Ok(Self {
-      access_token: access_token.to_string(),
-    })
-  }
+    let partial = client.all_highlights_from(1).unwrap_err();
 
-  /// Fetch all books from a specified page
-  ///
-  /// ```no_run
-  /// use readwise::client::Client;
-  ///
-  /// let client = Client::new("token").unwrap();
-  /// let books = client.books(1).unwrap();
-  /// ```
-  pub fn books(&self, page: u64) -> Result<Vec<Book>> {
-    Ok(
-      serde_json::from_str::<BooksResponse>(
-        &self
-          .request(&format!("/books?page={}", page), Method::GET, None)?
-          .text()?,
-      )?
-      .results,
-    )
-  }
+    assert_eq!(
+      partial
+        .items
+        .iter()
+        .map(|highlight| highlight.id)
+        .collect::<Vec<u64>>(),
+      [1, 2]
+    );
 
-  /// Fetch all highlights from a specified page
-  ///
-  /// ```no_run
-  /// use readwise::client::Client;
-  ///
-  /// let client = Client::new("token").unwrap();
-  /// let highlights = client.highlights(1).unwrap();
-  /// ```
-  pub fn highlights(&self, page: u64) -> Result<Vec<Highlight>> {
-    Ok(
-      serde_json::from_str::<HighlightsResponse>(
-        &self
-          .request(&format!("/highlights?page={}", page), Method::GET, None)?
-          .text()?,
-      )?
-      .results,
-    )
-  }
+    assert_eq!(partial.next_page, 3);
+    assert!(matches!(partial.error, Error::Cancelled));
 
-  /// Fetch a single book by identifier
-  ///
-  /// ```no_run
-  /// use readwise::client::Client;
-  ///
-  /// let client = Client::new("token").unwrap();
-  /// let book = client.book(1).unwrap();
-  /// ```
-  pub fn book(&self, id: u64) -> Result<Book> {
-    Ok(serde_json::from_str::<Book>(
-      &self
-        .request(&format!("/books/{}", id), Method::GET, None)?
-        .text()?,
-    )?)
+    assert!(matches!(client.books(1), Err(Error::Cancelled)));
   }
 
-  /// Fetch a single highlight by identifier
-  ///
-  /// ```no_run
-  /// use readwise::client::Client;
-  ///
-  /// let client = Client::new("token").unwrap();
-  /// let highlight = client.highlight(1).unwrap();
-  /// ```
-  pub fn highlight(&self, id: u64) -> Result<Highlight> {
-    Ok(serde_json::from_str::<Highlight>(
-      &self
-        .request(&format!("/highlights/{}", id), Method::GET, None)?
-        .text()?,
-    )?)
-  }
+  #[test]
+  fn all_highlights_from_keeps_partial_results() {
+    let transport = PagedTransport {
+      pages: 5,
+      delay: Duration::ZERO,
+      fail_page: Some(3),
+    };
 
-  /// Create and return one or more highlights
-  ///
-  /// ```no_run
-  /// use {
-  ///   std::collections::HashMap,
-  ///   readwise::client::Client
-  /// };
-  ///
-  /// let client = Client::new("token").unwrap();
-  ///
-  /// let mut new_highlight = HashMap::new();
-  ///
-  /// new_highlight.insert("text", "hello world!");
-  ///
-  /// for highlight in client.create_highlights(vec![new_highlight]).unwrap() {
-  ///   println!("{}", highlight.text);
-  /// }
-  /// ```
-  pub fn create_highlights(
-    &self,
-    highlights: Vec<HashMap<&str, &str>>,
-  ) -> Result<Vec<Highlight>> {
-    let mut body = HashMap::new();
+    let partial = Client::with_transport("token", Box::new(transport))
+      .unwrap()
+      .all_highlights_from(1)
+      .unwrap_err();
+
+    assert_eq!(
+      partial
+        .items
+        .iter()
+        .map(|highlight| highlight.id)
+        .collect::<Vec<u64>>(),
+      [1, 2]
+    );
 
-    body.insert("highlights", highlights);
+    assert_eq!(partial.next_page, 3);
 
-    let identifiers = serde_json::from_str::<Vec<HighlightCreateResponse>>(
-      &self
-        .request("/highlights", Method::POST, Some(body))?
-        .text()?,
-    )?
-    .into_iter()
-    .flat_map(|item| item.modified_highlights)
-    .collect::<Vec<u64>>();
+    assert!(matches!(
+      partial.error,
+      Error::BadRequest {
+        status: StatusCode::INTERNAL_SERVER_ERROR,
+        ..
+      }
+    ));
 
-    identifiers
-      .iter()
-      .map(|identifier| self.highlight(*identifier))
-      .collect::<Result<Vec<Highlight>, _>>()
+    let rest = paged_client(5, None)
+      .all_highlights_from(partial.next_page)
+      .unwrap();
+
+    assert_eq!(
+      rest
+        .iter()
+        .map(|highlight| highlight.id)
+        .collect::<Vec<u64>>(),
+      [3, 4, 5]
+    );
   }
 
-  /// Update a single highlight by identifier
-  ///
-  /// ```no_run
-  /// use {
-  ///   std::collections::HashMap,
-  ///   readwise::client::Client
-  /// };
-  ///
-  /// let client = Client::new("token").unwrap();
-  ///
-  /// let mut fields = HashMap::new();
-  /// fields.insert("text", "hello, world!");
-  ///
-  /// client.update_highlight(1, fields).unwrap();
-  /// ```
-  pub fn update_highlight(
-    &self,
-    id: i64,
-    body: HashMap<&str, &str>,
-  ) -> Result<Highlight> {
-    let mut container = HashMap::new();
+  #[test]
+  fn all_highlights_parallel_error() {
+    let transport = PagedTransport {
+      pages: 40,
+      delay: Duration::from_millis(5),
+      fail_page: Some(3),
+    };
 
-    container.insert("body", vec![body]);
+    let result = Client::with_transport("token", Box::new(transport))
+      .unwrap()
+      .all_highlights_parallel(2);
 
-    Ok(serde_json::from_str::<Highlight>(
-      &self
-        .request(
-          &format!("/highlights/{}", id),
-          Method::PATCH,
-          Some(container),
-        )?
-        .text()?,
-    )?)
+    assert!(matches!(
+      result,
+      Err(Error::BadRequest {
+        status: StatusCode::INTERNAL_SERVER_ERROR,
+        ..
+      })
+    ));
   }
 
-  /// Delete a single highlight by identifier
-  ///
-  /// ```no_run
-  /// use readwise::client::Client;
-  ///
-  /// let client = Client::new("token").unwrap();
-  /// client.delete_highlight(1).unwrap();
-  /// ```
-  pub fn delete_highlight(&self, id: i64) -> Result {
-    self.request(&format!("/highlights/{}", id), Method::DELETE, None)?;
-    Ok(())
-  }
+  #[test]
+  fn highlights_since_id() {
+    let page = |ids: [u64; 2], next: Option<&str>| {
+      serde_json::to_string(&HighlightsResponse {
+        count: 6,
+        next: next.map(String::from),
+        previous: None,
+        results: ids
+          .into_iter()
+          .map(|id| Highlight {
+            id,
+            ..Default::default()
+          })
+          .collect(),
+      })
+      .unwrap()
+    };
 
-  fn request(
-    &self,
-    endpoint: &str,
-    method: Method,
-    body: Option<HashMap<&str, Vec<HashMap<&str, &str>>>>,
-  ) -> Result<Response> {
-    let url = format!("{}/api/v2{}", &request_url(), endpoint);
+    let transport = FakeTransport::new()
+      .respond("GET", "/highlights", 200, &page([12, 14], Some("next")))
+      .respond("GET", "/highlights", 200, &page([5, 11], Some("next")))
+      .respond("GET", "/highlights", 200, &page([9, 3], Some("next")))
+      .respond("GET", "/highlights", 200, &page([2, 1], None));
 
-    let mut headers = header::HeaderMap::new();
+    let client =
+      Client::with_transport("token", Box::new(transport.clone())).unwrap();
 
-    headers.insert(
-      header::AUTHORIZATION,
-      header::HeaderValue::from_str(&format!("Token {}", self.access_token))?,
+    assert_eq!(
+      client
+        .highlights_since_id(10)
+        .unwrap()
+        .iter()
+        .map(|highlight| highlight.id)
+        .collect::<Vec<u64>>(),
+      [14, 12, 11]
     );
 
-    let request_client = blocking::Client::builder()
-      .default_headers(headers)
-      .build()?;
+    assert_eq!(
+      transport
+        .requests()
+        .iter()
+        .map(|request| request.query[0].clone())
+        .collect::<Vec<(String, String)>>(),
+      [1, 2, 3].map(|page| ("page".to_string(), page.to_string()))
+    );
+  }
 
-    let request = match method {
-      Method::GET => Ok(request_client.get(&url)),
-      Method::POST => Ok(request_client.post(&url).json(&body.unwrap())),
-      Method::PATCH => {
-        Ok(request_client.patch(&url).json(&body.unwrap()["body"][0]))
-      }
-      Method::DELETE => Ok(request_client.delete(&url)),
-      _ => Err(error::Error::UnsupportedRequest { method }),
-    };
+  #[derive(Clone, Default)]
+  struct RecordingObserver {
+    calls: std::sync::Arc<Mutex<Vec<String>>>,
+  }
 
-    let response = request?.send()?;
+  impl RequestObserver for RecordingObserver {
+    fn on_request(&self, request: &ApiRequest) {
+      self
+        .calls
+        .lock()
+        .unwrap()
+        .push(format!("request {} {}", request.method, request.path));
+    }
 
-    match response.status().is_success() {
-      true => Ok(response),
-      false => Err(error::Error::BadRequest {
-        status: response.status(),
-      }),
+    fn on_response(
+      &self,
+      request: &ApiRequest,
+      status: StatusCode,
+      _duration: Duration,
+    ) {
+      self.calls.lock().unwrap().push(format!(
+        "response {} {} {}",
+        request.method,
+        request.path,
+        status.as_u16()
+      ));
+    }
+
+    fn on_error(
+      &self,
+      request: &ApiRequest,
+      _error: &Error,
+      _duration: Duration,
+    ) {
+      self
+        .calls
+        .lock()
+        .unwrap()
+        .push(format!("error {} {}", request.method, request.path));
     }
   }
-}
 
-#[cfg(test)]
-mod tests {
-  use {super::*, mockito::mock};
+  struct FailingTransport;
 
-  fn client() -> Client {
-    Client {
-      access_token: String::new(),
+  impl Transport for FailingTransport {
+    fn execute(&self, request: ApiRequest) -> Result<ApiResponse> {
+      Err(Error::UnsupportedRequest {
+        method: request.method,
+      })
     }
   }
 
-  fn get_book_as_string() -> String {
-    serde_json::to_string(&Book::default()).unwrap()
+  /// Answers with an `ETag`, or `304 Not Modified` when it is sent back
+  #[derive(Clone, Default)]
+  struct EtagTransport {
+    requests: Arc<Mutex<Vec<ApiRequest>>>,
   }
 
-  fn get_highlight_as_string() -> String {
-    serde_json::to_string(&Highlight::default()).unwrap()
+  impl Transport for EtagTransport {
+    fn execute(&self, request: ApiRequest) -> Result<ApiResponse> {
+      let revalidated = request
+        .headers
+        .get(header::IF_NONE_MATCH)
+        .is_some_and(|etag| etag == "\"v1\"");
+
+      self.requests.lock().unwrap().push(request);
+
+      let mut headers = header::HeaderMap::new();
+
+      headers.insert(header::ETAG, header::HeaderValue::from_static("\"v1\""));
+
+      Ok(ApiResponse {
+        status: match revalidated {
+          true => StatusCode::NOT_MODIFIED,
+          false => StatusCode::OK,
+        },
+        headers,
+        url: String::new(),
+        body: match revalidated {
+          true => Vec::new(),
+          false => get_book_as_string().into_bytes(),
+        },
+      })
+    }
   }
 
   #[test]
-  fn authenticate() {
-    let _m = mock("GET", "/api/v2/auth").with_status(204).create();
+  fn response_cache() {
+    let transport = EtagTransport::default();
 
-    let result = Client::new("token");
+    let client = ClientBuilder::new("token")
+      .transport(Box::new(transport.clone()))
+      .response_cache(Box::new(MemoryCacheStore::new()))
+      .build()
+      .unwrap();
 
-    assert!(result.is_ok(), "{}", result.err().unwrap().to_string());
+    let first = client.book(1).unwrap();
+    let second = client.book(1).unwrap();
 
-    let client = result.unwrap();
+    assert_eq!(first.title, second.title);
 
-    assert_eq!("token", client.access_token);
+    assert_eq!(
+      client.last_response_meta().unwrap().status,
+      StatusCode::NOT_MODIFIED
+    );
+
+    let requests = transport.requests.lock().unwrap();
+
+    assert!(!requests[0].headers.contains_key(header::IF_NONE_MATCH));
+    assert_eq!(requests[1].headers[header::IF_NONE_MATCH], "\"v1\"");
   }
 
   #[test]
-  fn authenticate_bad_token() {
-    let _m = mock("GET", "/api/v2/auth").with_status(401).create();
+  fn observer() {
+    let observer = RecordingObserver::default();
 
-    let result = Client::new("token");
+    let client = ClientBuilder::new("token")
+      .transport(Box::new(
+        FakeTransport::new()
+          .respond("GET", "/books/1", 503, "")
+          .respond("GET", "/books/1", 200, &get_book_as_string()),
+      ))
+      .retries(1)
+      .backoff(Duration::ZERO, Duration::ZERO)
+      .observer(Box::new(observer.clone()))
+      .build()
+      .unwrap();
+
+    client.book(1).unwrap();
+
+    client.highlight(2).unwrap_err();
+
+    assert_eq!(
+      *observer.calls.lock().unwrap(),
+      [
+        "request GET /books/1",
+        "response GET /books/1 503",
+        "request GET /books/1",
+        "response GET /books/1 200",
+        "request GET /highlights/2",
+        "response GET /highlights/2 404",
+      ]
+    );
+
+    let observer = RecordingObserver::default();
 
-    assert!(result.is_err(), "{}", result.err().unwrap().to_string());
+    ClientBuilder::new("token")
+      .transport(Box::new(FailingTransport))
+      .observer(Box::new(observer.clone()))
+      .build()
+      .unwrap()
+      .book(1)
+      .unwrap_err();
+
+    assert_eq!(
+      *observer.calls.lock().unwrap(),
+      ["request GET /books/1", "error GET /books/1"]
+    );
   }
 
   #[test]
-  fn books() {
-    let _m = mock("GET", "/api/v2/books?page=1")
-      .with_status(200)
-      .with_body(format!(
-        r#" {{ "count": 1, "next": null, "previous": null, "results": [{}] }} "#,
-        &get_book_as_string()
-      ))
-      .create();
+  fn rate_limit_status() {
+    let transport = FakeTransport::new()
+      .respond("GET", "/books/1", 200, &get_book_as_string())
+      .respond("GET", "/books/2", 429, "");
 
-    let result = client().books(1);
+    let client = Client::with_transport("token", Box::new(transport)).unwrap();
 
-    assert!(result.is_ok(), "{}", result.err().unwrap().to_string());
+    client.book(1).unwrap();
+
+    assert_eq!(client.rate_limit_status(), None);
+
+    client.book(2).unwrap_err();
+
+    assert_eq!(
+      client.rate_limit_status(),
+      Some(RateLimitStatus {
+        limit: None,
+        remaining: 0,
+        reset_at: None,
+      })
+    );
+
+    client.book(1).unwrap();
+
+    assert_eq!(client.rate_limit_status().unwrap().remaining, 0);
   }
 
   #[test]
-  fn highlights() {
-    let _m = mock("GET", "/api/v2/highlights?page=1")
-      .with_status(200)
-      .with_body(format!(
-        r#" {{ "count": 1, "next": null, "previous": null, "results": [{}] }} "#,
-        get_highlight_as_string()
-      ))
-      .create();
-
-    let result = client().highlights(1);
+  fn client_is_send_and_sync() {
+    fn assert_send_sync<T: Send + Sync>() {}
 
-    assert!(result.is_ok(), "{}", result.err().unwrap().to_string());
+    assert_send_sync::<Client>();
+    assert_send_sync::<Arc<Client>>();
   }
 
   #[test]
-  fn single_book() {
-    let _m = mock("GET", "/api/v2/books/1")
-      .with_status(200)
-      .with_body(get_book_as_string())
-      .create();
+  fn clones_share_state() {
+    let transport = FakeTransport::new().respond(
+      "GET",
+      "/books/1",
+      200,
+      &get_book_as_string(),
+    );
 
-    let result = client().book(1);
+    let client = Client::with_transport("token", Box::new(transport.clone()))
+      .unwrap()
+      .cache_books(10);
 
-    assert!(result.is_ok(), "{}", result.err().unwrap().to_string());
+    let clone = client.clone();
+
+    std::thread::spawn(move || clone.book(1).unwrap())
+      .join()
+      .unwrap();
+
+    client.book(1).unwrap();
+
+    assert_eq!(transport.requests().len(), 1);
+
+    assert_eq!(client.last_response_meta().unwrap().status, StatusCode::OK);
   }
 
   #[test]
-  fn single_highlight() {
-    let _m = mock("GET", "/api/v2/highlights/1")
-      .with_status(200)
-      .with_body(get_highlight_as_string())
-      .create();
+  fn dedup_pagination() {
+    let page = |ids: &[u64], next: bool| {
+      serde_json::to_string(&HighlightsResponse {
+        count: 3,
+        next: next.then(|| "next".into()),
+        previous: None,
+        results: ids
+          .iter()
+          .map(|&id| Highlight {
+            id,
+            ..Default::default()
+          })
+          .collect(),
+      })
+      .unwrap()
+    };
 
-    let result = client().highlight(1);
+    let transport = || {
+      FakeTransport::new()
+        .respond("GET", "/highlights", 200, &page(&[3, 2], true))
+        .respond("GET", "/highlights", 200, &page(&[2, 1], false))
+    };
 
-    assert!(result.is_ok(), "{}", result.err().unwrap().to_string());
+    let ids = |dedup_pagination| {
+      ClientBuilder::new("token")
+        .transport(Box::new(transport()))
+        .dedup_pagination(dedup_pagination)
+        .build()
+        .unwrap()
+        .all_highlights_from(1)
+        .unwrap()
+        .iter()
+        .map(|highlight| highlight.id)
+        .collect::<Vec<u64>>()
+    };
+
+    assert_eq!(ids(false), [3, 2, 2, 1]);
+    assert_eq!(ids(true), [3, 2, 1]);
+
+    let mut output = Vec::new();
+
+    let stats = ClientBuilder::new("token")
+      .transport(Box::new(transport()))
+      .dedup_pagination(true)
+      .build()
+      .unwrap()
+      .export_highlights_jsonl(&mut output)
+      .unwrap();
+
+    assert_eq!(stats.lines, 3);
   }
 
   #[test]
-  fn create_highlights() {
-    let _m = mock("POST", "/api/v2/highlights")
-      .with_status(200)
-      .with_body(
-        r#"
-        [ { "id": 1,
-          "title": "Quotes",
-          "author": null,
-          "category": "books",
-          "num_highlights": 5,
-          "last_highlight_at": "2021-02-20T16:28:53.900414Z",
-          "updated": "2021-02-20T16:35:41.793746Z",
-          "cover_image_url": "https://readwise-assets.s3.amazonaws.com/static/images/default-book-icon-7.09749d3efd49.png",
-          "highlights_url": "https://readwise.io/bookreview/7843339",
-          "source_url": null,
-          "modified_highlights": [] }
-        ]"#,
+  fn dry_run() {
+    let transport = FakeTransport::new().respond(
+      "GET",
+      "/highlights/1",
+      200,
+      &get_highlight_as_string(),
+    );
+
+    let events = std::sync::Arc::new(Mutex::new(Vec::new()));
+
+    let collector = events.clone();
+
+    let client = ClientBuilder::new("token")
+      .transport(Box::new(transport.clone()))
+      .dry_run(true)
+      .on_event(move |event| collector.lock().unwrap().push(event))
+      .build()
+      .unwrap();
+
+    client.highlight(1).unwrap();
+
+    client
+      .update_highlight(
+        1,
+        HighlightUpdate {
+          note: Some("revisit".into()),
+          ..Default::default()
+        },
       )
-      .create();
+      .unwrap();
 
-    let result = client().create_highlights(Vec::new());
+    assert_eq!(client.delete_highlight(1).unwrap(), DeleteOutcome::Deleted);
 
-    assert!(result.is_ok(), "{}", result.err().unwrap().to_string());
+    assert_eq!(
+      client
+        .create_highlights(vec![HashMap::from([("text", "hello")])])
+        .unwrap()
+        .len(),
+      0
+    );
+
+    assert_eq!(transport.requests().len(), 1);
+    assert_eq!(transport.requests()[0].method, Method::GET);
+
+    assert_eq!(
+      *events.lock().unwrap(),
+      vec![
+        Event::DryRun {
+          method: Method::PATCH,
+          endpoint: "/highlights/1".into(),
+          body: Some(r#"{"note":"revisit"}"#.into()),
+        },
+        Event::DryRun {
+          method: Method::DELETE,
+          endpoint: "/highlights/1".into(),
+          body: None,
+        },
+        Event::DryRun {
+          method: Method::POST,
+          endpoint: "/highlights".into(),
+          body: Some(r#"{"highlights":[{"text":"hello"}]}"#.into()),
+        },
+        Event::Done { total: 0 },
+      ]
+    );
   }
 
   #[test]
-  fn update_highlight() {
-    let _m = mock("PATCH", "/api/v2/highlights/0")
-      .with_status(200)
-      .with_body(get_highlight_as_string())
-      .create();
+  fn tags() {
+    let tag = |id, name: &str| Tag {
+      id,
+      name: name.into(),
+    };
+
+    let transport = FakeTransport::new().respond(
+      "GET",
+      "/export",
+      200,
+      &serde_json::to_string(&ExportResponse {
+        count: 2,
+        next_page_cursor: None,
+        results: vec![
+          ExportBook {
+            book_tags: vec![tag(1, "reading")],
+            highlights: vec![ExportHighlight {
+              tags: vec![tag(2, "quote"), tag(1, "reading")],
+              ..Default::default()
+            }],
+            ..Default::default()
+          },
+          ExportBook {
+            book_tags: vec![tag(3, "archive")],
+            ..Default::default()
+          },
+        ],
+      })
+      .unwrap(),
+    );
 
-    let result = client().update_highlight(0, HashMap::new());
+    let client =
+      Client::with_transport("token", Box::new(transport.clone())).unwrap();
 
-    assert!(result.is_ok(), "{}", result.err().unwrap().to_string());
+    assert_eq!(
+      client
+        .tags()
+        .unwrap()
+        .into_iter()
+        .map(|tag| tag.name)
+        .collect::<Vec<String>>(),
+      ["archive", "quote", "reading"]
+    );
+
+    assert_eq!(transport.requests().len(), 1);
   }
 
   #[test]
-  fn delete_highlight() {
-    let _m = mock("DELETE", "/api/v2/highlights/1")
-      .with_status(200)
-      .create();
+  fn highlights_modified_since() {
+    let transport = FakeTransport::new()
+      .respond(
+        "GET",
+        "/export",
+        200,
+        &serde_json::to_string(&ExportResponse {
+          count: 1,
+          next_page_cursor: None,
+          results: vec![ExportBook {
+            highlights: vec![ExportHighlight {
+              id: 2,
+              text: "edited".into(),
+              ..Default::default()
+            }],
+            ..Default::default()
+          }],
+        })
+        .unwrap(),
+      )
+      .respond(
+        "GET",
+        "/highlights",
+        200,
+        &serde_json::to_string(&HighlightsResponse {
+          count: 2,
+          next: None,
+          previous: None,
+          results: [2, 4]
+            .into_iter()
+            .map(|id| Highlight {
+              id,
+              ..Default::default()
+            })
+            .collect(),
+        })
+        .unwrap(),
+      );
 
-    let result = client().delete_highlight(1);
+    let client =
+      Client::with_transport("token", Box::new(transport.clone())).unwrap();
 
-    assert!(result.is_ok(), "{}", result.err().unwrap().to_string());
+    let delta = client
+      .highlights_modified_since(
+        "2022-10-01T00:00:00Z",
+        &HashSet::from([1, 2, 3]),
+      )
+      .unwrap();
+
+    assert_eq!(delta.updated.len(), 1);
+    assert_eq!(delta.updated[0].text, "edited");
+    assert_eq!(delta.deleted, [1, 3]);
+
+    assert_eq!(
+      transport.requests()[0].query,
+      [("updatedAfter".into(), "2022-10-01T00:00:00Z".into())]
+    );
   }
 }
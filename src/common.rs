@@ -1,22 +1,92 @@
-pub(crate) use std::collections::HashMap;
+pub(crate) use std::{
+  collections::{HashMap, HashSet},
+  fmt::{self, Display, Formatter},
+  str::FromStr,
+  time::Duration,
+};
 
 pub(crate) use {
   http::Method,
-  reqwest::{
-    blocking::{self, Response},
-    header, StatusCode,
-  },
+  reqwest::{header, StatusCode},
   serde::{Deserialize, Serialize},
-  snafu::Snafu,
+  snafu::{ResultExt, Snafu},
 };
 
-pub(crate) use crate::{error, url::request_url};
+#[cfg(feature = "blocking")]
+pub(crate) use {
+  crate::cache::LruCache,
+  crate::observer::RequestObserver,
+  crate::response::RateLimitStatus,
+  crate::response_cache::{CacheStore, CachedResponse},
+  crate::sleep::{Sleeper, ThreadSleeper},
+  crate::stats::LibraryStats,
+  crate::text::{self, NormalizeOptions},
+  crate::timestamp::{format_timestamp, parse_timestamp},
+  crate::transport::{ApiRequest, ApiResponse, ReqwestTransport, Transport},
+  crate::{
+    event::Event,
+    model::{
+      sort_highlights, BookUpdate, CreateOptions, CreateReport, DailyReview,
+      ExportStats, ExportSummary, HighlightCreate, HighlightUpdateReport, Page,
+      PartialFetch, SyncDelta, TagReport,
+    },
+    url::with_query,
+  },
+  rand::{rngs::StdRng, Rng, SeedableRng},
+  reqwest::blocking,
+  serde::de::DeserializeOwned,
+  std::{
+    collections::hash_map::Entry,
+    ffi::OsString,
+    fs::{self, OpenOptions},
+    io::{BufWriter, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+    thread,
+  },
+};
 
 pub(crate) use crate::{
-  error::Error,
-  model::{
-    Book, BooksResponse, Highlight, HighlightCreateResponse, HighlightsResponse,
+  de::{
+    deserialize_empty_as_none, deserialize_lenient_number,
+    deserialize_lenient_option, deserialize_null_as_default,
   },
+  error::{self, Error},
+  model::{Book, Category, Color, Highlight, LocationType, Tag},
+  response::ResponseMeta,
+  url::READWISE_URL,
 };
 
+#[cfg(any(feature = "blocking", feature = "async"))]
+pub(crate) use {
+  crate::{
+    de::from_json,
+    filter::{BookFilter, HighlightFilter, MAX_PAGE_SIZE},
+    model::{
+      create_payloads, unique_tags, BooksResponse, DeleteOutcome, ExportBook,
+      ExportResponse, HighlightCreateResponse, HighlightUpdate,
+      HighlightsResponse, MAX_CREATE_BATCH_SIZE,
+    },
+    response::Stopwatch,
+    retry::{
+      RetryAllowance, RetryBudget, RetryPolicy, DEFAULT_BACKOFF_BASE,
+      DEFAULT_BACKOFF_CAP,
+    },
+    settings::HttpSettings,
+    throttle::Throttle,
+    token::SecretToken,
+    url::{api_url, append_query, export_endpoint, request_url, TOKEN_ENV_VAR},
+  },
+  std::{
+    env,
+    sync::{Arc, Mutex},
+  },
+};
+
+#[cfg(any(
+  feature = "blocking",
+  all(feature = "async", not(target_arch = "wasm32"))
+))]
+pub(crate) use crate::url::USER_AGENT;
+
 pub(crate) type Result<T = (), E = Error> = std::result::Result<T, E>;
@@ -0,0 +1,135 @@
+//! Export of highlights as CSV, for spreadsheets
+
+use {crate::common::*, std::io::Write};
+
+/// A row of `highlights_to_csv`, whose field names are the column headers
+#[derive(Serialize)]
+struct Row<'a> {
+  text: &'a str,
+  note: &'a str,
+  title: &'a str,
+  author: &'a str,
+  location: Option<u64>,
+  color: &'a str,
+  highlighted_at: &'a str,
+}
+
+/// Write `highlights` to `writer` as CSV, one row per highlight, with the
+/// columns `text`, `note`, `title`, `author`, `location`, `color` and
+/// `highlighted_at`
+///
+/// The title and author are those of the book in `books` with the
+/// highlight's `book_id`, and are left empty when it is not found, as is the
+/// location of a highlight without one. Fields containing commas, quotes or
+/// newlines are quoted.
+///
+/// ```
+/// use readwise::{
+///   csv::highlights_to_csv,
+///   model::{Book, Highlight},
+/// };
+///
+/// let books = [Book {
+///   id: 1,
+///   title: "Quotes".into(),
+///   ..Default::default()
+/// }];
+///
+/// let highlights = [Highlight {
+///   text: "one, \"two\"".into(),
+///   book_id: Some(1),
+///   ..Default::default()
+/// }];
+///
+/// let mut output = Vec::new();
+///
+/// highlights_to_csv(&highlights, &books, &mut output).unwrap();
+///
+/// assert_eq!(
+///   String::from_utf8(output).unwrap(),
+///   "text,note,title,author,location,color,highlighted_at\n\
+///    \"one, \"\"two\"\"\",,Quotes,,,,\n"
+/// );
+/// ```
+pub fn highlights_to_csv<W: Write>(
+  highlights: &[Highlight],
+  books: &[Book],
+  writer: W,
+) -> Result {
+  let books = books
+    .iter()
+    .map(|book| (book.id, book))
+    .collect::<HashMap<u64, &Book>>();
+
+  let mut writer = ::csv::Writer::from_writer(writer);
+
+  for highlight in highlights {
+    let book = highlight.book_id.and_then(|id| books.get(&id));
+
+    writer.serialize(Row {
+      text: &highlight.text,
+      note: &highlight.note,
+      title: book.map(|book| book.title.as_str()).unwrap_or_default(),
+      author: book
+        .and_then(|book| book.author.as_deref())
+        .unwrap_or_default(),
+      location: highlight.location,
+      color: &highlight.color,
+      highlighted_at: highlight.highlighted_at.as_deref().unwrap_or_default(),
+    })?;
+  }
+
+  Ok(writer.flush()?)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn escapes_fields() {
+    let books = [Book {
+      id: 1,
+      title: "Title, Subtitle".into(),
+      author: Some("Author".into()),
+      ..Default::default()
+    }];
+
+    let highlights = [
+      Highlight {
+        text: "line one\nline \"two\"".into(),
+        note: "a note".into(),
+        location: Some(12),
+        color: "yellow".into(),
+        highlighted_at: Some("2022-10-01T00:00:00Z".into()),
+        book_id: Some(1),
+        ..Default::default()
+      },
+      Highlight {
+        text: "orphan".into(),
+        book_id: Some(2),
+        ..Default::default()
+      },
+    ];
+
+    let mut output = Vec::new();
+
+    highlights_to_csv(&highlights, &books, &mut output).unwrap();
+
+    assert_eq!(
+      String::from_utf8(output).unwrap(),
+      "text,note,title,author,location,color,highlighted_at\n\
+       \"line one\nline \"\"two\"\"\",a note,\"Title, Subtitle\",Author,12,yellow,2022-10-01T00:00:00Z\n\
+       orphan,,,,,,\n"
+    );
+  }
+
+  #[test]
+  fn no_highlights() {
+    let mut output = Vec::new();
+
+    highlights_to_csv(&[], &[], &mut output).unwrap();
+
+    assert_eq!(String::from_utf8(output).unwrap(), "");
+  }
+}
@@ -0,0 +1,282 @@
+use {
+  crate::common::*,
+  serde::{de, Deserializer},
+};
+
+#[cfg(any(feature = "blocking", feature = "async"))]
+use {serde::de::DeserializeOwned, serde_json::Value};
+
+/// Deserialize a response body as `T`, reporting the path to the offending
+/// value, such as `results[42].highlighted_at`, when it does not match `T`;
+/// see `Error::DeserializeAt` for how far that path can be trusted
+///
+/// The body is read as bytes, so it need not be copied into a `String`
+/// first.
+#[cfg(any(feature = "blocking", feature = "async"))]
+pub(crate) fn from_json<T: DeserializeOwned>(json: &[u8]) -> Result<T> {
+  let source = match serde_json::from_slice(json) {
+    Ok(value) => return Ok(value),
+    Err(source) => source,
+  };
+
+  match error_path::<T>(json) {
+    Some(path) if !path.is_empty() => {
+      Err(Error::DeserializeAt { path, source })
+    }
+    _ => Err(source.into()),
+  }
+}
+
+/// The path to the value in `json` that `T` fails to deserialize
+///
+/// `json` is pretty printed, which places every value on its own line, and
+/// deserialized again, so that the line the error is reported on identifies
+/// the value.
+#[cfg(any(feature = "blocking", feature = "async"))]
+fn error_path<T: DeserializeOwned>(json: &[u8]) -> Option<String> {
+  let value = serde_json::from_slice::<Value>(json).ok()?;
+
+  let error =
+    serde_json::from_slice::<T>(&serde_json::to_vec_pretty(&value).ok()?)
+      .err()?;
+
+  let mut paths = vec![String::new()];
+
+  line_paths(&value, "", &mut paths);
+
+  paths.get(error.line().checked_sub(1)?).cloned()
+}
+
+/// Push the path of the value starting on each line of `value` pretty
+/// printed, after its first, which belongs to `path`
+#[cfg(any(feature = "blocking", feature = "async"))]
+fn line_paths(value: &Value, path: &str, paths: &mut Vec<String>) {
+  let children = match value {
+    Value::Object(map) if !map.is_empty() => map
+      .iter()
+      .map(|(key, child)| match path {
+        "" => (key.clone(), child),
+        _ => (format!("{}.{}", path, key), child),
+      })
+      .collect::<Vec<(String, &Value)>>(),
+    Value::Array(items) if !items.is_empty() => items
+      .iter()
+      .enumerate()
+      .map(|(index, child)| (format!("{}[{}]", path, index), child))
+      .collect(),
+    _ => return,
+  };
+
+  for (child_path, child) in children {
+    paths.push(child_path.clone());
+    line_paths(child, &child_path, paths);
+  }
+
+  paths.push(path.to_string());
+}
+
+/// Deserialize an optional string, treating an empty string like `null`
+///
+/// The API is inconsistent about whether missing values are sent as `""`
+/// or `null`; fields using this always hold `None` for either. Use with
+/// `#[serde(default)]` so that a missing field is also `None`.
+pub(crate) fn deserialize_empty_as_none<'de, D: Deserializer<'de>>(
+  deserializer: D,
+) -> Result<Option<String>, D::Error> {
+  Ok(Option::<String>::deserialize(deserializer)?.filter(|s| !s.is_empty()))
+}
+
+/// Deserialize a value that the API sometimes sends as `null`, treating
+/// `null` like the type's default
+pub(crate) fn deserialize_null_as_default<'de, D, T>(
+  deserializer: D,
+) -> Result<T, D::Error>
+where
+  D: Deserializer<'de>,
+  T: Default + Deserialize<'de>,
+{
+  Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+/// A number, or a string holding one
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Lenient<T> {
+  Number(T),
+  String(String),
+}
+
+impl<T: FromStr> Lenient<T>
+where
+  T::Err: Display,
+{
+  fn parse<E: de::Error>(self) -> Result<T, E> {
+    match self {
+      Self::Number(number) => Ok(number),
+      Self::String(string) => string.trim().parse().map_err(|error| {
+        E::custom(format!("invalid number `{}`: {}", string, error))
+      }),
+    }
+  }
+}
+
+/// Deserialize a number that the API sometimes sends as a numeric string,
+/// such as `"42"`
+///
+/// The export and list endpoints disagree on the types of some fields, so
+/// identifiers and locations accept either representation.
+pub(crate) fn deserialize_lenient_number<'de, D, T>(
+  deserializer: D,
+) -> Result<T, D::Error>
+where
+  D: Deserializer<'de>,
+  T: FromStr + Deserialize<'de>,
+  T::Err: Display,
+{
+  Lenient::deserialize(deserializer)?.parse()
+}
+
+/// Deserialize an optional number like `deserialize_lenient_number`,
+/// treating an empty string like `null`
+///
+/// Use with `#[serde(default)]` so that a missing field is `None`.
+pub(crate) fn deserialize_lenient_option<'de, D, T>(
+  deserializer: D,
+) -> Result<Option<T>, D::Error>
+where
+  D: Deserializer<'de>,
+  T: FromStr + Deserialize<'de>,
+  T::Err: Display,
+{
+  match Option::<Lenient<T>>::deserialize(deserializer)? {
+    Some(Lenient::String(string)) if string.trim().is_empty() => Ok(None),
+    lenient => lenient.map(Lenient::parse).transpose(),
+  }
+}
+
+#[cfg(all(test, any(feature = "blocking", feature = "async")))]
+mod tests {
+  use super::*;
+
+  #[test]
+  #[cfg(any(feature = "blocking", feature = "async"))]
+  fn error_path() {
+    let error = from_json::<HighlightsResponse>(
+      br#"{
+        "count": 2,
+        "next": null,
+        "previous": null,
+        "results": [
+          { "id": 1, "text": "", "note": "", "location": 0,
+            "location_type": "order", "highlighted_at": null, "url": null,
+            "color": "", "updated": "", "book_id": null },
+          { "id": 2, "text": "", "note": "", "location": 0,
+            "location_type": "order", "highlighted_at": 7, "url": null,
+            "color": "", "updated": "", "book_id": null }
+        ]
+      }"#,
+    )
+    .unwrap_err();
+
+    assert!(
+      matches!(&error, Error::DeserializeAt { path, .. } if path == "results[1].highlighted_at"),
+      "{}",
+      error
+    );
+    assert!(error
+      .to_string()
+      .starts_with("Failed to deserialize `results[1].highlighted_at`: "));
+  }
+
+  #[test]
+  fn missing_field_path() {
+    let error =
+      from_json::<Vec<Tag>>(br#"[{ "id": 1, "name": "a" }, { "id": 2 }]"#)
+        .unwrap_err();
+
+    assert!(
+      matches!(&error, Error::DeserializeAt { path, .. } if path == "[1]"),
+      "{}",
+      error
+    );
+  }
+
+  fn path_of<T: DeserializeOwned + fmt::Debug>(json: &str) -> String {
+    match from_json::<T>(json.as_bytes()).unwrap_err() {
+      Error::DeserializeAt { path, .. } => path,
+      error => panic!("{}", error),
+    }
+  }
+
+  #[test]
+  fn nested_array_paths() {
+    assert_eq!(path_of::<Vec<Vec<u64>>>(r#"[[1, 2], [3, "x"]]"#), "[1][1]");
+    assert_eq!(
+      path_of::<Vec<Vec<Vec<u64>>>>(r#"[[], [[1]], [[2, 3, {}]]]"#),
+      "[2][0][2]"
+    );
+    assert_eq!(
+      path_of::<HashMap<String, Vec<Vec<u64>>>>(
+        r#"{ "multi\nline \"key\"": [[1], [true]] }"#
+      ),
+      "multi\nline \"key\"[1][0]"
+    );
+    assert_eq!(
+      path_of::<Vec<(u64, u64)>>("[[1, 2], [3]]"),
+      "[1]",
+      "an error at a closing bracket belongs to its array"
+    );
+    assert_eq!(
+      path_of::<ExportResponse>(
+        r#"{
+          "count": 1,
+          "nextPageCursor": null,
+          "results": [{
+            "user_book_id": 1, "title": "", "readable_title": "",
+            "category": "books", "book_tags": [], "readwise_url": "",
+            "highlights": [
+              { "id": 1, "text": "", "location": 0, "location_type": "order",
+                "color": "yellow", "book_id": 1, "tags": [],
+                "is_favorite": false, "is_discard": false, "readwise_url": "" },
+              { "id": 2, "text": "", "location": 0, "location_type": "order",
+                "color": "yellow", "book_id": 1,
+                "tags": [{ "id": "b", "name": "b" }],
+                "is_favorite": false, "is_discard": false, "readwise_url": "" }
+            ]
+          }]
+        }"#
+      ),
+      "results[0].highlights[1].tags[0].id"
+    );
+  }
+
+  #[test]
+  fn invalid_numeric_string() {
+    let error =
+      from_json::<Tag>(br#"{ "id": "1a", "name": "a" }"#).unwrap_err();
+
+    assert!(
+      error.to_string().contains("invalid number `1a`"),
+      "{}",
+      error
+    );
+  }
+
+  #[test]
+  fn invalid_json() {
+    assert!(matches!(
+      from_json::<Tag>(b"{"),
+      Err(Error::Deserialize { .. })
+    ));
+  }
+
+  #[test]
+  fn valid_json() {
+    assert_eq!(
+      from_json::<Tag>(br#"{ "id": 1, "name": "a" }"#)
+        .unwrap()
+        .name,
+      "a"
+    );
+  }
+}
@@ -3,18 +3,408 @@ use crate::common::*;
 #[derive(Debug, Snafu)]
 #[snafu(visibility(pub(crate)))]
 pub enum Error {
-  #[snafu(context(false), display("Request error: {}", source))]
+  #[snafu(display("Request error: {}", source))]
   Client { source: reqwest::Error },
 
+  #[snafu(display("Request timed out: {}", source))]
+  Timeout { source: reqwest::Error },
+
+  /// A response body that is not valid JSON, or that does not match the
+  /// expected type at its top level, leaving `DeserializeAt` no path to report
   #[snafu(context(false), display("Serde JSON error: {}", source))]
   Deserialize { source: serde_json::Error },
 
+  /// A response body that is valid JSON but does not match the expected type,
+  /// along with the path to the offending value, such as
+  /// `results[42].highlighted_at`
+  ///
+  /// The path is a best guess: the body is parsed again pretty printed, one
+  /// value per line, and the line serde reports the error on is mapped back to
+  /// a value. An error reported on a closing bracket, such as a missing field
+  /// or a short array, names the object or array itself. An error in a value
+  /// serde buffers first, as with `#[serde(flatten)]` or internally tagged
+  /// enums, names the enclosing object. Keys are joined with `.` as they are,
+  /// so a key containing `.` or `[` makes the path ambiguous.
+  #[snafu(display("Failed to deserialize `{}`: {}", path, source))]
+  DeserializeAt {
+    path: String,
+    source: serde_json::Error,
+  },
+
+  #[cfg(feature = "csv")]
+  #[snafu(context(false), display("CSV error: {}", source))]
+  Csv { source: ::csv::Error },
+
   #[snafu(context(false), display("Invalid header value: {}", source))]
   HeaderValue { source: header::InvalidHeaderValue },
 
+  #[snafu(display("Invalid header `{}`: {}", name, source))]
+  InvalidHeader { name: String, source: http::Error },
+
+  #[snafu(context(false), display("I/O error: {}", source))]
+  Io { source: std::io::Error },
+
+  #[snafu(display("Environment variable `{}` is not set: {}", name, source))]
+  EnvironmentVariable {
+    name: String,
+    source: std::env::VarError,
+  },
+
+  #[snafu(display("Invalid webhook payload: {}", source))]
+  Webhook { source: serde_json::Error },
+
+  #[snafu(display(
+    "Unknown {} `{}`, expected one of: {}",
+    kind,
+    value,
+    expected.join(", ")
+  ))]
+  UnknownValue {
+    kind: &'static str,
+    value: String,
+    expected: Vec<&'static str>,
+  },
+
+  /// A timestamp argument, such as a bound of `Client::export_highlighted`,
+  /// that is neither RFC 3339 nor a bare date such as `2022-10-01`, meaning
+  /// midnight UTC; timestamp arguments are checked before any request is sent
+  #[snafu(display(
+    "Invalid timestamp `{}`, expected RFC 3339 such as `2022-10-01T00:00:00Z`",
+    value
+  ))]
+  InvalidTimestamp { value: String },
+
+  #[snafu(display(
+    "Invalid page URL `{}`, expected a {} URL from Readwise",
+    url,
+    endpoint
+  ))]
+  InvalidPageUrl { url: String, endpoint: &'static str },
+
+  #[cfg(feature = "block-on")]
+  #[snafu(display(
+    "`AsyncClient::block_on` cannot be called from within an async runtime, \
+     await the future instead"
+  ))]
+  NestedRuntime,
+
+  #[snafu(display("Cancelled: the client's cancel flag is set"))]
+  Cancelled,
+
   #[snafu(display("Unsupported request method: {}", method.to_string()))]
   UnsupportedRequest { method: Method },
 
+  #[snafu(display("{}", source))]
+  Shared { source: std::sync::Arc<Error> },
+
+  #[snafu(display("Export failed after {} lines: {}", lines, source))]
+  Export { lines: u64, source: Box<Error> },
+
+  #[snafu(display(
+    "Cannot resume the export to `{}`, which was started with a different \
+     `updated_after` of {:?}",
+    path,
+    started
+  ))]
+  ExportResumeMismatch {
+    path: String,
+    started: Option<String>,
+  },
+
+  #[snafu(display("API error ({}): {}", status.to_string(), message))]
+  Api {
+    status: StatusCode,
+    message: String,
+    /// Validation messages by field, when the body has the
+    /// `{"field": ["message"]}` shape, and otherwise empty
+    fields: HashMap<String, Vec<String>>,
+    meta: Box<ResponseMeta>,
+  },
+
+  #[snafu(display(
+    "Rate limited{}",
+    retry_after
+      .map(|wait| format!(", retry after {}s", wait.as_secs()))
+      .unwrap_or_default()
+  ))]
+  RateLimited {
+    retry_after: Option<Duration>,
+    meta: Box<ResponseMeta>,
+  },
+
+  #[snafu(display(
+    "Missing access token: the token is blank, check that the variable it is \
+     read from is set"
+  ))]
+  MissingToken,
+
+  #[snafu(display("Unauthorized: the access token is invalid or revoked"))]
+  Unauthorized { meta: Box<ResponseMeta> },
+
   #[snafu(display("Bad request: {}", status.to_string()))]
-  BadRequest { status: StatusCode },
+  BadRequest {
+    status: StatusCode,
+    meta: Box<ResponseMeta>,
+  },
+}
+
+impl From<reqwest::Error> for Error {
+  fn from(source: reqwest::Error) -> Self {
+    match source.is_timeout() {
+      true => Error::Timeout { source },
+      false => Error::Client { source },
+    }
+  }
+}
+
+impl Error {
+  pub(crate) fn unknown_value(
+    kind: &'static str,
+    value: &str,
+    expected: impl IntoIterator<Item = &'static str>,
+  ) -> Self {
+    Error::UnknownValue {
+      kind,
+      value: value.to_string(),
+      expected: expected.into_iter().collect(),
+    }
+  }
+
+  /// Whether the failed request may succeed if sent again
+  ///
+  /// Timeouts, rate limits and server errors are retryable, while client
+  /// errors such as `400`, `401` and `404` and deserialization failures are
+  /// not.
+  ///
+  /// ```no_run
+  /// use readwise::client::Client;
+  ///
+  /// let client = Client::new("token").unwrap();
+  ///
+  /// let books = loop {
+  ///   match client.books(1) {
+  ///     Err(error) if error.is_retryable() => continue,
+  ///     result => break result.unwrap(),
+  ///   }
+  /// };
+  /// ```
+  pub fn is_retryable(&self) -> bool {
+    match self {
+      Error::Timeout { .. } | Error::RateLimited { .. } => true,
+      Error::Api { status, .. } | Error::BadRequest { status, .. } => {
+        status.is_server_error()
+      }
+      Error::Shared { source } => source.is_retryable(),
+      Error::Export { source, .. } => source.is_retryable(),
+      _ => false,
+    }
+  }
+
+  /// The validation messages of a rejected request by field, such as
+  /// `text: ["This field is required."]`, if the API sent any
+  ///
+  /// ```no_run
+  /// use {readwise::client::Client, std::collections::HashMap};
+  ///
+  /// let client = Client::new("token").unwrap();
+  ///
+  /// let mut highlight = HashMap::new();
+  /// highlight.insert("title", "No text");
+  ///
+  /// if let Err(error) = client.create_highlights(vec![highlight]) {
+  ///   for (field, messages) in error.field_errors().into_iter().flatten() {
+  ///     println!("{}: {}", field, messages.join(" "));
+  ///   }
+  /// }
+  /// ```
+  pub fn field_errors(&self) -> Option<&HashMap<String, Vec<String>>> {
+    match self {
+      Error::Api { fields, .. } if !fields.is_empty() => Some(fields),
+      Error::Shared { source } => source.field_errors(),
+      Error::Export { source, .. } => source.field_errors(),
+      _ => None,
+    }
+  }
+
+  /// The error for an unsuccessful response, preferring the message in a
+  /// `400 Bad Request` body when the API provides one
+  #[cfg(any(feature = "blocking", feature = "async"))]
+  pub(crate) fn from_response(meta: ResponseMeta, body: &str) -> Self {
+    if meta.status == StatusCode::TOO_MANY_REQUESTS {
+      return Error::RateLimited {
+        retry_after: retry_after(&meta.headers),
+        meta: Box::new(meta),
+      };
+    }
+
+    if meta.status == StatusCode::UNAUTHORIZED {
+      return Error::Unauthorized {
+        meta: Box::new(meta),
+      };
+    }
+
+    if meta.status != StatusCode::BAD_REQUEST || body.trim().is_empty() {
+      return Error::BadRequest {
+        status: meta.status,
+        meta: Box::new(meta),
+      };
+    }
+
+    let text = |message: &serde_json::Value| match message {
+      serde_json::Value::String(message) => message.clone(),
+      message => message.to_string(),
+    };
+
+    let (message, fields) =
+      match serde_json::from_str::<serde_json::Value>(body) {
+        Ok(serde_json::Value::Object(fields)) => match fields.get("detail") {
+          Some(serde_json::Value::String(detail)) => {
+            (detail.clone(), HashMap::new())
+          }
+          _ => {
+            let fields = fields
+              .iter()
+              .map(|(field, messages)| {
+                let messages = match messages {
+                  serde_json::Value::Array(messages) => {
+                    messages.iter().map(text).collect()
+                  }
+                  message => vec![text(message)],
+                };
+
+                (field.clone(), messages)
+              })
+              .collect::<Vec<(String, Vec<String>)>>();
+
+            (
+              fields
+                .iter()
+                .map(|(field, messages)| {
+                  format!("{}: {}", field, messages.join(" "))
+                })
+                .collect::<Vec<_>>()
+                .join("; "),
+              fields.into_iter().collect(),
+            )
+          }
+        },
+        _ => (body.trim().to_string(), HashMap::new()),
+      };
+
+    Error::Api {
+      status: meta.status,
+      message,
+      fields,
+      meta: Box::new(meta),
+    }
+  }
+}
+
+/// The wait requested by a `Retry-After` header given in seconds
+#[cfg(any(feature = "blocking", feature = "async"))]
+pub(crate) fn retry_after(headers: &header::HeaderMap) -> Option<Duration> {
+  headers
+    .get(header::RETRY_AFTER)?
+    .to_str()
+    .ok()?
+    .trim()
+    .parse()
+    .ok()
+    .map(Duration::from_secs)
+}
+
+#[cfg(all(test, any(feature = "blocking", feature = "async")))]
+mod tests {
+  use {super::*, std::error::Error as _};
+
+  fn response(status: u16) -> Error {
+    response_with_body(status, "")
+  }
+
+  fn response_with_body(status: u16, body: &str) -> Error {
+    Error::from_response(
+      ResponseMeta {
+        status: StatusCode::from_u16(status).unwrap(),
+        headers: header::HeaderMap::new(),
+        url: String::new(),
+        elapsed: Duration::ZERO,
+      },
+      body,
+    )
+  }
+
+  #[test]
+  fn field_errors() {
+    let error = response_with_body(
+      400,
+      r#"{ "text": ["This field is required."], "location": "Invalid." }"#,
+    );
+
+    assert_eq!(
+      error.to_string(),
+      "API error (400 Bad Request): location: Invalid.; text: This field is \
+       required."
+    );
+
+    assert_eq!(
+      error.field_errors(),
+      Some(&HashMap::from([
+        (
+          "text".to_string(),
+          vec!["This field is required.".to_string()]
+        ),
+        ("location".to_string(), vec!["Invalid.".to_string()]),
+      ]))
+    );
+
+    for body in [r#"{ "detail": "Not allowed." }"#, "Bad request"] {
+      let error = response_with_body(400, body);
+      assert!(matches!(error, Error::Api { .. }), "{}", body);
+      assert_eq!(error.field_errors(), None, "{}", body);
+    }
+
+    assert_eq!(response(400).field_errors(), None);
+  }
+
+  #[test]
+  fn is_retryable() {
+    for status in [429, 500, 502, 503] {
+      assert!(response(status).is_retryable(), "{}", status);
+    }
+
+    for status in [400, 401, 403, 404] {
+      assert!(!response(status).is_retryable(), "{}", status);
+    }
+
+    assert!(
+      !Error::from(serde_json::from_str::<u64>("").unwrap_err()).is_retryable()
+    );
+
+    assert!(Error::Shared {
+      source: std::sync::Arc::new(response(503)),
+    }
+    .is_retryable());
+
+    assert!(Error::Export {
+      lines: 1,
+      source: Box::new(response(429)),
+    }
+    .is_retryable());
+  }
+
+  #[test]
+  fn sources_are_chained() {
+    let error = Error::Export {
+      lines: 1,
+      source: Box::new(Error::from(
+        serde_json::from_str::<u64>("").unwrap_err(),
+      )),
+    };
+
+    let source = error.source().unwrap();
+
+    assert!(source.to_string().starts_with("Serde JSON error"));
+
+    assert!(source.source().unwrap().is::<serde_json::Error>());
+  }
 }
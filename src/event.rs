@@ -0,0 +1,24 @@
+use crate::common::*;
+
+/// Progress reported by long-running operations to the handler registered
+/// with `Client::on_event`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+  /// A page of results was fetched
+  PageFetched { page: u64, items: u64 },
+  /// A highlight was created
+  HighlightCreated { id: u64 },
+  /// A failed request is about to be retried after waiting `after`
+  Retrying { attempt: u32, after: Duration },
+  /// The API asked the client to wait before sending more requests
+  RateLimited { wait: Duration },
+  /// A write request was skipped by `ClientBuilder::dry_run`, with its
+  /// endpoint and JSON body
+  DryRun {
+    method: Method,
+    endpoint: String,
+    body: Option<String>,
+  },
+  /// The operation finished having processed `total` items
+  Done { total: u64 },
+}
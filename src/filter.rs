@@ -0,0 +1,302 @@
+use crate::common::*;
+
+/// The largest page size accepted by the list endpoints
+#[cfg(any(feature = "blocking", feature = "async"))]
+pub(crate) const MAX_PAGE_SIZE: u64 = 1000;
+
+/// Query parameters for `Client::books_with`
+///
+/// ```
+/// use readwise::{filter::BookFilter, model::Category};
+///
+/// let filter = BookFilter::new()
+///   .page(2)
+///   .category(Category::Articles)
+///   .updated_after("2022-10-01T00:00:00Z");
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct BookFilter {
+  page: Option<u64>,
+  page_size: Option<u64>,
+  category: Option<Category>,
+  updated_after: Option<String>,
+  updated_before: Option<String>,
+  last_highlighted_after: Option<String>,
+}
+
+impl BookFilter {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// The page to fetch, starting from 1
+  pub fn page(mut self, page: u64) -> Self {
+    self.page = Some(page);
+    self
+  }
+
+  /// The number of books per page, at most 1000
+  pub fn page_size(mut self, page_size: u64) -> Self {
+    self.page_size = Some(page_size);
+    self
+  }
+
+  /// Only include books of this category
+  pub fn category(mut self, category: Category) -> Self {
+    self.category = Some(category);
+    self
+  }
+
+  /// Only include books updated after this RFC 3339 timestamp
+  pub fn updated_after(mut self, timestamp: &str) -> Self {
+    self.updated_after = Some(timestamp.to_string());
+    self
+  }
+
+  /// Only include books updated before this RFC 3339 timestamp
+  pub fn updated_before(mut self, timestamp: &str) -> Self {
+    self.updated_before = Some(timestamp.to_string());
+    self
+  }
+
+  /// Only include books highlighted after this RFC 3339 timestamp
+  pub fn last_highlighted_after(mut self, timestamp: &str) -> Self {
+    self.last_highlighted_after = Some(timestamp.to_string());
+    self
+  }
+
+  #[cfg(any(feature = "blocking", feature = "async"))]
+  pub(crate) fn query(&self) -> String {
+    let mut query = form_urlencoded::Serializer::new(String::new());
+
+    if let Some(page) = self.page {
+      query.append_pair("page", &page.to_string());
+    }
+
+    if let Some(page_size) = self.page_size {
+      query.append_pair("page_size", &page_size.to_string());
+    }
+
+    if let Some(category) = self.category {
+      query.append_pair("category", category.as_str());
+    }
+
+    if let Some(updated_after) = &self.updated_after {
+      query.append_pair("updated__gt", updated_after);
+    }
+
+    if let Some(updated_before) = &self.updated_before {
+      query.append_pair("updated__lt", updated_before);
+    }
+
+    if let Some(last_highlighted_after) = &self.last_highlighted_after {
+      query.append_pair("last_highlight_at__gt", last_highlighted_after);
+    }
+
+    query.finish()
+  }
+
+  /// The books list endpoint with this filter as its query
+  #[cfg(any(feature = "blocking", feature = "async"))]
+  pub(crate) fn endpoint(&self) -> String {
+    append_query("/books", &self.query())
+  }
+}
+
+/// Query parameters for `Client::highlights_with`
+///
+/// The API cannot filter on favorites or discards, so those conditions are
+/// applied to each page after it is fetched, and the page's `count` and
+/// links still describe the unfiltered list.
+///
+/// ```
+/// use readwise::filter::HighlightFilter;
+///
+/// let filter = HighlightFilter::new().book_id(1).page_size(100).favorite(true);
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct HighlightFilter {
+  page: Option<u64>,
+  page_size: Option<u64>,
+  book_id: Option<u64>,
+  updated_after: Option<String>,
+  updated_before: Option<String>,
+  favorite: Option<bool>,
+  discarded: Option<bool>,
+}
+
+impl HighlightFilter {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// The page to fetch, starting from 1
+  pub fn page(mut self, page: u64) -> Self {
+    self.page = Some(page);
+    self
+  }
+
+  /// The number of highlights per page, at most 1000
+  pub fn page_size(mut self, page_size: u64) -> Self {
+    self.page_size = Some(page_size);
+    self
+  }
+
+  /// Only include highlights of this book
+  pub fn book_id(mut self, book_id: u64) -> Self {
+    self.book_id = Some(book_id);
+    self
+  }
+
+  /// Only include highlights updated after this RFC 3339 timestamp
+  pub fn updated_after(mut self, timestamp: &str) -> Self {
+    self.updated_after = Some(timestamp.to_string());
+    self
+  }
+
+  /// Only include highlights updated before this RFC 3339 timestamp
+  pub fn updated_before(mut self, timestamp: &str) -> Self {
+    self.updated_before = Some(timestamp.to_string());
+    self
+  }
+
+  /// Only include highlights that are, or are not, favorited
+  pub fn favorite(mut self, favorite: bool) -> Self {
+    self.favorite = Some(favorite);
+    self
+  }
+
+  /// Only include highlights that are, or are not, discarded
+  pub fn discarded(mut self, discarded: bool) -> Self {
+    self.discarded = Some(discarded);
+    self
+  }
+
+  /// Whether `highlight` meets the conditions applied after fetching
+  ///
+  /// ```
+  /// use readwise::{filter::HighlightFilter, model::Highlight};
+  ///
+  /// let highlight = Highlight {
+  ///   is_favorite: true,
+  ///   ..Default::default()
+  /// };
+  ///
+  /// assert!(HighlightFilter::new().favorite(true).matches(&highlight));
+  /// assert!(!HighlightFilter::new().discarded(true).matches(&highlight));
+  /// ```
+  pub fn matches(&self, highlight: &Highlight) -> bool {
+    self
+      .favorite
+      .is_none_or(|favorite| highlight.is_favorite == favorite)
+      && self
+        .discarded
+        .is_none_or(|discarded| highlight.is_discard == discarded)
+  }
+
+  #[cfg(any(feature = "blocking", feature = "async"))]
+  pub(crate) fn query(&self) -> String {
+    let mut query = form_urlencoded::Serializer::new(String::new());
+
+    if let Some(page) = self.page {
+      query.append_pair("page", &page.to_string());
+    }
+
+    if let Some(page_size) = self.page_size {
+      query.append_pair("page_size", &page_size.to_string());
+    }
+
+    if let Some(book_id) = self.book_id {
+      query.append_pair("book_id", &book_id.to_string());
+    }
+
+    if let Some(updated_after) = &self.updated_after {
+      query.append_pair("updated__gt", updated_after);
+    }
+
+    if let Some(updated_before) = &self.updated_before {
+      query.append_pair("updated__lt", updated_before);
+    }
+
+    query.finish()
+  }
+
+  /// The highlights list endpoint with this filter as its query
+  #[cfg(any(feature = "blocking", feature = "async"))]
+  pub(crate) fn endpoint(&self) -> String {
+    append_query("/highlights", &self.query())
+  }
+}
+
+#[cfg(all(test, any(feature = "blocking", feature = "async")))]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn highlight_filter_matches() {
+    let highlights = [(false, false), (true, false), (false, true)].map(
+      |(is_favorite, is_discard)| Highlight {
+        is_favorite,
+        is_discard,
+        ..Default::default()
+      },
+    );
+
+    let matching = |filter: HighlightFilter| {
+      highlights
+        .iter()
+        .map(|highlight| filter.matches(highlight))
+        .collect::<Vec<bool>>()
+    };
+
+    assert_eq!(matching(HighlightFilter::new()), [true, true, true]);
+    assert_eq!(
+      matching(HighlightFilter::new().favorite(true)),
+      [false, true, false]
+    );
+    assert_eq!(
+      matching(HighlightFilter::new().discarded(false)),
+      [true, true, false]
+    );
+    assert_eq!(
+      HighlightFilter::new().favorite(true).book_id(1).query(),
+      "book_id=1"
+    );
+    assert_eq!(
+      HighlightFilter::new()
+        .updated_after("2022-10-01")
+        .updated_before("2022-11-01")
+        .query(),
+      "updated__gt=2022-10-01&updated__lt=2022-11-01"
+    );
+  }
+
+  #[test]
+  fn endpoints() {
+    assert_eq!(BookFilter::new().endpoint(), "/books");
+    assert_eq!(BookFilter::new().page(3).endpoint(), "/books?page=3");
+    assert_eq!(
+      HighlightFilter::new().page(2).book_id(7).endpoint(),
+      "/highlights?page=2&book_id=7"
+    );
+  }
+
+  #[test]
+  fn book_filter_query() {
+    assert_eq!(BookFilter::new().query(), "");
+
+    assert_eq!(
+      BookFilter::new()
+        .page(2)
+        .category(Category::Books)
+        .updated_after("2022-10-01T00:00:00Z")
+        .updated_before("2022-11-01T00:00:00+02:00")
+        .last_highlighted_after("2022-10-15T00:00:00Z")
+        .query(),
+      "page=2&category=books\
+       &updated__gt=2022-10-01T00%3A00%3A00Z\
+       &updated__lt=2022-11-01T00%3A00%3A00%2B02%3A00\
+       &last_highlight_at__gt=2022-10-15T00%3A00%3A00Z"
+    );
+  }
+}
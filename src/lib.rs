@@ -13,7 +13,7 @@
 //! use {
 //!   std::{collections::HashMap, env},
 //!   dotenv::dotenv,
-//!   readwise::client::Client
+//!   readwise::{client::Client, model::HighlightUpdate},
 //! };
 //!
 //! dotenv().ok();
@@ -39,16 +39,64 @@
 //! }
 //!
 //! // Update a highlight by ID
-//! let mut fields = HashMap::new();
-//! fields.insert("text", "hello, world!");
-//! client.update_highlight(138105649, fields).unwrap();
+//! client
+//!   .update_highlight(
+//!     138105649,
+//!     HighlightUpdate {
+//!       text: Some("hello, world!".into()),
+//!       ..Default::default()
+//!     },
+//!   )
+//!   .unwrap();
 //!
 //! // Delete a highlight by ID
 //! client.delete_highlight(136887156).unwrap();
 //! ```
+
+/// The version of this crate, which is also part of the default `User-Agent`
+/// header, `readwise/<VERSION>`
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[cfg(feature = "blocking")]
+mod cache;
 mod common;
+mod de;
+#[cfg(any(feature = "blocking", feature = "async"))]
+mod settings;
+#[cfg(any(feature = "blocking", feature = "async"))]
+mod throttle;
+#[cfg(feature = "blocking")]
+mod timestamp;
+#[cfg(any(feature = "blocking", feature = "async"))]
+mod token;
 mod url;
 
+#[cfg(feature = "async")]
+pub mod async_client;
+#[cfg(feature = "blocking")]
 pub mod client;
+#[cfg(feature = "csv")]
+pub mod csv;
 pub mod error;
+pub mod event;
+pub mod filter;
+#[cfg(feature = "markdown")]
+pub mod markdown;
 pub mod model;
+#[cfg(feature = "blocking")]
+pub mod observer;
+pub mod prelude;
+pub mod response;
+#[cfg(feature = "blocking")]
+pub mod response_cache;
+#[cfg(any(feature = "blocking", feature = "async"))]
+pub mod retry;
+#[cfg(any(feature = "blocking", feature = "async"))]
+pub mod sleep;
+pub mod stats;
+#[cfg(feature = "blocking")]
+pub mod testing;
+pub mod text;
+#[cfg(feature = "blocking")]
+pub mod transport;
+pub mod webhook;
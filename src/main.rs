@@ -0,0 +1,335 @@
+use {
+  clap::{Parser, Subcommand, ValueEnum},
+  readwise::{
+    client::ClientBuilder,
+    error::Error,
+    filter::{BookFilter, HighlightFilter},
+    model::{Category, DeleteOutcome, ExportBook, HighlightCreate},
+  },
+  serde::Serialize,
+  std::{
+    env,
+    fs::File,
+    io::{self, Write},
+    path::PathBuf,
+    process,
+  },
+};
+
+/// The environment variable the access token is read from
+const TOKEN_ENV_VAR: &str = "READWISE_ACCESS_TOKEN";
+
+/// The environment variable `--base-url` is read from when not given
+const BASE_URL_ENV_VAR: &str = "READWISE_BASE_URL";
+
+#[derive(Debug, Parser)]
+#[command(name = "readwise", version, about = "A command-line Readwise client")]
+struct Arguments {
+  /// Print results as JSON instead of a table
+  #[arg(long, global = true)]
+  json: bool,
+  /// Send requests to this server instead of https://readwise.io
+  #[arg(long, global = true, env = BASE_URL_ENV_VAR)]
+  base_url: Option<String>,
+  #[command(subcommand)]
+  command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+  /// Work with books
+  Books {
+    #[command(subcommand)]
+    command: BooksCommand,
+  },
+  /// Work with lists of highlights
+  Highlights {
+    #[command(subcommand)]
+    command: HighlightsCommand,
+  },
+  /// Work with a single highlight
+  Highlight {
+    #[command(subcommand)]
+    command: HighlightCommand,
+  },
+  /// Export every book and its highlights
+  Export {
+    /// The output format
+    #[arg(long, value_enum, default_value_t = Format::Json)]
+    format: Format,
+    /// The file to write to
+    #[arg(long)]
+    out: PathBuf,
+  },
+}
+
+#[derive(Debug, Subcommand)]
+enum BooksCommand {
+  /// List a page of books
+  List {
+    #[arg(long, default_value_t = 1)]
+    page: u64,
+    #[arg(long)]
+    category: Option<Category>,
+  },
+}
+
+#[derive(Debug, Subcommand)]
+enum HighlightsCommand {
+  /// List a page of highlights
+  List {
+    #[arg(long)]
+    book_id: Option<u64>,
+    #[arg(long, default_value_t = 1)]
+    page: u64,
+  },
+}
+
+#[derive(Debug, Subcommand)]
+enum HighlightCommand {
+  /// Create a highlight
+  Create {
+    #[arg(long)]
+    text: String,
+    #[arg(long)]
+    title: Option<String>,
+  },
+  /// Delete a highlight by identifier
+  Delete { id: u64 },
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Format {
+  Json,
+  Md,
+  Csv,
+}
+
+fn print<T: Serialize>(json: bool, items: &[T], row: impl Fn(&T) -> String) {
+  match json {
+    true => println!("{}", serde_json::to_string_pretty(items).unwrap()),
+    false => items.iter().for_each(|item| println!("{}", row(item))),
+  }
+}
+
+fn csv_field(field: &str) -> String {
+  match field.contains([',', '"', '\n', '\r']) {
+    true => format!("\"{}\"", field.replace('"', "\"\"")),
+    false => field.to_string(),
+  }
+}
+
+fn write_export(
+  books: &[ExportBook],
+  format: Format,
+  mut writer: impl Write,
+) -> io::Result<()> {
+  match format {
+    Format::Json => {
+      serde_json::to_writer_pretty(&mut writer, books)?;
+      writeln!(writer)?;
+    }
+    Format::Md => {
+      for book in books {
+        writeln!(writer, "# {}\n", book.title)?;
+
+        for highlight in &book.highlights {
+          writeln!(writer, "> {}\n", highlight.text.replace('\n', "\n> "))?;
+
+          if !highlight.note.is_empty() {
+            writeln!(writer, "{}\n", highlight.note)?;
+          }
+        }
+      }
+    }
+    Format::Csv => {
+      writeln!(writer, "title,author,text,note,location,highlighted_at")?;
+
+      for book in books {
+        for highlight in &book.highlights {
+          writeln!(
+            writer,
+            "{},{},{},{},{},{}",
+            csv_field(&book.title),
+            csv_field(book.author.as_deref().unwrap_or_default()),
+            csv_field(&highlight.text),
+            csv_field(&highlight.note),
+            highlight
+              .location
+              .map(|location| location.to_string())
+              .unwrap_or_default(),
+            highlight.highlighted_at.as_deref().unwrap_or_default(),
+          )?;
+        }
+      }
+    }
+  }
+
+  writer.flush()
+}
+
+fn run(arguments: Arguments) -> Result<(), Error> {
+  let token =
+    env::var(TOKEN_ENV_VAR).map_err(|source| Error::EnvironmentVariable {
+      name: TOKEN_ENV_VAR.to_string(),
+      source,
+    })?;
+
+  let builder = ClientBuilder::new(&token);
+
+  let client = match &arguments.base_url {
+    Some(base_url) => builder.base_url(base_url),
+    None => builder,
+  }
+  .build_and_auth()?;
+
+  match arguments.command {
+    Command::Books {
+      command: BooksCommand::List { page, category },
+    } => {
+      let filter = BookFilter::new().page(page);
+
+      let filter = match category {
+        Some(category) => filter.category(category),
+        None => filter,
+      };
+
+      print(
+        arguments.json,
+        &client.books_with(&filter)?.results,
+        |book| format!("{:>10}  {}", book.id, book),
+      );
+    }
+    Command::Highlights {
+      command: HighlightsCommand::List { book_id, page },
+    } => {
+      let filter = HighlightFilter::new().page(page);
+
+      let filter = match book_id {
+        Some(book_id) => filter.book_id(book_id),
+        None => filter,
+      };
+
+      print(
+        arguments.json,
+        &client.highlights_with(&filter)?.results,
+        |highlight| format!("{:>10}  {}", highlight.id, highlight),
+      );
+    }
+    Command::Highlight {
+      command: HighlightCommand::Create { text, title },
+    } => {
+      let highlights = client.create_highlights(vec![HighlightCreate {
+        text,
+        title,
+        ..Default::default()
+      }])?;
+
+      print(arguments.json, &highlights, |highlight| {
+        format!("{:>10}  {}", highlight.id, highlight)
+      });
+    }
+    Command::Highlight {
+      command: HighlightCommand::Delete { id },
+    } => {
+      if client.delete_highlight(id)? == DeleteOutcome::NotFound {
+        eprintln!("highlight {} does not exist", id);
+      }
+    }
+    Command::Export { format, out } => {
+      write_export(&client.export(None, None)?, format, File::create(out)?)?
+    }
+  }
+
+  Ok(())
+}
+
+fn main() {
+  if let Err(error) = run(Arguments::parse()) {
+    eprintln!("error: {}", error);
+    process::exit(1);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use {super::*, clap::CommandFactory, readwise::model::ExportHighlight};
+
+  fn books() -> Vec<ExportBook> {
+    vec![ExportBook {
+      title: "Quotes".into(),
+      author: Some("Anonymous".into()),
+      highlights: vec![ExportHighlight {
+        text: "one, \"two\"\nthree".into(),
+        location: Some(1),
+        ..Default::default()
+      }],
+      ..Default::default()
+    }]
+  }
+
+  #[test]
+  fn export_csv() {
+    let mut output = Vec::new();
+
+    write_export(&books(), Format::Csv, &mut output).unwrap();
+
+    assert_eq!(
+      String::from_utf8(output).unwrap(),
+      "title,author,text,note,location,highlighted_at\n\
+       Quotes,Anonymous,\"one, \"\"two\"\"\nthree\",,1,\n"
+    );
+  }
+
+  #[test]
+  fn export_markdown() {
+    let mut output = Vec::new();
+
+    write_export(&books(), Format::Md, &mut output).unwrap();
+
+    assert_eq!(
+      String::from_utf8(output).unwrap(),
+      "# Quotes\n\n> one, \"two\"\n> three\n\n"
+    );
+  }
+
+  #[test]
+  fn category_argument() {
+    let arguments = Arguments::try_parse_from([
+      "readwise",
+      "books",
+      "list",
+      "--category",
+      "Articles",
+    ])
+    .unwrap();
+
+    assert!(matches!(
+      arguments.command,
+      Command::Books {
+        command: BooksCommand::List {
+          category: Some(Category::Articles),
+          ..
+        }
+      }
+    ));
+
+    let error = Arguments::try_parse_from([
+      "readwise",
+      "books",
+      "list",
+      "--category",
+      "magazines",
+    ])
+    .unwrap_err();
+
+    assert!(error.to_string().contains(
+      "Unknown category `magazines`, expected one of: books, articles"
+    ));
+  }
+
+  #[test]
+  fn arguments() {
+    Arguments::command().debug_assert();
+  }
+}
@@ -0,0 +1,277 @@
+//! Rendering of books and highlights as Markdown notes, in the style of
+//! Obsidian and Logseq imports
+
+use crate::common::*;
+
+/// Options controlling `Highlight::to_markdown_with` and
+/// `Book::to_markdown_with`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarkdownOptions {
+  /// The level of the heading holding the book title
+  pub heading_level: usize,
+  /// List the book's author, category and source under its title
+  pub metadata: bool,
+  /// Written before a highlight's note
+  pub note_prefix: String,
+  /// Render tags as `#hashtags`, with whitespace in names replaced by `-`
+  pub tags: bool,
+  /// Link each highlight to its page on readwise.io
+  pub links: bool,
+}
+
+impl Default for MarkdownOptions {
+  fn default() -> Self {
+    Self {
+      heading_level: 1,
+      metadata: true,
+      note_prefix: "**Note:** ".into(),
+      tags: true,
+      links: true,
+    }
+  }
+}
+
+impl Highlight {
+  /// Render this highlight as a Markdown quote block with the default
+  /// `MarkdownOptions`
+  ///
+  /// ```
+  /// use readwise::model::Highlight;
+  ///
+  /// let highlight = Highlight {
+  ///   id: 1,
+  ///   text: "Simplicity is prerequisite\nfor reliability.".into(),
+  ///   note: "Dijkstra".into(),
+  ///   ..Default::default()
+  /// };
+  ///
+  /// assert_eq!(
+  ///   highlight.to_markdown(),
+  ///   "> Simplicity is prerequisite\n\
+  ///    > for reliability.\n\
+  ///    \n\
+  ///    **Note:** Dijkstra\n\
+  ///    \n\
+  ///    [View on Readwise](https://readwise.io/open/1)\n"
+  /// );
+  /// ```
+  pub fn to_markdown(&self) -> String {
+    self.to_markdown_with(&MarkdownOptions::default())
+  }
+
+  /// Render this highlight as a Markdown quote block, followed by its note,
+  /// tags and link as configured by `options`
+  pub fn to_markdown_with(&self, options: &MarkdownOptions) -> String {
+    let mut blocks = vec![self
+      .text
+      .trim()
+      .lines()
+      .map(|line| format!("> {}", line).trim_end().to_string())
+      .collect::<Vec<String>>()
+      .join("\n")];
+
+    if !self.note.trim().is_empty() {
+      blocks.push(format!("{}{}", options.note_prefix, self.note.trim()));
+    }
+
+    if options.tags && !self.tags.is_empty() {
+      blocks.push(hashtags(&self.tags));
+    }
+
+    if options.links {
+      blocks.push(format!("[View on Readwise]({})", self.readwise_url()));
+    }
+
+    blocks.join("\n\n") + "\n"
+  }
+}
+
+impl Book {
+  /// Render this book and `highlights` as a Markdown note with the default
+  /// `MarkdownOptions`
+  ///
+  /// ```
+  /// use readwise::model::{Book, Highlight};
+  ///
+  /// let book = Book {
+  ///   title: "Notes on Structured Programming".into(),
+  ///   author: Some("Edsger W. Dijkstra".into()),
+  ///   ..Default::default()
+  /// };
+  ///
+  /// let highlights = [Highlight {
+  ///   id: 1,
+  ///   text: "Program testing can be used to show the presence of bugs".into(),
+  ///   ..Default::default()
+  /// }];
+  ///
+  /// assert!(book
+  ///   .to_markdown(&highlights)
+  ///   .starts_with("# Notes on Structured Programming\n\n- Author: Edsger W. Dijkstra\n"));
+  /// ```
+  pub fn to_markdown(&self, highlights: &[Highlight]) -> String {
+    self.to_markdown_with(highlights, &MarkdownOptions::default())
+  }
+
+  /// Render this book as a heading, optionally followed by its metadata, and
+  /// then each of `highlights` in order, as configured by `options`
+  pub fn to_markdown_with(
+    &self,
+    highlights: &[Highlight],
+    options: &MarkdownOptions,
+  ) -> String {
+    let mut blocks = vec![format!(
+      "{} {}",
+      "#".repeat(options.heading_level.clamp(1, 6)),
+      self.title.trim()
+    )];
+
+    if options.metadata {
+      let mut metadata = Vec::new();
+
+      if let Some(author) = &self.author {
+        metadata.push(format!("- Author: {}", author));
+      }
+
+      metadata.push(format!("- Category: {}", self.category));
+
+      if let Some(source_url) = &self.source_url {
+        metadata.push(format!("- Source: {}", source_url));
+      }
+
+      blocks.push(metadata.join("\n"));
+    }
+
+    let mut markdown = blocks.join("\n\n") + "\n";
+
+    for highlight in highlights {
+      markdown.push('\n');
+      markdown.push_str(&highlight.to_markdown_with(options));
+    }
+
+    markdown
+  }
+}
+
+/// Tags as space separated `#hashtags`, which may not contain whitespace
+fn hashtags(tags: &[Tag]) -> String {
+  tags
+    .iter()
+    .map(|tag| {
+      format!(
+        "#{}",
+        tag.name.split_whitespace().collect::<Vec<&str>>().join("-")
+      )
+    })
+    .collect::<Vec<String>>()
+    .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn highlight() -> Highlight {
+    Highlight {
+      id: 7,
+      text: "  First line\n\nSecond line  ".into(),
+      note: "A thought".into(),
+      highlight_url: Some("https://readwise.io/open/7".into()),
+      tags: vec![
+        Tag {
+          id: 1,
+          name: "favorite".into(),
+        },
+        Tag {
+          id: 2,
+          name: "to read".into(),
+        },
+      ],
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn highlight_to_markdown() {
+    assert_eq!(
+      highlight().to_markdown(),
+      "> First line\n\
+       >\n\
+       > Second line\n\
+       \n\
+       **Note:** A thought\n\
+       \n\
+       #favorite #to-read\n\
+       \n\
+       [View on Readwise](https://readwise.io/open/7)\n"
+    );
+  }
+
+  #[test]
+  fn highlight_to_markdown_with_options() {
+    let options = MarkdownOptions {
+      note_prefix: "Note: ".into(),
+      tags: false,
+      links: false,
+      ..Default::default()
+    };
+
+    assert_eq!(
+      highlight().to_markdown_with(&options),
+      "> First line\n>\n> Second line\n\nNote: A thought\n"
+    );
+  }
+
+  #[test]
+  fn book_to_markdown() {
+    let book = Book {
+      title: "Title".into(),
+      author: Some("Author".into()),
+      source_url: Some("https://example.com".into()),
+      ..Default::default()
+    };
+
+    let highlights = [
+      Highlight {
+        id: 1,
+        text: "one".into(),
+        ..Default::default()
+      },
+      Highlight {
+        id: 2,
+        text: "two".into(),
+        ..Default::default()
+      },
+    ];
+
+    let options = MarkdownOptions {
+      heading_level: 2,
+      links: false,
+      ..Default::default()
+    };
+
+    assert_eq!(
+      book.to_markdown_with(&highlights, &options),
+      "## Title\n\
+       \n\
+       - Author: Author\n\
+       - Category: books\n\
+       - Source: https://example.com\n\
+       \n\
+       > one\n\
+       \n\
+       > two\n"
+    );
+
+    assert_eq!(
+      book.to_markdown_with(
+        &[],
+        &MarkdownOptions {
+          metadata: false,
+          ..Default::default()
+        }
+      ),
+      "# Title\n"
+    );
+  }
+}
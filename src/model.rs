@@ -1,60 +1,1699 @@
-use crate::common::*;
+//! Models of the API's requests and responses
+//!
+//! Response models serialize `None` as `null`, as the API does, while models
+//! sent to the API, such as `HighlightCreate` and `BookUpdate`, omit unset
+//! fields so that Readwise leaves them unchanged. Optional strings that the
+//! API sometimes sends as `""` deserialize to `None`.
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+use {
+  crate::common::*,
+  std::{
+    borrow::Borrow,
+    collections::BTreeMap,
+    hash::{Hash, Hasher},
+    ops::Deref,
+  },
+};
+
+/// Highlights longer than this many characters are truncated when displayed
+pub const DISPLAY_TEXT_LENGTH: usize = 80;
+
+/// The most highlights sent in a single create request, matching the page
+/// size limit of the API; larger creates are split into batches of this size
+pub const MAX_CREATE_BATCH_SIZE: usize = 1000;
+
+/// The kind of source a book's highlights came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Category {
+  Books,
+  Articles,
+  Tweets,
+  Supplementals,
+  Podcasts,
+}
+
+impl Category {
+  /// Every category, in the order Readwise lists them
+  pub const ALL: [Self; 5] = [
+    Self::Books,
+    Self::Articles,
+    Self::Tweets,
+    Self::Supplementals,
+    Self::Podcasts,
+  ];
+
+  /// The categories a highlight can be filed under when it is created, every
+  /// category but `Supplementals`, which Readwise reserves for its own
+  /// content
+  pub const CREATABLE: [Self; 4] =
+    [Self::Books, Self::Articles, Self::Tweets, Self::Podcasts];
+
+  /// The name Readwise uses for this category in requests and responses
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Self::Books => "books",
+      Self::Articles => "articles",
+      Self::Tweets => "tweets",
+      Self::Supplementals => "supplementals",
+      Self::Podcasts => "podcasts",
+    }
+  }
+
+  pub(crate) fn from_name(name: &str) -> Option<Self> {
+    match name {
+      "books" => Some(Self::Books),
+      "articles" => Some(Self::Articles),
+      "tweets" => Some(Self::Tweets),
+      "supplementals" => Some(Self::Supplementals),
+      "podcasts" => Some(Self::Podcasts),
+      _ => None,
+    }
+  }
+}
+
+/// A highlight color
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Color {
+  Yellow,
+  Blue,
+  Pink,
+  Orange,
+  Green,
+  Purple,
+}
+
+impl Color {
+  /// Every color, in the order Readwise lists them
+  pub const ALL: [Self; 6] = [
+    Self::Yellow,
+    Self::Blue,
+    Self::Pink,
+    Self::Orange,
+    Self::Green,
+    Self::Purple,
+  ];
+
+  /// The name Readwise uses for this color in requests and responses
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Self::Yellow => "yellow",
+      Self::Blue => "blue",
+      Self::Pink => "pink",
+      Self::Orange => "orange",
+      Self::Green => "green",
+      Self::Purple => "purple",
+    }
+  }
+
+  pub(crate) fn from_name(name: &str) -> Option<Self> {
+    match name {
+      "yellow" => Some(Self::Yellow),
+      "blue" => Some(Self::Blue),
+      "pink" => Some(Self::Pink),
+      "orange" => Some(Self::Orange),
+      "green" => Some(Self::Green),
+      "purple" => Some(Self::Purple),
+      _ => None,
+    }
+  }
+}
+
+/// Parses a category name, ignoring case, such as from a command line
+/// argument
+///
+/// ```
+/// use readwise::model::Category;
+///
+/// assert_eq!("Articles".parse::<Category>().unwrap(), Category::Articles);
+///
+/// assert_eq!(
+///   "movies".parse::<Category>().unwrap_err().to_string(),
+///   "Unknown category `movies`, expected one of: books, articles, tweets, \
+///    supplementals, podcasts"
+/// );
+/// ```
+impl FromStr for Category {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self> {
+    Self::from_name(&s.to_lowercase()).ok_or_else(|| {
+      Error::unknown_value(
+        "category",
+        s,
+        Self::ALL.map(|category| category.as_str()),
+      )
+    })
+  }
+}
+
+/// Parses a color name, ignoring case, such as from a command line argument
+///
+/// ```
+/// use readwise::model::Color;
+///
+/// assert_eq!("yellow".parse::<Color>().unwrap(), Color::Yellow);
+/// assert!("red".parse::<Color>().is_err());
+/// ```
+impl FromStr for Color {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self> {
+    Self::from_name(&s.to_lowercase()).ok_or_else(|| {
+      Error::unknown_value("color", s, Self::ALL.map(|color| color.as_str()))
+    })
+  }
+}
+
+impl Display for Category {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    f.write_str(self.as_str())
+  }
+}
+
+impl Display for Color {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    f.write_str(self.as_str())
+  }
+}
+
+/// How a highlight's `location` is measured
+#[derive(
+  Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum LocationType {
+  /// A page number
+  Page,
+  /// The highlight's position among the book's highlights
+  #[default]
+  Order,
+  /// Seconds from the start of a podcast or video
+  TimeOffset,
+  /// A Kindle location
+  Location,
+}
+
+impl LocationType {
+  /// The name Readwise uses for this location type in requests and responses
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Self::Page => "page",
+      Self::Order => "order",
+      Self::TimeOffset => "time_offset",
+      Self::Location => "location",
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Book {
+  #[serde(deserialize_with = "deserialize_lenient_number")]
   pub id: u64,
   pub title: String,
+  #[serde(default, deserialize_with = "deserialize_empty_as_none")]
   pub author: Option<String>,
   pub category: String,
+  /// The number of highlights when the book was fetched, which later writes
+  /// do not update; see `Client::refresh_book`
+  #[serde(default, deserialize_with = "deserialize_null_as_default")]
   pub num_highlights: u64,
+  #[serde(rename = "last_highlight_at", alias = "last_highlighted_at")]
   pub last_highlighted_at: Option<String>,
+  #[serde(default, deserialize_with = "deserialize_null_as_default")]
   pub updated: String,
+  #[serde(default, deserialize_with = "deserialize_null_as_default")]
   pub cover_image_url: String,
+  #[serde(default, deserialize_with = "deserialize_null_as_default")]
   pub highlights_url: String,
+  #[serde(default, deserialize_with = "deserialize_empty_as_none")]
   pub source_url: Option<String>,
 }
 
+impl Default for Book {
+  fn default() -> Self {
+    Self {
+      id: 0,
+      title: String::new(),
+      author: None,
+      category: Category::Books.as_str().to_string(),
+      num_highlights: 0,
+      last_highlighted_at: None,
+      updated: String::new(),
+      cover_image_url: String::new(),
+      highlights_url: String::new(),
+      source_url: None,
+    }
+  }
+}
+
+impl Book {
+  /// The total number of words in `highlights`, presumably this book's, as
+  /// counted by `text::word_count`
+  pub fn word_count(highlights: &[Highlight]) -> usize {
+    highlights.iter().map(Highlight::word_count).sum()
+  }
+
+  /// How long reading all of `highlights` takes, see `text::reading_time`
+  pub fn reading_time(highlights: &[Highlight]) -> Duration {
+    crate::text::reading_time(Self::word_count(highlights))
+  }
+
+  /// The page on readwise.io listing this book's highlights
+  pub fn readwise_url(&self) -> &str {
+    &self.highlights_url
+  }
+
+  /// Open this book's highlights page in the default web browser
+  #[cfg(feature = "browser")]
+  pub fn open(&self) -> Result {
+    Ok(webbrowser::open(self.readwise_url())?)
+  }
+}
+
+impl Display for Book {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    match &self.author {
+      Some(author) => write!(f, "{} by {}", self.title, author),
+      None => write!(f, "{}", self.title),
+    }
+  }
+}
+
+/// Changes to a book's metadata, as accepted by `Client::update_book`
+///
+/// Unset fields are left unchanged.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct BookUpdate {
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub title: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub author: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub cover_image_url: Option<String>,
+}
+
+/// A page of a paginated list endpoint, as returned by `Client::get_paged`
 #[derive(Debug, Serialize, Deserialize)]
-pub struct BooksResponse {
+pub struct Page<T> {
+  /// The total number of items across all pages
   pub count: u64,
+  /// The URL of the next page, if any
   pub next: Option<String>,
+  /// The URL of the previous page, if any
   pub previous: Option<String>,
-  pub results: Vec<Book>,
+  pub results: Vec<T>,
+}
+
+/// Iterate over the items of the page, as in `for book in page`
+impl<T> IntoIterator for Page<T> {
+  type Item = T;
+  type IntoIter = std::vec::IntoIter<T>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    self.results.into_iter()
+  }
 }
 
+impl<'a, T> IntoIterator for &'a Page<T> {
+  type Item = &'a T;
+  type IntoIter = std::slice::Iter<'a, T>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    self.results.iter()
+  }
+}
+
+pub type BooksResponse = Page<Book>;
+
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct Highlight {
+  #[serde(deserialize_with = "deserialize_lenient_number")]
   pub id: u64,
   pub text: String,
+  #[serde(default, deserialize_with = "deserialize_null_as_default")]
   pub note: String,
-  pub location: u64,
-  pub location_type: String,
+  /// The position of this highlight in its source, measured in
+  /// `location_type` units, or `None` where it has none, as with tweets
+  #[serde(default, deserialize_with = "deserialize_lenient_option")]
+  pub location: Option<u64>,
+  #[serde(default, deserialize_with = "deserialize_null_as_default")]
+  pub location_type: LocationType,
   pub highlighted_at: Option<String>,
+  #[serde(default, deserialize_with = "deserialize_empty_as_none")]
   pub url: Option<String>,
+  /// Link to this highlight on readwise.io, unlike `url` which links to its
+  /// source
+  #[serde(alias = "readwise_url")]
+  pub highlight_url: Option<String>,
+  #[serde(default, deserialize_with = "deserialize_null_as_default")]
   pub color: String,
+  #[serde(default, deserialize_with = "deserialize_null_as_default")]
   pub updated: String,
-  pub books_id: Option<String>,
+  #[serde(default, deserialize_with = "deserialize_lenient_option")]
+  pub book_id: Option<u64>,
+  #[serde(default)]
+  pub tags: Vec<Tag>,
+  #[serde(default)]
+  pub is_favorite: bool,
+  #[serde(default)]
+  pub is_discard: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct HighlightsResponse {
-  pub count: u64,
-  pub next: Option<String>,
-  pub previous: Option<String>,
-  pub results: Vec<Highlight>,
+impl Highlight {
+  /// The number of words in this highlight's text, as counted by
+  /// `text::word_count`
+  pub fn word_count(&self) -> usize {
+    crate::text::word_count(&self.text)
+  }
+
+  /// How long reading this highlight's text takes, see `text::reading_time`
+  pub fn reading_time(&self) -> Duration {
+    crate::text::reading_time(self.word_count())
+  }
+
+  /// The page on readwise.io showing this highlight, preferring the link
+  /// provided by the API
+  pub fn readwise_url(&self) -> String {
+    match &self.highlight_url {
+      Some(url) => url.clone(),
+      None => open_url(self.id),
+    }
+  }
+
+  /// Open this highlight on readwise.io in the default web browser
+  #[cfg(feature = "browser")]
+  pub fn open(&self) -> Result {
+    Ok(webbrowser::open(&self.readwise_url())?)
+  }
+
+  /// The position of this highlight in reading order, for sorting the
+  /// highlights of a book
+  pub fn sort_key(&self) -> SortKey {
+    SortKey {
+      missing: self.location.is_none_or(|location| location == 0),
+      group: match self.location_type {
+        LocationType::Page => 0,
+        LocationType::Order => 1,
+        LocationType::TimeOffset => 2,
+        LocationType::Location => 3,
+      },
+      location: self.location.unwrap_or_default(),
+      undated: self.highlighted_at.is_none(),
+      highlighted_at: self.highlighted_at.clone(),
+    }
+  }
 }
 
+/// The page on readwise.io showing the highlight with identifier `id`
+fn open_url(id: u64) -> String {
+  format!("{}/open/{}", READWISE_URL, id)
+}
+
+/// A comparable position of a highlight within its book, as returned by
+/// `Highlight::sort_key`
+///
+/// Keys order by location type (pages, then orders, then time offsets, then
+/// Kindle locations), then location, and finally by when the highlight was
+/// made. Highlights without a location sort after all others, and those
+/// without a highlight date after their dated peers.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SortKey {
+  missing: bool,
+  group: u8,
+  location: u64,
+  undated: bool,
+  highlighted_at: Option<String>,
+}
+
+/// Sort highlights into reading order by `Highlight::sort_key`, keeping
+/// highlights with equal keys in their original order
+pub fn sort_highlights(highlights: &mut [Highlight]) {
+  highlights.sort_by_cached_key(Highlight::sort_key);
+}
+
+/// A highlight compared and hashed by its ID alone, so that a `HashSet` of
+/// keys holds at most one version of each highlight
+///
+/// Two fetches of the same highlight are equal keys even if it was edited in
+/// between. Keys borrow as their ID, so a set can be queried with an ID:
+///
+/// ```
+/// use {
+///   readwise::model::{Highlight, HighlightKey},
+///   std::collections::HashSet,
+/// };
+///
+/// let mut seen = HashSet::new();
+///
+/// seen.insert(HighlightKey::from(Highlight { id: 1, ..Default::default() }));
+///
+/// assert!(seen.contains(&1));
+/// ```
+#[derive(Debug, Default)]
+pub struct HighlightKey(pub Highlight);
+
+impl PartialEq for HighlightKey {
+  fn eq(&self, other: &Self) -> bool {
+    self.0.id == other.0.id
+  }
+}
+
+impl Eq for HighlightKey {}
+
+impl Hash for HighlightKey {
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    self.0.id.hash(state);
+  }
+}
+
+impl Borrow<u64> for HighlightKey {
+  fn borrow(&self) -> &u64 {
+    &self.0.id
+  }
+}
+
+impl Deref for HighlightKey {
+  type Target = Highlight;
+
+  fn deref(&self) -> &Highlight {
+    &self.0
+  }
+}
+
+impl From<Highlight> for HighlightKey {
+  fn from(highlight: Highlight) -> Self {
+    Self(highlight)
+  }
+}
+
+impl Display for Highlight {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    match self.text.char_indices().nth(DISPLAY_TEXT_LENGTH) {
+      Some((end, _)) => write!(f, "{}…", self.text[..end].trim_end()),
+      None => write!(f, "{}", self.text),
+    }
+  }
+}
+
+pub type HighlightsResponse = Page<Highlight>;
+
+/// A new highlight, as accepted by `Client::create_highlights`
+///
+/// Only `text` is required; unset fields are omitted from the request.
+///
+/// Readwise deduplicates created highlights on their `highlight_url`, so
+/// setting it to a stable identifier for the highlight in the source system
+/// makes repeated imports idempotent: re-sending an existing highlight updates
+/// it instead of creating a duplicate.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct HighlightCreate {
+  pub text: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub title: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub author: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub image_url: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub source_url: Option<String>,
+  /// An identifier of the importing app, such as `my_podcast_app`, that
+  /// Readwise groups the created book's highlights under
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub source_type: Option<String>,
+  /// The category to file the created book under, one of
+  /// `Category::CREATABLE`, or `Books` when unset
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub category: Option<Category>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub note: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub location: Option<u64>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub location_type: Option<LocationType>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub highlighted_at: Option<String>,
+  /// A unique URL for this highlight, used by Readwise as its dedup key
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub highlight_url: Option<String>,
+}
+
+impl From<&Highlight> for HighlightCreate {
+  fn from(highlight: &Highlight) -> Self {
+    let location = highlight.location.filter(|location| *location != 0);
+
+    Self {
+      text: highlight.text.clone(),
+      note: Some(highlight.note.clone()).filter(|note| !note.is_empty()),
+      location,
+      location_type: location.and(Some(highlight.location_type)),
+      highlighted_at: highlight.highlighted_at.clone(),
+      source_url: highlight.url.clone(),
+      ..Default::default()
+    }
+  }
+}
+
+/// Serialize highlights for a create request, checking that each `category`
+/// given is one of `Category::CREATABLE` before anything is sent
+#[cfg(any(feature = "blocking", feature = "async"))]
+pub(crate) fn create_payloads<T: Serialize>(
+  highlights: Vec<T>,
+) -> Result<Vec<serde_json::Value>> {
+  highlights
+    .into_iter()
+    .map(|highlight| {
+      let payload = serde_json::to_value(highlight)?;
+
+      if let Some(name) = payload.get("category").and_then(|c| c.as_str()) {
+        Category::from_name(name)
+          .filter(|category| Category::CREATABLE.contains(category))
+          .ok_or_else(|| {
+            Error::unknown_value(
+              "category",
+              name,
+              Category::CREATABLE.map(|category| category.as_str()),
+            )
+          })?;
+      }
+
+      Ok(payload)
+    })
+    .collect()
+}
+
+/// Options for `Client::create_highlights_with`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CreateOptions {
+  /// Skip highlights whose normalized text already exists in the target book
+  pub skip_existing: bool,
+}
+
+/// The outcome of `Client::create_highlights_with`
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CreateReport {
+  /// Identifiers of the created or updated highlights
+  pub created: Vec<u64>,
+  /// Number of highlights left out because they already existed
+  pub skipped: usize,
+  /// Number of highlights sent that Readwise matched to existing ones and
+  /// left unchanged, so that they are missing from `created`
+  pub existing: usize,
+}
+
+impl CreateReport {
+  /// A link to each created highlight on readwise.io, in the order of
+  /// `created`, for logging clickable links
+  ///
+  /// The create endpoint returns only identifiers, from which the links are
+  /// built without fetching the highlights.
+  ///
+  /// ```
+  /// use readwise::model::CreateReport;
+  ///
+  /// let report = CreateReport {
+  ///   created: vec![42],
+  ///   ..Default::default()
+  /// };
+  ///
+  /// assert_eq!(report.highlight_urls(), ["https://readwise.io/open/42"]);
+  /// ```
+  pub fn highlight_urls(&self) -> Vec<String> {
+    self.created.iter().copied().map(open_url).collect()
+  }
+}
+
+/// Changes to an account's highlights since a previous sync, as returned by
+/// `Client::highlights_modified_since`
+#[derive(Debug, Default)]
+pub struct SyncDelta {
+  /// Highlights created or updated since the previous sync
+  pub updated: Vec<Highlight>,
+  /// Identifiers of previously known highlights that no longer exist, in
+  /// ascending order
+  pub deleted: Vec<u64>,
+}
+
+/// Today's daily review, as returned by `Client::daily_review`
 #[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DailyReview {
+  pub review_id: u64,
+  /// The page on readwise.io presenting this review
+  #[serde(default, deserialize_with = "deserialize_null_as_default")]
+  pub review_url: String,
+  /// Whether the review has already been completed today
+  #[serde(default)]
+  pub review_completed: bool,
+  /// The highlights to review, empty when nothing is due
+  #[serde(default, deserialize_with = "deserialize_null_as_default")]
+  pub highlights: Vec<Highlight>,
+}
+
+impl DailyReview {
+  /// Whether there is nothing to review today
+  pub fn is_empty(&self) -> bool {
+    self.highlights.is_empty()
+  }
+}
+
+/// The outcome of a delete request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeleteOutcome {
+  /// The resource was deleted
+  Deleted,
+  /// The resource did not exist, for example because it was already deleted
+  NotFound,
+}
+
+impl DeleteOutcome {
+  /// Treat a `404 Not Found` response as `NotFound`, leaving other errors
+  /// untouched
+  #[cfg(any(feature = "blocking", feature = "async"))]
+  pub(crate) fn from_result<T>(result: Result<T>) -> Result<Self> {
+    match result {
+      Ok(_) => Ok(DeleteOutcome::Deleted),
+      Err(Error::BadRequest {
+        status: StatusCode::NOT_FOUND,
+        ..
+      }) => Ok(DeleteOutcome::NotFound),
+      Err(error) => Err(error),
+    }
+  }
+}
+
+/// Changes to a highlight, as accepted by `Client::update_highlight`
+///
+/// Unset fields are left unchanged.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct HighlightUpdate {
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub text: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub note: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub location: Option<u64>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub url: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub color: Option<Color>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct HighlightCreateResponse {
   pub id: u64,
   pub title: String,
-  pub auhtor: Option<String>,
+  #[serde(default, deserialize_with = "deserialize_empty_as_none")]
+  pub author: Option<String>,
   pub category: String,
   pub num_highlights: u64,
+  #[serde(rename = "last_highlight_at", alias = "last_highlighted_at")]
   pub last_highlighted_at: Option<String>,
   pub updated: String,
   pub cover_image_url: String,
   pub highlights_url: String,
+  #[serde(default, deserialize_with = "deserialize_empty_as_none")]
   pub source_url: Option<String>,
   pub modified_highlights: Vec<u64>,
 }
+
+impl Default for HighlightCreateResponse {
+  fn default() -> Self {
+    Self {
+      id: 0,
+      title: String::new(),
+      author: None,
+      category: Category::Books.as_str().to_string(),
+      num_highlights: 0,
+      last_highlighted_at: None,
+      updated: String::new(),
+      cover_image_url: String::new(),
+      highlights_url: String::new(),
+      source_url: None,
+      modified_highlights: Vec::new(),
+    }
+  }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ExportStats {
+  /// Number of pages fetched from the API
+  pub pages: u64,
+  /// Number of JSON lines written
+  pub lines: u64,
+  /// Number of bytes written, including newlines
+  pub bytes: u64,
+}
+
+/// The outcome of `Client::export_to_file`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ExportSummary {
+  /// Number of pages fetched by this run
+  pub pages: u64,
+  /// Number of books written by this run
+  pub items: u64,
+  /// Whether this run continued an earlier, interrupted one
+  pub resumed: bool,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Tag {
+  #[serde(deserialize_with = "deserialize_lenient_number")]
+  pub id: u64,
+  pub name: String,
+}
+
+/// The outcome of `Client::tag_highlights`
+#[derive(Debug, Default)]
+pub struct TagReport {
+  /// The tag added to each highlight that was tagged, by highlight identifier
+  pub tagged: Vec<(u64, Tag)>,
+  /// The error for each highlight that could not be tagged
+  pub failed: Vec<(u64, Error)>,
+}
+
+/// The outcome of `Client::update_highlight_full`
+#[derive(Debug, Default)]
+pub struct HighlightUpdateReport {
+  /// The highlight with its new note and the tags it now has
+  pub highlight: Highlight,
+  /// The tags added to the highlight
+  pub added: Vec<Tag>,
+  /// The tags removed from the highlight
+  pub removed: Vec<Tag>,
+  /// The error for each tag, by name, that could not be added or removed
+  pub failed: Vec<(String, Error)>,
+}
+
+/// The items fetched by a paginated request that failed part of the way
+/// through, such as `Client::all_highlights_from`
+///
+/// Fetching again from `next_page` picks up where the request stopped.
+#[derive(Debug)]
+pub struct PartialFetch<T> {
+  /// Every item on the pages fetched before the failure, in order
+  pub items: Vec<T>,
+  /// The page whose request failed
+  pub next_page: u64,
+  /// Why the page could not be fetched
+  pub error: Error,
+}
+
+impl<T> From<PartialFetch<T>> for Error {
+  fn from(partial: PartialFetch<T>) -> Self {
+    partial.error
+  }
+}
+
+/// A highlight as returned by the export endpoint
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ExportHighlight {
+  #[serde(deserialize_with = "deserialize_lenient_number")]
+  pub id: u64,
+  pub text: String,
+  #[serde(default, deserialize_with = "deserialize_null_as_default")]
+  pub note: String,
+  #[serde(default, deserialize_with = "deserialize_lenient_option")]
+  pub location: Option<u64>,
+  pub location_type: Option<LocationType>,
+  pub highlighted_at: Option<String>,
+  pub created_at: Option<String>,
+  pub updated_at: Option<String>,
+  pub external_id: Option<String>,
+  #[serde(default, deserialize_with = "deserialize_lenient_option")]
+  pub end_location: Option<u64>,
+  #[serde(default, deserialize_with = "deserialize_empty_as_none")]
+  pub url: Option<String>,
+  #[serde(deserialize_with = "deserialize_lenient_number")]
+  pub book_id: u64,
+  #[serde(default)]
+  pub tags: Vec<Tag>,
+  #[serde(default)]
+  pub is_favorite: bool,
+  #[serde(default)]
+  pub is_discard: bool,
+  pub readwise_url: Option<String>,
+  pub color: Option<String>,
+}
+
+impl From<ExportHighlight> for Highlight {
+  fn from(highlight: ExportHighlight) -> Self {
+    Self {
+      id: highlight.id,
+      text: highlight.text,
+      note: highlight.note,
+      location: highlight.location,
+      location_type: highlight.location_type.unwrap_or_default(),
+      highlighted_at: highlight.highlighted_at,
+      url: highlight.url,
+      highlight_url: highlight.readwise_url,
+      color: highlight.color.unwrap_or_default(),
+      updated: highlight.updated_at.unwrap_or_default(),
+      book_id: Some(highlight.book_id),
+      tags: highlight.tags,
+      is_favorite: highlight.is_favorite,
+      is_discard: highlight.is_discard,
+    }
+  }
+}
+
+/// A book and its highlights as returned by the export endpoint
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportBook {
+  #[serde(deserialize_with = "deserialize_lenient_number")]
+  pub user_book_id: u64,
+  pub title: String,
+  #[serde(default, deserialize_with = "deserialize_empty_as_none")]
+  pub author: Option<String>,
+  pub readable_title: Option<String>,
+  pub source: Option<String>,
+  pub cover_image_url: Option<String>,
+  pub unique_url: Option<String>,
+  #[serde(default)]
+  pub book_tags: Vec<Tag>,
+  pub category: String,
+  #[serde(default, deserialize_with = "deserialize_empty_as_none")]
+  pub document_note: Option<String>,
+  pub readwise_url: Option<String>,
+  #[serde(default, deserialize_with = "deserialize_empty_as_none")]
+  pub source_url: Option<String>,
+  pub asin: Option<String>,
+  #[serde(default)]
+  pub highlights: Vec<ExportHighlight>,
+}
+
+impl Default for ExportBook {
+  fn default() -> Self {
+    Self {
+      user_book_id: 0,
+      title: String::new(),
+      author: None,
+      readable_title: None,
+      source: None,
+      cover_image_url: None,
+      unique_url: None,
+      book_tags: Vec::new(),
+      category: Category::Books.as_str().to_string(),
+      document_note: None,
+      readwise_url: None,
+      source_url: None,
+      asin: None,
+      highlights: Vec::new(),
+    }
+  }
+}
+
+impl ExportBook {
+  /// Split into the book, as the books endpoint would describe it, and its
+  /// highlights
+  ///
+  /// The export has no book update time, so `updated` is empty, and
+  /// `num_highlights` and `last_highlighted_at` are computed from the
+  /// exported highlights.
+  pub fn split(self) -> (Book, Vec<Highlight>) {
+    let highlights = self
+      .highlights
+      .into_iter()
+      .map(Highlight::from)
+      .collect::<Vec<Highlight>>();
+
+    let book = Book {
+      id: self.user_book_id,
+      title: self.title,
+      author: self.author,
+      category: self.category,
+      num_highlights: highlights.len() as u64,
+      last_highlighted_at: highlights
+        .iter()
+        .filter_map(|highlight| highlight.highlighted_at.clone())
+        .max(),
+      updated: String::new(),
+      cover_image_url: self.cover_image_url.unwrap_or_default(),
+      highlights_url: self.readwise_url.unwrap_or_default(),
+      source_url: self.source_url,
+    };
+
+    (book, highlights)
+  }
+}
+
+/// Every distinct tag on the exported books and their highlights, sorted by
+/// name
+///
+/// Tags are deduplicated by name, keeping the first one seen.
+pub fn unique_tags(books: &[ExportBook]) -> Vec<Tag> {
+  let mut tags = BTreeMap::new();
+
+  for book in books {
+    for tag in book
+      .book_tags
+      .iter()
+      .chain(book.highlights.iter().flat_map(|highlight| &highlight.tags))
+    {
+      tags.entry(tag.name.as_str()).or_insert(tag);
+    }
+  }
+
+  tags.into_values().cloned().collect()
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportResponse {
+  pub count: u64,
+  #[serde(rename = "nextPageCursor")]
+  pub next_page_cursor: Option<String>,
+  pub results: Vec<ExportBook>,
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn display_book() {
+    let mut book = Book {
+      title: "Meditations".into(),
+      author: Some("Marcus Aurelius".into()),
+      ..Default::default()
+    };
+
+    assert_eq!(book.to_string(), "Meditations by Marcus Aurelius");
+
+    book.author = None;
+
+    assert_eq!(book.to_string(), "Meditations");
+  }
+
+  #[test]
+  fn highlight_key_identity() {
+    let mut seen = HashSet::new();
+
+    assert!(seen.insert(HighlightKey::from(Highlight {
+      id: 1,
+      updated: "2022-01-01".into(),
+      ..Default::default()
+    })));
+
+    assert!(!seen.insert(HighlightKey::from(Highlight {
+      id: 1,
+      text: "edited".into(),
+      updated: "2022-02-01".into(),
+      ..Default::default()
+    })));
+
+    assert!(seen.insert(HighlightKey::from(Highlight {
+      id: 2,
+      ..Default::default()
+    })));
+
+    assert_eq!(seen.len(), 2);
+    assert!(seen.contains(&1));
+    assert!(!seen.contains(&3));
+    assert_eq!(seen.get(&1).unwrap().updated, "2022-01-01");
+  }
+
+  #[test]
+  fn empty_strings_as_none() {
+    for author in [r#""""#, "null"] {
+      let book = serde_json::from_str::<Book>(&format!(
+        r#"{{
+          "id": 1,
+          "title": "Meditations",
+          "author": {},
+          "category": "books",
+          "num_highlights": 0,
+          "last_highlight_at": null,
+          "updated": "",
+          "cover_image_url": "",
+          "highlights_url": "",
+          "source_url": ""
+        }}"#,
+        author
+      ))
+      .unwrap();
+
+      assert_eq!(book.author, None);
+      assert_eq!(book.source_url, None);
+      assert_eq!(book.to_string(), "Meditations");
+
+      let json = serde_json::to_value(&book).unwrap();
+
+      assert_eq!(json["author"], serde_json::Value::Null);
+
+      let round_trip = serde_json::from_value::<Book>(json).unwrap();
+
+      assert_eq!(round_trip.author, None);
+    }
+
+    let highlight = serde_json::from_str::<ExportHighlight>(
+      r#"{ "id": 1, "text": "", "book_id": 1, "url": "" }"#,
+    )
+    .unwrap();
+
+    assert_eq!(highlight.url, None);
+
+    let highlight = serde_json::from_str::<ExportHighlight>(
+      r#"{ "id": 1, "text": "", "book_id": 1, "url": "https://example.com" }"#,
+    )
+    .unwrap();
+
+    assert_eq!(highlight.url.as_deref(), Some("https://example.com"));
+  }
+
+  const BOOKS_FIXTURE: &str = r#"{
+    "count": 1,
+    "next": null,
+    "previous": null,
+    "results": [{
+      "id": 6307386,
+      "title": "Think Like a Rocket Scientist",
+      "author": "Ozan Varol",
+      "category": "books",
+      "source": "kindle",
+      "num_highlights": 10,
+      "last_highlight_at": "2020-10-01T17:47:31.234Z",
+      "updated": "2020-10-01T17:47:31.234Z",
+      "cover_image_url": "https://images-na.ssl-images-amazon.com/images/I/51XPjj5mvUL._SL75_.jpg",
+      "highlights_url": "https://readwise.io/bookreview/6307386",
+      "source_url": null,
+      "asin": "B07P8NCSK9",
+      "tags": [],
+      "document_note": ""
+    }]
+  }"#;
+
+  const HIGHLIGHTS_FIXTURE: &str = r#"{
+    "count": 1,
+    "next": "https://readwise.io/api/v2/highlights?page=2",
+    "previous": null,
+    "results": [{
+      "id": 59758950,
+      "text": "The fox jumped over the fence.",
+      "note": "",
+      "location": 1,
+      "location_type": "order",
+      "highlighted_at": "2020-07-01T18:53:00.523Z",
+      "url": null,
+      "color": "yellow",
+      "updated": "2020-07-01T18:53:00.523Z",
+      "book_id": 6307386,
+      "tags": []
+    }]
+  }"#;
+
+  const CREATE_RESPONSE_FIXTURE: &str = r#"[{
+    "id": 13863970,
+    "title": "Quotes",
+    "author": "Readwise",
+    "category": "books",
+    "source": "api_book",
+    "num_highlights": 1,
+    "last_highlight_at": null,
+    "updated": "2020-10-01T12:58:44.716235Z",
+    "cover_image_url": "https://readwise-assets.s3.amazonaws.com/static/images/default-book-icon-4.11327a2af05a.png",
+    "highlights_url": "https://readwise.io/bookreview/13863970",
+    "source_url": null,
+    "modified_highlights": [259466361]
+  }]"#;
+
+  const EXPORT_FIXTURE: &str = r#"{
+    "count": 1,
+    "nextPageCursor": null,
+    "results": [{
+      "user_book_id": 123,
+      "title": "Some title",
+      "author": "Some author",
+      "readable_title": "Some title",
+      "source": "raindrop",
+      "cover_image_url": "https://cover.com/image.png",
+      "unique_url": "",
+      "book_tags": [{ "id": 1, "name": "reading" }],
+      "category": "articles",
+      "document_note": "",
+      "readwise_url": "https://readwise.io/bookreview/123",
+      "source_url": "",
+      "asin": null,
+      "highlights": [{
+        "id": 456,
+        "text": "XPTO.",
+        "location": 1,
+        "location_type": "order",
+        "note": null,
+        "color": "yellow",
+        "highlighted_at": "2022-09-13T16:41:53.186Z",
+        "created_at": "2022-09-13T16:41:53.186Z",
+        "updated_at": "2022-09-14T18:50:30.564Z",
+        "external_id": "6320b2bd7fbcdd7b0c000b3e",
+        "end_location": null,
+        "url": null,
+        "book_id": 123,
+        "tags": [],
+        "is_favorite": false,
+        "is_discard": false,
+        "readwise_url": "https://readwise.io/open/456"
+      }]
+    }]
+  }"#;
+
+  /// Parse `json` as `T`, checking that serializing and parsing it again
+  /// loses nothing
+  fn round_trip<T: Serialize + serde::de::DeserializeOwned>(json: &str) -> T {
+    let parsed = serde_json::from_str::<T>(json).unwrap();
+
+    let value = serde_json::to_value(&parsed).unwrap();
+
+    assert_eq!(
+      serde_json::to_value(serde_json::from_value::<T>(value.clone()).unwrap())
+        .unwrap(),
+      value
+    );
+
+    parsed
+  }
+
+  #[test]
+  fn books_round_trip() {
+    let response = round_trip::<BooksResponse>(BOOKS_FIXTURE);
+
+    assert_eq!(response.count, 1);
+
+    let book = &response.results[0];
+
+    assert_eq!(book.id, 6307386);
+    assert_eq!(book.title, "Think Like a Rocket Scientist");
+    assert_eq!(book.author.as_deref(), Some("Ozan Varol"));
+    assert_eq!(book.category, "books");
+    assert_eq!(book.num_highlights, 10);
+    assert_eq!(
+      book.last_highlighted_at.as_deref(),
+      Some("2020-10-01T17:47:31.234Z")
+    );
+    assert_eq!(book.updated, "2020-10-01T17:47:31.234Z");
+    assert_eq!(
+      book.highlights_url,
+      "https://readwise.io/bookreview/6307386"
+    );
+    assert_eq!(book.source_url, None);
+  }
+
+  #[test]
+  fn highlights_round_trip() {
+    let response = round_trip::<HighlightsResponse>(HIGHLIGHTS_FIXTURE);
+
+    assert_eq!(
+      response.next.as_deref(),
+      Some("https://readwise.io/api/v2/highlights?page=2")
+    );
+
+    let highlight = &response.results[0];
+
+    assert_eq!(highlight.id, 59758950);
+    assert_eq!(highlight.text, "The fox jumped over the fence.");
+    assert_eq!(highlight.note, "");
+    assert_eq!(highlight.location, Some(1));
+    assert_eq!(highlight.location_type, LocationType::Order);
+    assert_eq!(
+      highlight.highlighted_at.as_deref(),
+      Some("2020-07-01T18:53:00.523Z")
+    );
+    assert_eq!(highlight.url, None);
+    assert_eq!(highlight.color, "yellow");
+    assert_eq!(highlight.book_id, Some(6307386));
+  }
+
+  #[test]
+  fn create_response_round_trip() {
+    let books =
+      round_trip::<Vec<HighlightCreateResponse>>(CREATE_RESPONSE_FIXTURE);
+
+    assert_eq!(books[0].id, 13863970);
+    assert_eq!(books[0].title, "Quotes");
+    assert_eq!(books[0].author.as_deref(), Some("Readwise"));
+    assert_eq!(books[0].num_highlights, 1);
+    assert_eq!(books[0].modified_highlights, [259466361]);
+  }
+
+  #[test]
+  fn export_round_trip() {
+    let response = round_trip::<ExportResponse>(EXPORT_FIXTURE);
+
+    assert_eq!(response.next_page_cursor, None);
+
+    let book = &response.results[0];
+
+    assert_eq!(book.user_book_id, 123);
+    assert_eq!(book.author.as_deref(), Some("Some author"));
+    assert_eq!(book.category, "articles");
+    assert_eq!(book.book_tags[0].name, "reading");
+    assert_eq!(book.document_note, None);
+    assert_eq!(book.source_url, None);
+    assert_eq!(book.asin, None);
+
+    let highlight = &book.highlights[0];
+
+    assert_eq!(highlight.id, 456);
+    assert_eq!(highlight.note, "");
+    assert_eq!(highlight.location, Some(1));
+    assert_eq!(highlight.location_type, Some(LocationType::Order));
+    assert_eq!(
+      highlight.external_id.as_deref(),
+      Some("6320b2bd7fbcdd7b0c000b3e")
+    );
+    assert_eq!(highlight.book_id, 123);
+    assert!(!highlight.is_favorite);
+    assert_eq!(
+      highlight.readwise_url.as_deref(),
+      Some("https://readwise.io/open/456")
+    );
+  }
+
+  #[test]
+  fn request_models_round_trip() {
+    let create = round_trip::<HighlightCreate>(
+      r#"{
+        "text": "Gravity is the curvature of spacetime",
+        "title": "Einstein's Theory",
+        "author": "Albert Einstein",
+        "source_type": "book",
+        "category": "books",
+        "location": 3,
+        "location_type": "page",
+        "highlighted_at": "2020-07-14T20:11:24+00:00"
+      }"#,
+    );
+
+    assert_eq!(create.location_type, Some(LocationType::Page));
+    assert_eq!(create.note, None);
+
+    let update = round_trip::<BookUpdate>(r#"{ "author": "Jane Doe" }"#);
+
+    assert_eq!(update.author.as_deref(), Some("Jane Doe"));
+    assert_eq!(
+      serde_json::to_string(&update).unwrap(),
+      r#"{"author":"Jane Doe"}"#
+    );
+
+    let tag = round_trip::<Tag>(r#"{ "id": 1, "name": "favorite" }"#);
+
+    assert_eq!(tag.name, "favorite");
+  }
+
+  #[test]
+  fn unique_tags_sorted_by_name() {
+    let tag = |id, name: &str| Tag {
+      id,
+      name: name.into(),
+    };
+
+    let books = [
+      ExportBook {
+        book_tags: vec![tag(1, "reading"), tag(2, "favorite")],
+        highlights: vec![ExportHighlight {
+          tags: vec![tag(3, "quote"), tag(4, "favorite")],
+          ..Default::default()
+        }],
+        ..Default::default()
+      },
+      ExportBook {
+        highlights: vec![ExportHighlight {
+          tags: vec![tag(5, "archive")],
+          ..Default::default()
+        }],
+        ..Default::default()
+      },
+    ];
+
+    assert_eq!(
+      unique_tags(&books)
+        .iter()
+        .map(|tag| (tag.id, tag.name.as_str()))
+        .collect::<Vec<(u64, &str)>>(),
+      vec![
+        (5, "archive"),
+        (2, "favorite"),
+        (3, "quote"),
+        (1, "reading")
+      ]
+    );
+  }
+
+  #[test]
+  fn iterate_page() {
+    let page = BooksResponse {
+      count: 2,
+      next: None,
+      previous: None,
+      results: vec![
+        Book {
+          id: 1,
+          ..Default::default()
+        },
+        Book {
+          id: 2,
+          ..Default::default()
+        },
+      ],
+    };
+
+    assert_eq!((&page).into_iter().map(|book| book.id).sum::<u64>(), 3);
+
+    assert_eq!(
+      page.into_iter().map(|book| book.id).collect::<Vec<u64>>(),
+      [1, 2]
+    );
+  }
+
+  #[test]
+  fn split_export_book() {
+    let (book, highlights) = ExportBook {
+      user_book_id: 7,
+      title: "Meditations".into(),
+      readwise_url: Some("https://readwise.io/bookreview/7".into()),
+      highlights: vec![
+        ExportHighlight {
+          id: 1,
+          book_id: 7,
+          highlighted_at: Some("2022-09-12T21:03:11Z".into()),
+          ..Default::default()
+        },
+        ExportHighlight {
+          id: 2,
+          book_id: 7,
+          highlighted_at: Some("2022-09-13T16:41:53Z".into()),
+          ..Default::default()
+        },
+      ],
+      ..Default::default()
+    }
+    .split();
+
+    assert_eq!(book.id, 7);
+    assert_eq!(book.num_highlights, 2);
+    assert_eq!(
+      book.last_highlighted_at.as_deref(),
+      Some("2022-09-13T16:41:53Z")
+    );
+    assert_eq!(book.readwise_url(), "https://readwise.io/bookreview/7");
+
+    assert_eq!(
+      highlights
+        .iter()
+        .map(|highlight| (highlight.id, highlight.book_id))
+        .collect::<Vec<_>>(),
+      [(1, Some(7)), (2, Some(7))]
+    );
+  }
+
+  #[test]
+  fn word_counts() {
+    let highlights = [
+      Highlight {
+        text: "The fox jumped \u{2014} over the fence.".into(),
+        ..Default::default()
+      },
+      Highlight {
+        text: "x ".repeat(232),
+        ..Default::default()
+      },
+    ];
+
+    assert_eq!(highlights[0].word_count(), 6);
+    assert_eq!(Book::word_count(&highlights), 238);
+    assert_eq!(Book::reading_time(&highlights), Duration::from_secs(60));
+  }
+
+  #[test]
+  fn sparse_payloads() {
+    let highlight = serde_json::from_str::<Highlight>(
+      r#"{ "id": 1, "text": "old", "note": null, "location": null }"#,
+    )
+    .unwrap();
+
+    assert_eq!(highlight.note, "");
+    assert_eq!(highlight.location, None);
+    assert_eq!(highlight.location_type, LocationType::default());
+    assert_eq!(highlight.color, "");
+    assert_eq!(highlight.updated, "");
+
+    let book = serde_json::from_str::<Book>(
+      r#"{ "id": 1, "title": "Old", "category": "books" }"#,
+    )
+    .unwrap();
+
+    assert_eq!(book.num_highlights, 0);
+    assert_eq!(book.updated, "");
+    assert_eq!(book.cover_image_url, "");
+    assert_eq!(book.highlights_url, "");
+  }
+
+  #[test]
+  fn lenient_numbers() {
+    for json in [
+      r#"{ "id": 7, "text": "", "location": 42, "book_id": 3 }"#,
+      r#"{ "id": "7", "text": "", "location": "42", "book_id": "3" }"#,
+    ] {
+      let highlight = serde_json::from_str::<Highlight>(json).unwrap();
+
+      assert_eq!(highlight.id, 7);
+      assert_eq!(highlight.location, Some(42));
+      assert_eq!(highlight.book_id, Some(3));
+    }
+
+    let highlight = serde_json::from_str::<Highlight>(
+      r#"{ "id": 7, "text": "", "location": "", "book_id": null }"#,
+    )
+    .unwrap();
+
+    assert_eq!(highlight.location, None);
+    assert_eq!(highlight.book_id, None);
+
+    for json in [
+      r#"{ "id": 7, "text": "", "location": 42, "book_id": 3 }"#,
+      r#"{ "id": "7", "text": "", "location": "42", "book_id": "3" }"#,
+    ] {
+      let highlight = serde_json::from_str::<ExportHighlight>(json).unwrap();
+
+      assert_eq!(highlight.id, 7);
+      assert_eq!(highlight.location, Some(42));
+      assert_eq!(highlight.book_id, 3);
+    }
+
+    assert!(serde_json::from_str::<Highlight>(
+      r#"{ "id": 7, "text": "", "location": "forty-two" }"#
+    )
+    .is_err());
+  }
+
+  #[cfg(any(feature = "blocking", feature = "async"))]
+  #[test]
+  fn create_payloads_check_category() {
+    let payloads = create_payloads(vec![HighlightCreate {
+      text: "hello".into(),
+      category: Some(Category::Podcasts),
+      source_type: Some("podcast_app".into()),
+      ..Default::default()
+    }])
+    .unwrap();
+
+    assert_eq!(payloads[0]["category"], "podcasts");
+    assert_eq!(payloads[0]["source_type"], "podcast_app");
+
+    for category in ["supplementals", "movies"] {
+      assert_eq!(
+        create_payloads(vec![HashMap::from([
+          ("text", "hello"),
+          ("category", category),
+        ])])
+        .unwrap_err()
+        .to_string(),
+        format!(
+          "Unknown category `{}`, expected one of: books, articles, tweets, \
+           podcasts",
+          category
+        )
+      );
+    }
+  }
+
+  #[test]
+  fn default_category() {
+    assert_eq!(
+      Category::from_name(&Book::default().category),
+      Some(Category::Books)
+    );
+    assert_eq!(
+      Category::from_name(&ExportBook::default().category),
+      Some(Category::Books)
+    );
+    assert_eq!(
+      Category::from_name(&HighlightCreateResponse::default().category),
+      Some(Category::Books)
+    );
+  }
+
+  #[test]
+  fn location_type_serde() {
+    for location_type in [
+      LocationType::Page,
+      LocationType::Order,
+      LocationType::TimeOffset,
+      LocationType::Location,
+    ] {
+      let json = serde_json::to_string(&location_type).unwrap();
+
+      assert_eq!(json, format!("\"{}\"", location_type.as_str()));
+      assert_eq!(
+        serde_json::from_str::<LocationType>(&json).unwrap(),
+        location_type
+      );
+    }
+  }
+
+  #[test]
+  fn highlight_url() {
+    let highlight = serde_json::from_str::<Highlight>(
+      r#"{
+        "id": 1,
+        "text": "hello world!",
+        "note": "",
+        "location": 0,
+        "location_type": "order",
+        "highlighted_at": null,
+        "url": "https://example.com/article",
+        "highlight_url": "https://readwise.io/open/1",
+        "color": "",
+        "updated": "",
+        "book_id": null
+      }"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+      highlight.url.as_deref(),
+      Some("https://example.com/article")
+    );
+    assert_eq!(
+      highlight.highlight_url.as_deref(),
+      Some("https://readwise.io/open/1")
+    );
+
+    let export = Highlight::from(ExportHighlight {
+      readwise_url: Some("https://readwise.io/open/2".into()),
+      ..Default::default()
+    });
+
+    assert_eq!(export.readwise_url(), "https://readwise.io/open/2");
+  }
+
+  fn shuffle<T>(items: &mut [T], mut seed: u64) {
+    for i in (1..items.len()).rev() {
+      seed ^= seed << 13;
+      seed ^= seed >> 7;
+      seed ^= seed << 17;
+      items.swap(i, (seed % (i as u64 + 1)) as usize);
+    }
+  }
+
+  #[test]
+  fn sort_highlights_in_reading_order() {
+    let highlight =
+      |id, location_type, location, highlighted_at: Option<&str>| Highlight {
+        id,
+        location: Some(location),
+        location_type,
+        highlighted_at: highlighted_at.map(String::from),
+        ..Default::default()
+      };
+
+    let expected = [
+      highlight(1, LocationType::Page, 1, None),
+      highlight(2, LocationType::Page, 5, Some("2022-01-01T00:00:00Z")),
+      highlight(3, LocationType::Page, 5, Some("2022-02-01T00:00:00Z")),
+      highlight(4, LocationType::Page, 5, None),
+      highlight(5, LocationType::Page, 12, None),
+      highlight(6, LocationType::Order, 2, None),
+      highlight(7, LocationType::Order, 10, None),
+      highlight(8, LocationType::TimeOffset, 30, None),
+      highlight(9, LocationType::TimeOffset, 3600, None),
+      highlight(10, LocationType::Location, 7, None),
+      highlight(11, LocationType::Page, 0, None),
+      highlight(12, LocationType::TimeOffset, 0, None),
+      Highlight {
+        id: 13,
+        location: None,
+        location_type: LocationType::Location,
+        ..Default::default()
+      },
+    ];
+
+    for seed in 1..=100 {
+      let mut highlights = expected
+        .iter()
+        .map(|highlight| Highlight {
+          id: highlight.id,
+          location: highlight.location,
+          location_type: highlight.location_type,
+          highlighted_at: highlight.highlighted_at.clone(),
+          ..Default::default()
+        })
+        .collect::<Vec<Highlight>>();
+
+      shuffle(&mut highlights, seed);
+      sort_highlights(&mut highlights);
+
+      assert!(highlights
+        .windows(2)
+        .all(|pair| pair[0].sort_key() <= pair[1].sort_key()));
+
+      assert_eq!(
+        highlights.iter().map(|h| h.id).collect::<Vec<u64>>(),
+        (1..=13).collect::<Vec<u64>>(),
+        "seed {}",
+        seed
+      );
+    }
+  }
+
+  #[test]
+  fn sort_highlights_is_stable() {
+    let mut highlights = (1..=5)
+      .map(|id| Highlight {
+        id,
+        location: Some(3),
+        location_type: LocationType::Page,
+        ..Default::default()
+      })
+      .collect::<Vec<Highlight>>();
+
+    shuffle(&mut highlights, 42);
+
+    let before = highlights.iter().map(|h| h.id).collect::<Vec<u64>>();
+
+    sort_highlights(&mut highlights);
+
+    assert_eq!(
+      highlights.iter().map(|h| h.id).collect::<Vec<u64>>(),
+      before
+    );
+  }
+
+  #[test]
+  fn display_highlight() {
+    let short = Highlight {
+      text: "hello world!".into(),
+      ..Default::default()
+    };
+
+    assert_eq!(short.to_string(), "hello world!");
+
+    let long = Highlight {
+      text: "é".repeat(DISPLAY_TEXT_LENGTH + 1),
+      ..Default::default()
+    };
+
+    assert_eq!(
+      long.to_string(),
+      format!("{}…", "é".repeat(DISPLAY_TEXT_LENGTH))
+    );
+  }
+}
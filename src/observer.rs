@@ -0,0 +1,71 @@
+//! Hooks for monitoring the requests a `Client` sends, such as counting them
+//! or recording their latency in Prometheus or statsd
+
+use crate::{common::*, transport::ApiRequest};
+
+/// Observes every request sent by a `Client`, registered with
+/// `ClientBuilder::observer`
+///
+/// Each attempt is observed separately, so a retried request is reported
+/// once per attempt. Every method does nothing by default.
+///
+/// ```no_run
+/// use {
+///   readwise::{
+///     client::ClientBuilder,
+///     observer::RequestObserver,
+///     transport::ApiRequest,
+///   },
+///   reqwest::StatusCode,
+///   std::{
+///     sync::atomic::{AtomicU64, Ordering},
+///     time::Duration,
+///   },
+/// };
+///
+/// #[derive(Default)]
+/// struct Errors(AtomicU64);
+///
+/// impl RequestObserver for Errors {
+///   fn on_response(
+///     &self,
+///     request: &ApiRequest,
+///     status: StatusCode,
+///     duration: Duration,
+///   ) {
+///     if !status.is_success() {
+///       self.0.fetch_add(1, Ordering::Relaxed);
+///       eprintln!("{} {} took {:?}", status, request.path, duration);
+///     }
+///   }
+/// }
+///
+/// let client = ClientBuilder::new("token")
+///   .observer(Box::new(Errors::default()))
+///   .build_and_auth()
+///   .unwrap();
+/// ```
+pub trait RequestObserver: Send + Sync {
+  /// Called before `request` is sent
+  fn on_request(&self, _request: &ApiRequest) {}
+
+  /// Called when a response to `request` arrives, whatever its status, after
+  /// `duration`
+  fn on_response(
+    &self,
+    _request: &ApiRequest,
+    _status: StatusCode,
+    _duration: Duration,
+  ) {
+  }
+
+  /// Called when `request` fails without a response, for example because it
+  /// timed out, after `duration`
+  fn on_error(
+    &self,
+    _request: &ApiRequest,
+    _error: &Error,
+    _duration: Duration,
+  ) {
+  }
+}
@@ -0,0 +1,34 @@
+//! The most commonly used types, for importing with a single glob
+//!
+//! ```no_run
+//! # #[cfg(feature = "blocking")]
+//! # {
+//! use readwise::prelude::*;
+//!
+//! let client = Client::new("token").unwrap();
+//!
+//! let response = client
+//!   .highlights_with(&HighlightFilter::new().book_id(1))
+//!   .unwrap();
+//!
+//! for highlight in response {
+//!   println!("{}", highlight);
+//! }
+//! # }
+//! ```
+
+#[cfg(feature = "async")]
+pub use crate::async_client::AsyncClient;
+
+#[cfg(feature = "blocking")]
+pub use crate::client::{Client, ClientBuilder};
+
+pub use crate::{
+  error::Error,
+  filter::{BookFilter, HighlightFilter},
+  model::{
+    Book, BookUpdate, BooksResponse, Category, Color, DeleteOutcome, Highlight,
+    HighlightCreate, HighlightUpdate, HighlightsResponse, LocationType, Page,
+    Tag,
+  },
+};
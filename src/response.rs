@@ -0,0 +1,181 @@
+use crate::common::*;
+
+#[cfg(feature = "blocking")]
+use std::time::SystemTime;
+
+/// Metadata about an HTTP response returned by the Readwise API
+#[derive(Debug, Clone)]
+pub struct ResponseMeta {
+  /// The response status code
+  pub status: StatusCode,
+  /// The response headers
+  pub headers: header::HeaderMap,
+  /// The URL that was requested
+  pub url: String,
+  /// How long the request took, or zero where timing is unavailable (wasm)
+  pub elapsed: Duration,
+}
+
+/// The rate limit state reported by a response, see
+/// `Client::rate_limit_status`
+#[cfg(feature = "blocking")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateLimitStatus {
+  /// How many requests the window allows, from `X-RateLimit-Limit`
+  pub limit: Option<u64>,
+  /// How many requests remain in the current window
+  pub remaining: u64,
+  /// When the window resets and requests may be sent again
+  pub reset_at: Option<SystemTime>,
+}
+
+#[cfg(feature = "blocking")]
+impl RateLimitStatus {
+  /// The status reported by `meta`, received at `now`, from its
+  /// `X-RateLimit-*` headers or otherwise the `Retry-After` header of a
+  /// `429 Too Many Requests` response
+  ///
+  /// `X-RateLimit-Reset` is read as a Unix timestamp when it is later than
+  /// 2001, and as a number of seconds from `now` otherwise.
+  pub(crate) fn from_response(
+    meta: &ResponseMeta,
+    now: SystemTime,
+  ) -> Option<Self> {
+    let header = |name: &str| -> Option<u64> {
+      meta.headers.get(name)?.to_str().ok()?.trim().parse().ok()
+    };
+
+    match header("x-ratelimit-remaining") {
+      Some(remaining) => Some(Self {
+        limit: header("x-ratelimit-limit"),
+        remaining,
+        reset_at: header("x-ratelimit-reset").map(|reset| match reset {
+          reset if reset >= 1_000_000_000 => {
+            SystemTime::UNIX_EPOCH + Duration::from_secs(reset)
+          }
+          reset => now + Duration::from_secs(reset),
+        }),
+      }),
+      None if meta.status == StatusCode::TOO_MANY_REQUESTS => Some(Self {
+        limit: None,
+        remaining: 0,
+        reset_at: error::retry_after(&meta.headers).map(|wait| now + wait),
+      }),
+      None => None,
+    }
+  }
+}
+
+/// Measures request durations without panicking on `wasm32`, where
+/// `std::time::Instant` is unsupported
+#[cfg(any(feature = "blocking", feature = "async"))]
+pub(crate) struct Stopwatch {
+  #[cfg(not(target_arch = "wasm32"))]
+  start: std::time::Instant,
+}
+
+#[cfg(any(feature = "blocking", feature = "async"))]
+impl Stopwatch {
+  pub(crate) fn start() -> Self {
+    Self {
+      #[cfg(not(target_arch = "wasm32"))]
+      start: std::time::Instant::now(),
+    }
+  }
+
+  pub(crate) fn elapsed(&self) -> Duration {
+    #[cfg(not(target_arch = "wasm32"))]
+    return self.start.elapsed();
+    #[cfg(target_arch = "wasm32")]
+    return Duration::ZERO;
+  }
+}
+
+#[cfg(all(test, feature = "blocking"))]
+mod tests {
+  use super::*;
+
+  fn meta(
+    status: StatusCode,
+    headers: &[(&'static str, &str)],
+  ) -> ResponseMeta {
+    ResponseMeta {
+      status,
+      headers: headers
+        .iter()
+        .map(|(name, value)| {
+          (
+            header::HeaderName::from_static(name),
+            header::HeaderValue::from_str(value).unwrap(),
+          )
+        })
+        .collect(),
+      url: String::new(),
+      elapsed: Duration::ZERO,
+    }
+  }
+
+  #[test]
+  fn rate_limit_headers() {
+    let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+
+    assert_eq!(
+      RateLimitStatus::from_response(
+        &meta(
+          StatusCode::OK,
+          &[
+            ("x-ratelimit-limit", "240"),
+            ("x-ratelimit-remaining", "12"),
+            ("x-ratelimit-reset", "30"),
+          ],
+        ),
+        now,
+      ),
+      Some(RateLimitStatus {
+        limit: Some(240),
+        remaining: 12,
+        reset_at: Some(now + Duration::from_secs(30)),
+      })
+    );
+
+    assert_eq!(
+      RateLimitStatus::from_response(
+        &meta(
+          StatusCode::OK,
+          &[
+            ("x-ratelimit-remaining", "0"),
+            ("x-ratelimit-reset", "1700000060"),
+          ],
+        ),
+        now,
+      ),
+      Some(RateLimitStatus {
+        limit: None,
+        remaining: 0,
+        reset_at: Some(now + Duration::from_secs(60)),
+      })
+    );
+  }
+
+  #[test]
+  fn rate_limited_without_headers() {
+    let now = SystemTime::UNIX_EPOCH;
+
+    assert_eq!(
+      RateLimitStatus::from_response(
+        &meta(StatusCode::TOO_MANY_REQUESTS, &[("retry-after", "5")]),
+        now,
+      ),
+      Some(RateLimitStatus {
+        limit: None,
+        remaining: 0,
+        reset_at: Some(now + Duration::from_secs(5)),
+      })
+    );
+
+    assert_eq!(
+      RateLimitStatus::from_response(&meta(StatusCode::OK, &[]), now),
+      None
+    );
+  }
+}
@@ -0,0 +1,162 @@
+//! Conditional requests, which let a `Client` revalidate the responses of
+//! frequently polled endpoints instead of downloading them again
+
+use crate::{common::*, transport::ApiResponse};
+
+/// Stores the validators and bodies of `GET` responses, registered with
+/// `ClientBuilder::response_cache`
+///
+/// Entries are keyed by endpoint, including its query string, such as
+/// `/books?page=1`. Implementations may keep them in memory, like
+/// `MemoryCacheStore`, or on disk to survive restarts.
+///
+/// ```no_run
+/// use {
+///   readwise::{
+///     client::ClientBuilder,
+///     response_cache::{CacheStore, CachedResponse},
+///   },
+///   std::{fs, path::PathBuf},
+/// };
+///
+/// struct DiskStore(PathBuf);
+///
+/// impl DiskStore {
+///   fn path(&self, endpoint: &str) -> PathBuf {
+///     self.0.join(endpoint.replace(['/', '?', '&'], "_"))
+///   }
+/// }
+///
+/// impl CacheStore for DiskStore {
+///   fn get(&self, endpoint: &str) -> Option<CachedResponse> {
+///     let body = fs::read(self.path(endpoint)).ok()?;
+///     let etag = fs::read_to_string(self.path(endpoint).with_extension("etag")).ok();
+///
+///     Some(CachedResponse {
+///       etag,
+///       last_modified: None,
+///       body,
+///     })
+///   }
+///
+///   fn put(&self, endpoint: &str, response: CachedResponse) {
+///     if let Some(etag) = &response.etag {
+///       fs::write(self.path(endpoint).with_extension("etag"), etag).ok();
+///       fs::write(self.path(endpoint), &response.body).ok();
+///     }
+///   }
+/// }
+///
+/// let client = ClientBuilder::new("token")
+///   .response_cache(Box::new(DiskStore("cache".into())))
+///   .build()
+///   .unwrap();
+/// ```
+pub trait CacheStore: Send + Sync {
+  /// The stored response for `endpoint`, if any
+  fn get(&self, endpoint: &str) -> Option<CachedResponse>;
+
+  /// Store `response` for `endpoint`, replacing any earlier one
+  fn put(&self, endpoint: &str, response: CachedResponse);
+}
+
+/// A response body along with the validators used to revalidate it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedResponse {
+  /// The `ETag` header, sent back as `If-None-Match`
+  pub etag: Option<String>,
+  /// The `Last-Modified` header, sent back as `If-Modified-Since`
+  pub last_modified: Option<String>,
+  pub body: Vec<u8>,
+}
+
+impl CachedResponse {
+  /// The cacheable part of `response`, if it carries a validator
+  pub(crate) fn from_response(response: &ApiResponse) -> Option<Self> {
+    let header = |name: header::HeaderName| {
+      Some(response.headers.get(name)?.to_str().ok()?.to_string())
+    };
+
+    let (etag, last_modified) =
+      (header(header::ETAG), header(header::LAST_MODIFIED));
+
+    (etag.is_some() || last_modified.is_some()).then(|| Self {
+      etag,
+      last_modified,
+      body: response.body.clone(),
+    })
+  }
+
+  /// Add the conditional headers that revalidate this response to `headers`
+  pub(crate) fn validate(&self, headers: &mut header::HeaderMap) {
+    for (name, value) in [
+      (header::IF_NONE_MATCH, &self.etag),
+      (header::IF_MODIFIED_SINCE, &self.last_modified),
+    ] {
+      if let Some(value) = value
+        .as_deref()
+        .and_then(|value| header::HeaderValue::from_str(value).ok())
+      {
+        headers.insert(name, value);
+      }
+    }
+  }
+}
+
+/// A `CacheStore` that keeps every response in memory for the life of the
+/// client
+#[derive(Debug, Default)]
+pub struct MemoryCacheStore {
+  entries: Mutex<HashMap<String, CachedResponse>>,
+}
+
+impl MemoryCacheStore {
+  pub fn new() -> Self {
+    Self::default()
+  }
+}
+
+impl CacheStore for MemoryCacheStore {
+  fn get(&self, endpoint: &str) -> Option<CachedResponse> {
+    self.entries.lock().ok()?.get(endpoint).cloned()
+  }
+
+  fn put(&self, endpoint: &str, response: CachedResponse) {
+    if let Ok(mut entries) = self.entries.lock() {
+      entries.insert(endpoint.to_string(), response);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn validators() {
+    let mut response = ApiResponse {
+      status: StatusCode::OK,
+      headers: header::HeaderMap::new(),
+      url: String::new(),
+      body: b"{}".to_vec(),
+    };
+
+    assert_eq!(CachedResponse::from_response(&response), None);
+
+    response
+      .headers
+      .insert(header::ETAG, header::HeaderValue::from_static("\"v1\""));
+
+    let cached = CachedResponse::from_response(&response).unwrap();
+
+    assert_eq!(cached.etag.as_deref(), Some("\"v1\""));
+    assert_eq!(cached.last_modified, None);
+
+    let mut headers = header::HeaderMap::new();
+
+    cached.validate(&mut headers);
+
+    assert_eq!(headers[header::IF_NONE_MATCH], "\"v1\"");
+    assert!(!headers.contains_key(header::IF_MODIFIED_SINCE));
+  }
+}
@@ -0,0 +1,182 @@
+//! Limits on the retries spent across many requests, such as those of a
+//! whole sync run
+
+use {crate::common::*, std::sync::PoisonError};
+
+/// The longest wait before the first retry of a request whose response has no
+/// `Retry-After` header
+pub(crate) const DEFAULT_BACKOFF_BASE: Duration = Duration::from_secs(1);
+
+/// The longest wait before any retry of a request whose response has no
+/// `Retry-After` header
+pub(crate) const DEFAULT_BACKOFF_CAP: Duration = Duration::from_secs(60);
+
+/// The most retries, and the longest total wait before them, that a client
+/// may spend across all of its requests, see `Client::retry_budget`
+///
+/// Limits left unset are unbounded.
+///
+/// ```
+/// use {readwise::retry::RetryBudget, std::time::Duration};
+///
+/// let budget = RetryBudget::new()
+///   .retries(50)
+///   .backoff(Duration::from_secs(5 * 60));
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RetryBudget {
+  retries: Option<u32>,
+  backoff: Option<Duration>,
+}
+
+impl RetryBudget {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Allow at most `retries` retries in total
+  pub fn retries(mut self, retries: u32) -> Self {
+    self.retries = Some(retries);
+    self
+  }
+
+  /// Allow at most `backoff` of waiting before retries in total, including
+  /// the waits asked for by `Retry-After` headers
+  pub fn backoff(mut self, backoff: Duration) -> Self {
+    self.backoff = Some(backoff);
+    self
+  }
+}
+
+/// The retries and waits spent so far from a `RetryBudget`
+#[derive(Debug)]
+pub(crate) struct RetryAllowance {
+  budget: RetryBudget,
+  spent: Mutex<(u32, Duration)>,
+}
+
+impl RetryAllowance {
+  pub(crate) fn new(budget: RetryBudget) -> Self {
+    Self {
+      budget,
+      spent: Mutex::new((0, Duration::ZERO)),
+    }
+  }
+
+  /// Spend a retry after `wait`, unless that would exceed the budget
+  pub(crate) fn spend(&self, wait: Duration) -> bool {
+    let mut spent = self.spent.lock().unwrap_or_else(PoisonError::into_inner);
+
+    let (retries, backoff) =
+      (spent.0.saturating_add(1), spent.1.saturating_add(wait));
+
+    let allowed = self.budget.retries.is_none_or(|max| retries <= max)
+      && self.budget.backoff.is_none_or(|max| backoff <= max);
+
+    if allowed {
+      *spent = (retries, backoff);
+    }
+
+    allowed
+  }
+}
+
+/// How a client retries its requests, shared by `Client` and `AsyncClient`
+#[derive(Debug, Clone)]
+pub(crate) struct RetryPolicy {
+  /// How many times a rate limited or failed request is retried
+  pub(crate) retries: u32,
+  /// The longest wait before the first retry without `Retry-After`
+  pub(crate) backoff_base: Duration,
+  /// The longest wait before any retry without `Retry-After`
+  pub(crate) backoff_cap: Duration,
+  /// The retries left to all requests, see `Client::retry_budget`
+  pub(crate) allowance: Option<Arc<RetryAllowance>>,
+}
+
+impl RetryPolicy {
+  /// The wait before retrying a request that has been retried `attempt`
+  /// times, `retry_after` if the response asked for one, or `None` once its
+  /// retries or the retry budget are spent
+  pub(crate) fn wait(
+    &self,
+    attempt: u32,
+    retry_after: Option<Duration>,
+  ) -> Option<Duration> {
+    (attempt < self.retries)
+      .then(|| retry_after.unwrap_or_else(|| self.backoff(attempt + 1)))
+      .filter(|wait| {
+        self
+          .allowance
+          .as_ref()
+          .is_none_or(|allowance| allowance.spend(*wait))
+      })
+  }
+
+  /// A random wait before retry `attempt`, between half of and the full
+  /// exponentially growing backoff
+  pub(crate) fn backoff(&self, attempt: u32) -> Duration {
+    let ceiling = self
+      .backoff_base
+      .saturating_mul(1 << attempt.saturating_sub(1).min(31))
+      .min(self.backoff_cap);
+
+    let half = ceiling / 2;
+
+    half + (ceiling - half).mul_f64(jitter())
+  }
+}
+
+/// A random fraction between zero and one
+fn jitter() -> f64 {
+  #[cfg(not(target_arch = "wasm32"))]
+  return rand::random();
+
+  #[cfg(target_arch = "wasm32")]
+  return js_sys::Math::random();
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn backoff_grows_to_cap() {
+    let policy = RetryPolicy {
+      retries: 0,
+      backoff_base: Duration::from_millis(100),
+      backoff_cap: Duration::from_secs(1),
+      allowance: None,
+    };
+
+    for (attempt, ceiling) in
+      [(1, 100), (2, 200), (4, 800), (5, 1000), (40, 1000)]
+    {
+      let ceiling = Duration::from_millis(ceiling);
+
+      for _ in 0..20 {
+        let wait = policy.backoff(attempt);
+        assert!(wait >= ceiling / 2 && wait <= ceiling, "{:?}", wait);
+      }
+    }
+  }
+
+  #[test]
+  fn spend() {
+    let allowance = RetryAllowance::new(
+      RetryBudget::new()
+        .retries(3)
+        .backoff(Duration::from_secs(10)),
+    );
+
+    assert!(allowance.spend(Duration::from_secs(4)));
+    assert!(allowance.spend(Duration::from_secs(4)));
+    assert!(!allowance.spend(Duration::from_secs(4)));
+    assert!(allowance.spend(Duration::from_secs(2)));
+    assert!(!allowance.spend(Duration::ZERO));
+
+    let unbounded = RetryAllowance::new(RetryBudget::new());
+
+    assert!((0..100).all(|_| unbounded.spend(Duration::from_secs(60))));
+  }
+}
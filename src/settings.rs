@@ -0,0 +1,126 @@
+//! Connection settings shared by `ClientBuilder` and `AsyncClientBuilder`, so
+//! that both clients can be configured once
+
+use crate::common::*;
+
+/// How requests reach the API, and how they are paced and retried, whichever
+/// client sends them
+#[derive(Debug, Clone)]
+pub(crate) struct HttpSettings {
+  pub(crate) access_token: SecretToken,
+  pub(crate) base_url: String,
+  pub(crate) timeout: Option<Duration>,
+  pub(crate) user_agent: Option<String>,
+  pub(crate) proxy: Option<String>,
+  pub(crate) headers: Vec<(String, String)>,
+  pub(crate) retries: u32,
+  pub(crate) retry_budget: Option<RetryBudget>,
+  pub(crate) backoff_base: Duration,
+  pub(crate) backoff_cap: Duration,
+  pub(crate) rate_limit: u32,
+}
+
+impl HttpSettings {
+  pub(crate) fn new(access_token: &str) -> Self {
+    Self {
+      access_token: SecretToken::new(access_token),
+      base_url: request_url(),
+      timeout: None,
+      user_agent: None,
+      proxy: None,
+      headers: Vec::new(),
+      retries: 0,
+      retry_budget: None,
+      backoff_base: DEFAULT_BACKOFF_BASE,
+      backoff_cap: DEFAULT_BACKOFF_CAP,
+      rate_limit: 0,
+    }
+  }
+
+  /// The retries, backoff and a fresh allowance from the retry budget
+  pub(crate) fn retry_policy(&self) -> RetryPolicy {
+    RetryPolicy {
+      retries: self.retries,
+      backoff_base: self.backoff_base,
+      backoff_cap: self.backoff_cap,
+      allowance: self
+        .retry_budget
+        .map(|budget| Arc::new(RetryAllowance::new(budget))),
+    }
+  }
+
+  /// A throttle for the rate limit, or `None` if requests are unlimited
+  pub(crate) fn throttle(&self) -> Option<Arc<Throttle>> {
+    Throttle::per_minute(self.rate_limit).map(Arc::new)
+  }
+
+  /// The headers sent with every request: the extra headers followed by the
+  /// access token, which replaces any `Authorization` header among them
+  ///
+  /// An invalid extra header fails with `Error::InvalidHeader` and a blank
+  /// token with `Error::MissingToken`.
+  pub(crate) fn default_headers(&self) -> Result<header::HeaderMap> {
+    let mut headers = header::HeaderMap::new();
+
+    for (name, value) in &self.headers {
+      let context = error::InvalidHeaderSnafu { name };
+
+      headers.append(
+        header::HeaderName::try_from(name.as_str())
+          .map_err(http::Error::from)
+          .context(context)?,
+        header::HeaderValue::try_from(value.as_str())
+          .map_err(http::Error::from)
+          .context(context)?,
+      );
+    }
+
+    headers.insert(header::AUTHORIZATION, self.access_token.header_value()?);
+
+    Ok(headers)
+  }
+
+  /// A blocking `reqwest` client with the timeout, user agent and proxy
+  #[cfg(feature = "blocking")]
+  pub(crate) fn blocking_client(&self) -> Result<blocking::Client> {
+    let mut http = blocking::Client::builder()
+      .user_agent(self.user_agent.as_deref().unwrap_or(USER_AGENT));
+
+    if let Some(timeout) = self.timeout {
+      http = http.timeout(timeout);
+    }
+
+    if let Some(proxy) = &self.proxy {
+      http = http.proxy(reqwest::Proxy::all(proxy)?);
+    }
+
+    Ok(http.build()?)
+  }
+
+  /// An asynchronous `reqwest` client sending the default headers, with the
+  /// timeout, user agent and proxy except on `wasm32`, where the browser
+  /// controls them
+  #[cfg(feature = "async")]
+  pub(crate) fn async_client(&self) -> Result<reqwest::Client> {
+    let http =
+      reqwest::Client::builder().default_headers(self.default_headers()?);
+
+    #[cfg(not(target_arch = "wasm32"))]
+    let http = {
+      let mut http =
+        http.user_agent(self.user_agent.as_deref().unwrap_or(USER_AGENT));
+
+      if let Some(timeout) = self.timeout {
+        http = http.timeout(timeout);
+      }
+
+      if let Some(proxy) = &self.proxy {
+        http = http.proxy(reqwest::Proxy::all(proxy)?);
+      }
+
+      http
+    };
+
+    Ok(http.build()?)
+  }
+}
@@ -0,0 +1,171 @@
+//! Waiting before throttled and retried requests, behind traits so that
+//! applications can supply their own timers, such as their runtime's or a
+//! fake one in tests
+
+use crate::common::*;
+
+#[cfg(all(feature = "async", not(target_arch = "wasm32")))]
+use std::{
+  future::Future,
+  pin::Pin,
+  task::{Context, Poll, Waker},
+};
+
+/// The future returned by `AsyncSleeper::sleep`
+#[cfg(all(feature = "async", not(target_arch = "wasm32")))]
+pub type SleepFuture = futures_util::future::BoxFuture<'static, ()>;
+
+/// The future returned by `AsyncSleeper::sleep`
+#[cfg(all(feature = "async", target_arch = "wasm32"))]
+pub type SleepFuture = futures_util::future::LocalBoxFuture<'static, ()>;
+
+/// Blocks the calling thread for a while, used by the blocking `Client` to
+/// space and retry requests, see `ClientBuilder::sleeper`
+#[cfg(feature = "blocking")]
+pub trait Sleeper: Send + Sync {
+  fn sleep(&self, duration: Duration);
+}
+
+/// Sleeps with `std::thread::sleep`, the default for `Client`
+#[cfg(feature = "blocking")]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ThreadSleeper;
+
+#[cfg(feature = "blocking")]
+impl Sleeper for ThreadSleeper {
+  fn sleep(&self, duration: Duration) {
+    thread::sleep(duration);
+  }
+}
+
+/// Waits without blocking, used by the `AsyncClient` to space and retry
+/// requests, see `AsyncClientBuilder::sleeper`
+///
+/// ```
+/// use {
+///   readwise::sleep::{AsyncSleeper, SleepFuture},
+///   std::time::Duration,
+/// };
+///
+/// struct NoSleep;
+///
+/// impl AsyncSleeper for NoSleep {
+///   fn sleep(&self, _duration: Duration) -> SleepFuture {
+///     Box::pin(async {})
+///   }
+/// }
+/// ```
+#[cfg(feature = "async")]
+pub trait AsyncSleeper: Send + Sync {
+  fn sleep(&self, duration: Duration) -> SleepFuture;
+}
+
+/// Waits on a timer that works under any executor, the default for
+/// `AsyncClient`
+///
+/// Natively a helper thread wakes the waiting task once the duration has
+/// passed; on `wasm32` the wait is a `setTimeout` in the browser.
+#[cfg(feature = "async")]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TimerSleeper;
+
+#[cfg(feature = "async")]
+impl AsyncSleeper for TimerSleeper {
+  #[cfg(not(target_arch = "wasm32"))]
+  fn sleep(&self, duration: Duration) -> SleepFuture {
+    if duration.is_zero() {
+      return Box::pin(futures_util::future::ready(()));
+    }
+
+    let state = Arc::new(Mutex::new(TimerState::default()));
+
+    std::thread::spawn({
+      let state = state.clone();
+      move || {
+        std::thread::sleep(duration);
+
+        let waker = state.lock().ok().and_then(|mut state| {
+          state.elapsed = true;
+          state.waker.take()
+        });
+
+        if let Some(waker) = waker {
+          waker.wake();
+        }
+      }
+    });
+
+    Box::pin(ThreadTimer { state })
+  }
+
+  #[cfg(target_arch = "wasm32")]
+  fn sleep(&self, duration: Duration) -> SleepFuture {
+    let promise = js_sys::Promise::new(&mut |resolve, _reject| {
+      let global = js_sys::global();
+
+      if let Ok(set_timeout) =
+        js_sys::Reflect::get(&global, &js_sys::JsString::from("setTimeout"))
+      {
+        let _ = js_sys::Function::from(set_timeout).call2(
+          &global,
+          &resolve,
+          &js_sys::Number::from(duration.as_secs_f64() * 1000.0),
+        );
+      }
+    });
+
+    Box::pin(async move {
+      let _ = wasm_bindgen_futures::JsFuture::from(promise).await;
+    })
+  }
+}
+
+#[cfg(all(feature = "async", not(target_arch = "wasm32")))]
+#[derive(Default)]
+struct TimerState {
+  elapsed: bool,
+  waker: Option<Waker>,
+}
+
+/// Completes once the helper thread spawned by `TimerSleeper` marks it
+/// elapsed
+#[cfg(all(feature = "async", not(target_arch = "wasm32")))]
+struct ThreadTimer {
+  state: Arc<Mutex<TimerState>>,
+}
+
+#[cfg(all(feature = "async", not(target_arch = "wasm32")))]
+impl Future for ThreadTimer {
+  type Output = ();
+
+  fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+    let mut state = self
+      .state
+      .lock()
+      .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+    if state.elapsed {
+      return Poll::Ready(());
+    }
+
+    state.waker = Some(cx.waker().clone());
+
+    Poll::Pending
+  }
+}
+
+#[cfg(all(test, feature = "async", not(target_arch = "wasm32")))]
+mod tests {
+  use {super::*, std::time::Instant};
+
+  #[tokio::test]
+  async fn timer_sleeps() {
+    let start = Instant::now();
+
+    TimerSleeper.sleep(Duration::from_millis(50)).await;
+
+    assert!(start.elapsed() >= Duration::from_millis(50));
+
+    TimerSleeper.sleep(Duration::ZERO).await;
+  }
+}
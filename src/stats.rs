@@ -0,0 +1,164 @@
+//! Aggregate numbers over a library, such as for a yearly review
+
+use {crate::common::*, std::collections::BTreeMap};
+
+/// How many of the most highlighted books `LibraryStats` keeps
+const TOP_BOOKS: usize = 10;
+
+/// Aggregate numbers over a library's books and highlights
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct LibraryStats {
+  /// Highlights per `(year, month)` they were made in, with highlights
+  /// lacking a valid `highlighted_at` under `None`
+  pub highlights_by_month: BTreeMap<Option<(i32, u32)>, u64>,
+  /// `(book id, highlight count)` of the most highlighted books, most
+  /// highlighted first
+  pub top_books: Vec<(u64, u64)>,
+  /// Highlights in books of each category
+  pub by_category: HashMap<Category, u64>,
+  /// Highlights of each color, leaving out uncolored highlights
+  pub by_color: HashMap<Color, u64>,
+  /// Words across the text of every highlight
+  pub total_words_highlighted: u64,
+}
+
+impl LibraryStats {
+  /// Compute the statistics of `books` and `highlights`
+  ///
+  /// ```
+  /// use readwise::{model::Highlight, stats::LibraryStats};
+  ///
+  /// let stats = LibraryStats::compute(
+  ///   &[],
+  ///   &[Highlight {
+  ///     text: "hello world".into(),
+  ///     highlighted_at: Some("2022-10-01T12:00:00Z".into()),
+  ///     ..Default::default()
+  ///   }],
+  /// );
+  ///
+  /// assert_eq!(stats.highlights_by_month[&Some((2022, 10))], 1);
+  /// assert_eq!(stats.total_words_highlighted, 2);
+  /// ```
+  pub fn compute(books: &[Book], highlights: &[Highlight]) -> Self {
+    let mut stats = Self::default();
+
+    for highlight in highlights {
+      *stats
+        .highlights_by_month
+        .entry(highlight.highlighted_at.as_deref().and_then(year_month))
+        .or_default() += 1;
+
+      if let Some(color) = Color::from_name(&highlight.color) {
+        *stats.by_color.entry(color).or_default() += 1;
+      }
+
+      stats.total_words_highlighted += highlight.word_count() as u64;
+    }
+
+    for book in books {
+      if let Some(category) = Category::from_name(&book.category) {
+        *stats.by_category.entry(category).or_default() += book.num_highlights;
+      }
+    }
+
+    let mut top_books = books
+      .iter()
+      .map(|book| (book.id, book.num_highlights))
+      .collect::<Vec<(u64, u64)>>();
+
+    top_books.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    top_books.truncate(TOP_BOOKS);
+
+    stats.top_books = top_books;
+
+    stats
+  }
+}
+
+/// The year and month of an ISO 8601 timestamp such as
+/// `2021-02-20T16:28:53.900414Z`
+fn year_month(timestamp: &str) -> Option<(i32, u32)> {
+  let mut parts = timestamp.splitn(3, '-');
+
+  let year = parts.next()?.parse().ok()?;
+  let month = parts
+    .next()?
+    .parse()
+    .ok()
+    .filter(|month| (1..=12).contains(month))?;
+
+  Some((year, month))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn compute() {
+    let books = (1..=12)
+      .map(|id| Book {
+        id,
+        category: match id % 3 {
+          0 => "books",
+          1 => "articles",
+          _ => "unknown",
+        }
+        .into(),
+        num_highlights: id * 2,
+        ..Default::default()
+      })
+      .collect::<Vec<Book>>();
+
+    let highlight =
+      |text: &str, highlighted_at: Option<&str>, color: &str| Highlight {
+        text: text.into(),
+        highlighted_at: highlighted_at.map(String::from),
+        color: color.into(),
+        ..Default::default()
+      };
+
+    let highlights = [
+      highlight("one two three", Some("2022-01-05T10:00:00Z"), "yellow"),
+      highlight("four\u{00a0}five", Some("2022-01-20T10:00:00Z"), "yellow"),
+      highlight("six", Some("2022-03-01T00:00:00.123456Z"), "blue"),
+      highlight("  seven\neight  ", None, ""),
+      highlight("nine", Some("not a date"), "mauve"),
+    ];
+
+    let stats = LibraryStats::compute(&books, &highlights);
+
+    assert_eq!(
+      stats.highlights_by_month,
+      BTreeMap::from([(None, 2), (Some((2022, 1)), 2), (Some((2022, 3)), 1),])
+    );
+
+    assert_eq!(
+      stats.top_books,
+      (3..=12).rev().map(|id| (id, id * 2)).collect::<Vec<_>>()
+    );
+
+    assert_eq!(
+      stats.by_category,
+      HashMap::from([
+        (Category::Books, 2 * (3 + 6 + 9 + 12)),
+        (Category::Articles, 2 * (1 + 4 + 7 + 10)),
+      ])
+    );
+
+    assert_eq!(
+      stats.by_color,
+      HashMap::from([(Color::Yellow, 2), (Color::Blue, 1)])
+    );
+
+    assert_eq!(stats.total_words_highlighted, 9);
+  }
+
+  #[test]
+  fn year_month() {
+    assert_eq!(super::year_month("2021-02-20T16:28:53Z"), Some((2021, 2)));
+    assert_eq!(super::year_month("2021-13-01"), None);
+    assert_eq!(super::year_month(""), None);
+  }
+}
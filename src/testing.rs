@@ -0,0 +1,113 @@
+//! Test doubles for code built on this crate
+
+use {
+  crate::{
+    common::*,
+    transport::{ApiRequest, ApiResponse, Transport},
+  },
+  std::collections::VecDeque,
+};
+
+/// A `Transport` that records requests and answers them with canned
+/// responses instead of touching the network
+///
+/// Responses are registered per method and path, ignoring the query, and
+/// are returned in the order they were added, the last one repeating.
+/// Requests with no registered response receive an empty `404 Not Found`.
+/// Clones share their responses and recorded requests, so a clone can be
+/// kept to inspect the requests a `Client` made.
+///
+/// ```
+/// use readwise::{
+///   client::Client,
+///   model::DeleteOutcome,
+///   testing::FakeTransport,
+/// };
+///
+/// let transport = FakeTransport::new().respond("DELETE", "/highlights/1", 204, "");
+///
+/// let client =
+///   Client::with_transport("token", Box::new(transport.clone())).unwrap();
+///
+/// assert_eq!(client.delete_highlight(1).unwrap(), DeleteOutcome::Deleted);
+/// assert_eq!(transport.requests()[0].path, "/highlights/1");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct FakeTransport {
+  inner: Arc<Mutex<Inner>>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+  responses: HashMap<(Method, String), VecDeque<ApiResponse>>,
+  requests: Vec<ApiRequest>,
+}
+
+impl FakeTransport {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Answer `method` requests to `path` with `status` and `body`
+  ///
+  /// # Panics
+  ///
+  /// If `method` or `status` is invalid.
+  pub fn respond(
+    self,
+    method: &str,
+    path: &str,
+    status: u16,
+    body: &str,
+  ) -> Self {
+    let method = Method::from_bytes(method.as_bytes()).unwrap();
+
+    let response = ApiResponse {
+      status: StatusCode::from_u16(status).unwrap(),
+      headers: header::HeaderMap::new(),
+      url: format!("{}/api/v2{}", READWISE_URL, path),
+      body: body.as_bytes().to_vec(),
+    };
+
+    self
+      .inner
+      .lock()
+      .unwrap()
+      .responses
+      .entry((method, path.to_string()))
+      .or_default()
+      .push_back(response);
+
+    self
+  }
+
+  /// Every request received so far, oldest first
+  pub fn requests(&self) -> Vec<ApiRequest> {
+    self.inner.lock().unwrap().requests.clone()
+  }
+}
+
+impl Transport for FakeTransport {
+  fn execute(&self, request: ApiRequest) -> Result<ApiResponse> {
+    let mut inner = self.inner.lock().unwrap();
+
+    let key = (request.method.clone(), request.path.clone());
+
+    let response = match inner.responses.get_mut(&key) {
+      Some(queue) if queue.len() > 1 => queue.pop_front(),
+      Some(queue) => queue.front().cloned(),
+      None => None,
+    };
+
+    let response = response.unwrap_or_else(|| ApiResponse {
+      status: StatusCode::NOT_FOUND,
+      headers: header::HeaderMap::new(),
+      url: format!("{}/api/v2{}", READWISE_URL, request.path),
+      body: Vec::new(),
+    });
+
+    inner.requests.push(request);
+
+    Ok(response)
+  }
+}
@@ -0,0 +1,385 @@
+//! Text normalization and comparison for highlights that describe the same
+//! passage but were imported from different sources.
+
+use {
+  crate::common::*, std::collections::hash_map::Entry,
+  unicode_normalization::UnicodeNormalization,
+};
+
+/// The reading speed assumed by `reading_time`, the average for adults
+/// reading silently
+pub const WORDS_PER_MINUTE: u64 = 238;
+
+/// Options controlling `normalize`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NormalizeOptions {
+  /// Fold curly quotes, primes and dashes to their ASCII equivalents
+  pub fold_punctuation: bool,
+  /// Collapse runs of whitespace, including non-breaking spaces, into a
+  /// single space, rejoining words hyphenated across line breaks
+  pub collapse_whitespace: bool,
+  /// Lowercase the text
+  pub lowercase: bool,
+  /// Strip punctuation, such as trailing ellipses, from both ends
+  pub strip_punctuation: bool,
+}
+
+impl Default for NormalizeOptions {
+  fn default() -> Self {
+    Self {
+      fold_punctuation: true,
+      collapse_whitespace: true,
+      lowercase: false,
+      strip_punctuation: false,
+    }
+  }
+}
+
+impl NormalizeOptions {
+  /// The options used by `similarity`, which ignore case and surrounding
+  /// punctuation entirely
+  pub fn comparison() -> Self {
+    Self {
+      lowercase: true,
+      strip_punctuation: true,
+      ..Self::default()
+    }
+  }
+}
+
+/// Normalize `text` so that trivially different renderings of the same
+/// passage compare equal
+///
+/// The text is always converted to Unicode NFC first, so precomposed and
+/// combining forms of the same character are treated alike.
+///
+/// ```
+/// use readwise::text::{normalize, NormalizeOptions};
+///
+/// assert_eq!(
+///   normalize("“Hello,\u{a0}world”", NormalizeOptions::default()),
+///   "\"Hello, world\""
+/// );
+/// ```
+pub fn normalize(text: &str, options: NormalizeOptions) -> String {
+  let mut normalized = text.nfc().collect::<String>();
+
+  if options.fold_punctuation {
+    normalized = normalized.chars().map(fold).collect();
+  }
+
+  if options.collapse_whitespace {
+    normalized = collapse_whitespace(&normalized);
+  }
+
+  if options.lowercase {
+    normalized = normalized.to_lowercase();
+  }
+
+  if options.strip_punctuation {
+    normalized = normalized
+      .trim_matches(|c: char| !c.is_alphanumeric())
+      .to_string();
+  }
+
+  normalized
+}
+
+/// The token-based Jaccard similarity of two texts, between `0.0` and `1.0`
+///
+/// Both texts are normalized with `NormalizeOptions::comparison` and split
+/// into words with surrounding punctuation removed.
+///
+/// ```
+/// use readwise::text::similarity;
+///
+/// assert_eq!(similarity("Hello, world!", "hello world"), 1.0);
+/// ```
+pub fn similarity(a: &str, b: &str) -> f64 {
+  let (a, b) = (tokens(a), tokens(b));
+
+  if a.is_empty() && b.is_empty() {
+    return 1.0;
+  }
+
+  a.intersection(&b).count() as f64 / a.union(&b).count() as f64
+}
+
+/// The number of words in `text`, separated by any Unicode whitespace
+///
+/// Runs of punctuation on their own, such as a spaced dash, are not words.
+///
+/// ```
+/// use readwise::text::word_count;
+///
+/// assert_eq!(word_count("It\u{2019}s \u{2014} well,\u{a0}fine"), 3);
+/// ```
+pub fn word_count(text: &str) -> usize {
+  text
+    .split_whitespace()
+    .filter(|word| word.chars().any(char::is_alphanumeric))
+    .count()
+}
+
+/// How long reading `words` words takes at `WORDS_PER_MINUTE`
+pub fn reading_time(words: usize) -> Duration {
+  Duration::from_millis(words as u64 * 60_000 / WORDS_PER_MINUTE)
+}
+
+/// Group highlights whose text is at least `threshold` similar to the first
+/// highlight of a group, keeping only groups with more than one member
+pub fn group_similar(
+  highlights: Vec<Highlight>,
+  threshold: f64,
+) -> Vec<Vec<Highlight>> {
+  let mut groups: Vec<Vec<Highlight>> = Vec::new();
+
+  for highlight in highlights {
+    match groups
+      .iter_mut()
+      .find(|group| similarity(&group[0].text, &highlight.text) >= threshold)
+    {
+      Some(group) => group.push(highlight),
+      None => groups.push(vec![highlight]),
+    }
+  }
+
+  groups.retain(|group| group.len() > 1);
+
+  groups
+}
+
+/// Group highlights whose text is identical once normalized with `options`,
+/// keeping only groups with more than one member, in order of first
+/// appearance
+pub fn group_identical(
+  highlights: Vec<Highlight>,
+  options: NormalizeOptions,
+) -> Vec<Vec<Highlight>> {
+  let mut groups: Vec<Vec<Highlight>> = Vec::new();
+  let mut index = HashMap::<String, usize>::new();
+
+  for highlight in highlights {
+    match index.entry(normalize(&highlight.text, options)) {
+      Entry::Occupied(entry) => groups[*entry.get()].push(highlight),
+      Entry::Vacant(entry) => {
+        entry.insert(groups.len());
+        groups.push(vec![highlight]);
+      }
+    }
+  }
+
+  groups.retain(|group| group.len() > 1);
+
+  groups
+}
+
+fn tokens(text: &str) -> HashSet<String> {
+  normalize(text, NormalizeOptions::comparison())
+    .split_whitespace()
+    .map(|token| token.trim_matches(|c: char| !c.is_alphanumeric()))
+    .filter(|token| !token.is_empty())
+    .map(str::to_string)
+    .collect()
+}
+
+fn fold(c: char) -> char {
+  match c {
+    '\u{2018}' | '\u{2019}' | '\u{201a}' | '\u{201b}' | '\u{2032}' => '\'',
+    '\u{201c}' | '\u{201d}' | '\u{201e}' | '\u{201f}' | '\u{2033}' => '"',
+    '\u{00ab}' | '\u{00bb}' => '"',
+    '\u{2010}' | '\u{2011}' | '\u{2012}' | '\u{2013}' | '\u{2014}'
+    | '\u{2015}' | '\u{2212}' => '-',
+    c => c,
+  }
+}
+
+fn collapse_whitespace(text: &str) -> String {
+  let mut collapsed = String::with_capacity(text.len());
+
+  let mut chars = text.chars().peekable();
+
+  while let Some(c) = chars.next() {
+    if !c.is_whitespace() {
+      collapsed.push(c);
+      continue;
+    }
+
+    let mut line_break = c == '\n';
+
+    while let Some(next) = chars.peek().filter(|next| next.is_whitespace()) {
+      line_break |= *next == '\n';
+      chars.next();
+    }
+
+    let hyphenated = line_break
+      && collapsed.ends_with('-')
+      && collapsed
+        .chars()
+        .rev()
+        .nth(1)
+        .is_some_and(char::is_alphabetic)
+      && chars.peek().is_some_and(|next| next.is_alphabetic());
+
+    if hyphenated {
+      collapsed.pop();
+    } else if !collapsed.is_empty() && chars.peek().is_some() {
+      collapsed.push(' ');
+    }
+  }
+
+  collapsed
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn count_words() {
+    assert_eq!(word_count(""), 0);
+    assert_eq!(word_count(" \t\n "), 0);
+    assert_eq!(word_count("one\u{3000}two\u{2003}three - four"), 4);
+    assert_eq!(word_count("\u{201c}Quoted,\u{201d} she said."), 3);
+  }
+
+  #[test]
+  fn reading_time_at_average_speed() {
+    assert_eq!(reading_time(0), Duration::ZERO);
+    assert_eq!(reading_time(238), Duration::from_secs(60));
+    assert_eq!(reading_time(119), Duration::from_secs(30));
+  }
+
+  #[test]
+  fn fold_quotes_and_dashes() {
+    assert_eq!(
+      normalize(
+        "\u{201c}It\u{2019}s\u{201d} \u{2014} she said",
+        NormalizeOptions::default()
+      ),
+      "\"It's\" - she said"
+    );
+  }
+
+  #[test]
+  fn collapse_non_breaking_spaces() {
+    assert_eq!(
+      normalize(
+        "  one\u{a0}\u{a0}two\t\nthree  ",
+        NormalizeOptions::default()
+      ),
+      "one two three"
+    );
+  }
+
+  #[test]
+  fn rejoin_hyphenated_line_breaks() {
+    assert_eq!(
+      normalize(
+        "an exam-\nple of well-known text",
+        NormalizeOptions::default()
+      ),
+      "an example of well-known text"
+    );
+  }
+
+  #[test]
+  fn compose_combining_characters() {
+    assert_eq!(
+      normalize("cafe\u{301}", NormalizeOptions::default()),
+      normalize("caf\u{e9}", NormalizeOptions::default())
+    );
+  }
+
+  #[test]
+  fn strip_punctuation_and_lowercase() {
+    assert_eq!(
+      normalize("\u{2026}The End\u{2026}", NormalizeOptions::comparison()),
+      "the end"
+    );
+  }
+
+  #[test]
+  fn similarity_ignores_formatting() {
+    assert_eq!(
+      similarity(
+        "\u{201c}Stay hungry,\u{a0}stay foolish.\u{201d}",
+        "\"stay hungry, stay foolish...\""
+      ),
+      1.0
+    );
+  }
+
+  #[test]
+  fn group_similar_highlights() {
+    let highlight = |id, text: &str| Highlight {
+      id,
+      text: text.into(),
+      ..Default::default()
+    };
+
+    let groups = group_similar(
+      vec![
+        highlight(1, "\u{201c}Stay hungry, stay foolish.\u{201d}"),
+        highlight(2, "Something else entirely"),
+        highlight(3, "stay hungry stay foolish\u{2026}"),
+      ],
+      0.9,
+    );
+
+    assert_eq!(groups.len(), 1);
+
+    assert_eq!(
+      groups[0].iter().map(|h| h.id).collect::<Vec<_>>(),
+      vec![1, 3]
+    );
+  }
+
+  #[test]
+  fn group_identical_highlights() {
+    let highlight = |id, text: &str| Highlight {
+      id,
+      text: text.into(),
+      ..Default::default()
+    };
+
+    let highlights = || {
+      vec![
+        highlight(1, "  Stay hungry,\nstay foolish. "),
+        highlight(2, "Something else"),
+        highlight(3, "stay hungry, stay foolish."),
+        highlight(4, "Stay hungry, stay foolish."),
+      ]
+    };
+
+    let ids = |groups: Vec<Vec<Highlight>>| {
+      groups
+        .iter()
+        .map(|group| group.iter().map(|h| h.id).collect::<Vec<u64>>())
+        .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+      ids(group_identical(highlights(), NormalizeOptions::default())),
+      [vec![1, 4]]
+    );
+
+    assert_eq!(
+      ids(group_identical(
+        highlights(),
+        NormalizeOptions {
+          lowercase: true,
+          ..NormalizeOptions::default()
+        }
+      )),
+      [vec![1, 3, 4]]
+    );
+  }
+
+  #[test]
+  fn similarity_partial_overlap() {
+    assert_eq!(similarity("a b c d", "a b"), 0.5);
+    assert_eq!(similarity("a", "b"), 0.0);
+    assert_eq!(similarity("", ""), 1.0);
+  }
+}
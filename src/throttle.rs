@@ -0,0 +1,95 @@
+use {crate::common::*, std::sync::PoisonError};
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::{sync::OnceLock, time::Instant};
+
+/// The time since a fixed point in the past, monotonic natively and taken from
+/// `Date.now()` on `wasm32`, which has no `Instant`
+fn now() -> Duration {
+  #[cfg(not(target_arch = "wasm32"))]
+  return {
+    static START: OnceLock<Instant> = OnceLock::new();
+    START.get_or_init(Instant::now).elapsed()
+  };
+
+  #[cfg(target_arch = "wasm32")]
+  return Duration::from_secs_f64(js_sys::Date::now() / 1000.0);
+}
+
+/// Spaces requests at least `interval` apart, a token bucket holding a single
+/// token
+#[derive(Debug)]
+pub(crate) struct Throttle {
+  interval: Duration,
+  /// The earliest time, per `now`, the next request may be sent
+  next: Mutex<Option<Duration>>,
+}
+
+impl Throttle {
+  /// A throttle allowing at most `requests_per_minute` requests a minute, or
+  /// `None` if that is zero
+  pub(crate) fn per_minute(requests_per_minute: u32) -> Option<Self> {
+    (requests_per_minute > 0).then(|| Self {
+      interval: Duration::from_secs(60) / requests_per_minute,
+      next: Mutex::new(None),
+    })
+  }
+
+  /// Reserve the next slot for a request, returning how long to wait before
+  /// sending it
+  pub(crate) fn reserve(&self) -> Duration {
+    let now = now();
+
+    let mut next = self.next.lock().unwrap_or_else(PoisonError::into_inner);
+    let at = next.map_or(now, |next| next.max(now));
+    *next = Some(at + self.interval);
+
+    at - now
+  }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn zero_is_unlimited() {
+    assert!(Throttle::per_minute(0).is_none());
+  }
+
+  #[test]
+  fn spaces_requests() {
+    let throttle = Throttle::per_minute(1200).unwrap();
+
+    let waits = (0..4).map(|_| throttle.reserve()).collect::<Vec<_>>();
+
+    assert_eq!(waits[0], Duration::ZERO);
+
+    for (i, wait) in waits.iter().enumerate().skip(1) {
+      let expected = Duration::from_millis(50) * i as u32;
+      assert!(
+        *wait <= expected && *wait + Duration::from_millis(10) >= expected,
+        "{:?}",
+        waits
+      );
+    }
+  }
+
+  #[test]
+  fn shared_between_threads() {
+    let throttle = Throttle::per_minute(1200).unwrap();
+
+    let mut waits = std::thread::scope(|scope| {
+      (0..4)
+        .map(|_| scope.spawn(|| throttle.reserve()))
+        .collect::<Vec<_>>()
+        .into_iter()
+        .map(|handle| handle.join().unwrap())
+        .collect::<Vec<_>>()
+    });
+
+    waits.sort();
+
+    assert!(waits[3] >= Duration::from_millis(140), "{:?}", waits);
+  }
+}
@@ -0,0 +1,210 @@
+/// Parse an RFC 3339 timestamp, such as `2022-10-01T12:00:00.5Z` or
+/// `2022-10-01T14:00:00+02:00`, into seconds and nanoseconds since the Unix
+/// epoch, so that timestamps in different offsets compare correctly
+///
+/// A bare date is midnight UTC, and a time without an offset is UTC.
+pub(crate) fn parse_timestamp(value: &str) -> Option<(i64, u32)> {
+  let (date, time) = match value.split_once(['T', 't', ' ']) {
+    Some((date, time)) => (date, Some(time)),
+    None => (value, None),
+  };
+
+  let mut fields = date.splitn(3, '-');
+
+  let year = fields.next()?.parse::<i64>().ok()?;
+  let month = fields.next()?.parse::<u32>().ok()?;
+  let day = fields.next()?.parse::<u32>().ok()?;
+
+  if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+    return None;
+  }
+
+  let (seconds, nanos) = match time {
+    Some(time) => parse_time(time)?,
+    None => (0, 0),
+  };
+
+  Some((days_from_civil(year, month, day) * 86_400 + seconds, nanos))
+}
+
+/// Format seconds since the Unix epoch as an RFC 3339 timestamp in UTC, such
+/// as `2022-10-01T12:00:00Z`
+pub(crate) fn format_timestamp(seconds: i64) -> String {
+  let (days, seconds) =
+    (seconds.div_euclid(86_400), seconds.rem_euclid(86_400));
+
+  let (year, month, day) = civil_from_days(days);
+
+  format!(
+    "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+    year,
+    month,
+    day,
+    seconds / 3600,
+    seconds % 3600 / 60,
+    seconds % 60
+  )
+}
+
+/// Seconds since midnight UTC and nanoseconds of a time such as
+/// `12:00:00.5+02:00`
+fn parse_time(time: &str) -> Option<(i64, u32)> {
+  let (clock, offset) = match time.find(['Z', 'z', '+', '-']) {
+    Some(index) => time.split_at(index),
+    None => (time, ""),
+  };
+
+  let offset = match offset {
+    "" | "Z" | "z" => 0,
+    offset => {
+      let (hours, minutes) = offset[1..].split_once(':')?;
+
+      let seconds =
+        hours.parse::<i64>().ok()? * 3600 + minutes.parse::<i64>().ok()? * 60;
+
+      match offset.starts_with('-') {
+        true => -seconds,
+        false => seconds,
+      }
+    }
+  };
+
+  let (clock, fraction) = clock.split_once('.').unwrap_or((clock, ""));
+
+  let mut fields = clock.split(':');
+
+  let hour = fields.next()?.parse::<i64>().ok()?;
+  let minute = fields.next()?.parse::<i64>().ok()?;
+  let second = match fields.next() {
+    Some(second) => second.parse::<i64>().ok()?,
+    None => 0,
+  };
+
+  if hour > 23 || minute > 59 || second > 60 || fields.next().is_some() {
+    return None;
+  }
+
+  if !fraction.bytes().all(|byte| byte.is_ascii_digit()) {
+    return None;
+  }
+
+  let nanos = format!("{:0<9}", &fraction[..fraction.len().min(9)])
+    .parse::<u32>()
+    .ok()?;
+
+  Some((hour * 3600 + minute * 60 + second - offset, nanos))
+}
+
+/// Days between the Unix epoch and a date in the proleptic Gregorian
+/// calendar, after Howard Hinnant's `days_from_civil`
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+  let year = if month <= 2 { year - 1 } else { year };
+
+  let era = year.div_euclid(400);
+  let year_of_era = year.rem_euclid(400);
+
+  let month = i64::from(month);
+
+  let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5
+    + i64::from(day)
+    - 1;
+
+  let day_of_era =
+    year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+  era * 146_097 + day_of_era - 719_468
+}
+
+/// The date in the proleptic Gregorian calendar a number of days after the
+/// Unix epoch, after Howard Hinnant's `civil_from_days`
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+  let days = days + 719_468;
+
+  let era = days.div_euclid(146_097);
+  let day_of_era = days.rem_euclid(146_097);
+
+  let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524
+    - day_of_era / 146_096)
+    / 365;
+
+  let day_of_year =
+    day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+
+  let month = (5 * day_of_year + 2) / 153;
+
+  let day = (day_of_year - (153 * month + 2) / 5 + 1) as u32;
+  let month = if month < 10 { month + 3 } else { month - 9 } as u32;
+
+  let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+  (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn epoch() {
+    assert_eq!(parse_timestamp("1970-01-01T00:00:00Z"), Some((0, 0)));
+    assert_eq!(parse_timestamp("1970-01-01"), Some((0, 0)));
+    assert_eq!(parse_timestamp("1969-12-31T23:59:59Z"), Some((-1, 0)));
+  }
+
+  #[test]
+  fn offsets_and_fractions() {
+    assert_eq!(
+      parse_timestamp("2020-07-14T20:11:24+00:00"),
+      parse_timestamp("2020-07-14T20:11:24Z")
+    );
+
+    assert_eq!(
+      parse_timestamp("2022-10-01T02:30:00+02:30"),
+      parse_timestamp("2022-10-01")
+    );
+
+    assert_eq!(
+      parse_timestamp("2022-09-30T19:00:00-05:00"),
+      parse_timestamp("2022-10-01T00:00:00")
+    );
+
+    assert_eq!(
+      parse_timestamp("2022-09-13T16:41:53.186Z"),
+      Some((1_663_087_313, 186_000_000))
+    );
+
+    assert!(
+      parse_timestamp("2022-09-13T16:41:53.1Z")
+        < parse_timestamp("2022-09-13T16:41:53.186Z")
+    );
+  }
+
+  #[test]
+  fn format_round_trip() {
+    assert_eq!(format_timestamp(0), "1970-01-01T00:00:00Z");
+    assert_eq!(format_timestamp(-1), "1969-12-31T23:59:59Z");
+    assert_eq!(format_timestamp(951_782_400), "2000-02-29T00:00:00Z");
+
+    for seconds in [-86_401, 1_663_087_313, 4_102_444_800] {
+      assert_eq!(
+        parse_timestamp(&format_timestamp(seconds)),
+        Some((seconds, 0))
+      );
+    }
+  }
+
+  #[test]
+  fn invalid() {
+    for value in [
+      "",
+      "2022",
+      "2022-13-01",
+      "2022-01-01T25:00:00Z",
+      "2022-01-01T12:00:00.5xZ",
+      "2022-01-01T12:00:00+0200",
+      "yesterday",
+    ] {
+      assert_eq!(parse_timestamp(value), None, "{}", value);
+    }
+  }
+}
@@ -0,0 +1,81 @@
+use crate::common::*;
+
+/// An access token that is masked when formatted, so that it does not leak
+/// into logs through `Debug` output
+#[derive(Clone, Default, PartialEq, Eq)]
+pub(crate) struct SecretToken(String);
+
+impl SecretToken {
+  pub(crate) fn new(token: &str) -> Self {
+    Self(token.to_string())
+  }
+
+  /// The `Authorization` header value for this token, marked sensitive so
+  /// that `HeaderMap`'s `Debug` output omits it
+  ///
+  /// A blank token fails with `Error::MissingToken`, since every request
+  /// sent with it would be rejected.
+  pub(crate) fn header_value(&self) -> Result<header::HeaderValue> {
+    if self.0.trim().is_empty() {
+      return Err(Error::MissingToken);
+    }
+
+    let mut value =
+      header::HeaderValue::from_str(&format!("Token {}", self.0))?;
+    value.set_sensitive(true);
+    Ok(value)
+  }
+}
+
+impl fmt::Debug for SecretToken {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    let visible = match self.0.chars().count() {
+      count if count > 8 => {
+        &self.0[self.0.char_indices().nth(count - 4).unwrap().0..]
+      }
+      _ => "",
+    };
+
+    write!(f, "Token(\"****{}\")", visible)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn debug_masks_token() {
+    assert_eq!(
+      format!("{:?}", SecretToken::new("abcdefgh12345678")),
+      "Token(\"****5678\")"
+    );
+    assert_eq!(
+      format!("{:?}", SecretToken::new("short")),
+      "Token(\"****\")"
+    );
+  }
+
+  #[test]
+  fn blank_token() {
+    for token in ["", "  \n"] {
+      assert!(matches!(
+        SecretToken::new(token).header_value(),
+        Err(Error::MissingToken)
+      ));
+    }
+  }
+
+  #[test]
+  fn header_value_is_sensitive() {
+    let mut headers = header::HeaderMap::new();
+
+    headers.insert(
+      header::AUTHORIZATION,
+      SecretToken::new("abcdefgh12345678").header_value().unwrap(),
+    );
+
+    assert!(headers[header::AUTHORIZATION].is_sensitive());
+    assert!(!format!("{:?}", headers).contains("abcdefgh"));
+  }
+}
@@ -0,0 +1,154 @@
+//! The HTTP layer underneath `Client`, which can be replaced to test code
+//! built on this crate without a network; see `testing::FakeTransport`
+
+use crate::common::*;
+
+/// A request to the Readwise API
+#[derive(Debug, Clone)]
+pub struct ApiRequest {
+  pub method: Method,
+  /// The endpoint path below `/api/v2`, such as `/books`
+  pub path: String,
+  /// Decoded query parameters, in order
+  pub query: Vec<(String, String)>,
+  /// Headers to send, including `Authorization`
+  pub headers: header::HeaderMap,
+  /// The JSON body, if any
+  pub body: Option<serde_json::Value>,
+}
+
+impl ApiRequest {
+  /// Build a request for an endpoint that may include a query string, such
+  /// as `/books?page=1`
+  pub(crate) fn new(
+    method: Method,
+    endpoint: &str,
+    headers: header::HeaderMap,
+    body: Option<serde_json::Value>,
+  ) -> Self {
+    let (path, query) = endpoint.split_once('?').unwrap_or((endpoint, ""));
+
+    Self {
+      method,
+      path: path.to_string(),
+      query: form_urlencoded::parse(query.as_bytes())
+        .into_owned()
+        .collect(),
+      headers,
+      body,
+    }
+  }
+
+  /// The path and encoded query string
+  pub fn endpoint(&self) -> String {
+    match self.query.is_empty() {
+      true => self.path.clone(),
+      false => format!(
+        "{}?{}",
+        self.path,
+        form_urlencoded::Serializer::new(String::new())
+          .extend_pairs(&self.query)
+          .finish()
+      ),
+    }
+  }
+}
+
+/// A response from the Readwise API
+#[derive(Debug, Clone)]
+pub struct ApiResponse {
+  pub status: StatusCode,
+  pub headers: header::HeaderMap,
+  /// The URL that was requested
+  pub url: String,
+  /// The raw body, deserialized straight from these bytes
+  pub body: Vec<u8>,
+}
+
+/// Sends API requests
+pub trait Transport: Send + Sync {
+  /// Send `request`, returning any response the server gave, whatever its
+  /// status
+  fn execute(&self, request: ApiRequest) -> Result<ApiResponse>;
+}
+
+/// The default transport, which sends requests with `reqwest`
+pub struct ReqwestTransport {
+  http: blocking::Client,
+  base_url: String,
+}
+
+impl ReqwestTransport {
+  /// Send requests with `http` to the API served from `base_url`
+  pub fn new(http: blocking::Client, base_url: &str) -> Self {
+    Self {
+      http,
+      base_url: base_url.trim_end_matches('/').to_string(),
+    }
+  }
+}
+
+impl Transport for ReqwestTransport {
+  fn execute(&self, request: ApiRequest) -> Result<ApiResponse> {
+    let url = api_url(&self.base_url, &request.endpoint());
+
+    let builder = match request.method {
+      Method::GET => self.http.get(&url),
+      Method::POST => self.http.post(&url),
+      Method::PATCH => self.http.patch(&url),
+      Method::DELETE => self.http.delete(&url),
+      method => return Err(Error::UnsupportedRequest { method }),
+    };
+
+    let builder = match &request.body {
+      Some(body) => builder.json(body),
+      None => builder,
+    };
+
+    let response = builder.headers(request.headers).send()?;
+
+    let status = response.status();
+    let headers = response.headers().clone();
+    let url = response.url().to_string();
+
+    Ok(ApiResponse {
+      status,
+      headers,
+      url,
+      body: response.bytes()?.to_vec(),
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn endpoint_round_trip() {
+    let request = ApiRequest::new(
+      Method::GET,
+      "/export?updatedAfter=2022-10-01T00%3A00%3A00Z&pageCursor=abc",
+      header::HeaderMap::new(),
+      None,
+    );
+
+    assert_eq!(request.path, "/export");
+    assert_eq!(
+      request.query,
+      [
+        ("updatedAfter".into(), "2022-10-01T00:00:00Z".into()),
+        ("pageCursor".into(), "abc".into())
+      ]
+    );
+    assert_eq!(
+      request.endpoint(),
+      "/export?updatedAfter=2022-10-01T00%3A00%3A00Z&pageCursor=abc"
+    );
+
+    let request =
+      ApiRequest::new(Method::GET, "/export?", header::HeaderMap::new(), None);
+
+    assert_eq!(request.endpoint(), "/export");
+  }
+}
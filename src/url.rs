@@ -1,7 +1,81 @@
+#[cfg(any(feature = "blocking", feature = "async"))]
+use crate::common::*;
+
+/// The Readwise web application, which hosts both the API and the review UI
+pub(crate) const READWISE_URL: &str = "https://readwise.io";
+
+/// The `User-Agent` header sent unless another one is configured, which
+/// browsers do not allow setting
+#[cfg(any(
+  feature = "blocking",
+  all(feature = "async", not(target_arch = "wasm32"))
+))]
+pub(crate) const USER_AGENT: &str =
+  concat!("readwise/", env!("CARGO_PKG_VERSION"));
+
+/// The environment variable `from_env` constructors read the token from
+#[cfg(any(feature = "blocking", feature = "async"))]
+pub(crate) const TOKEN_ENV_VAR: &str = "READWISE_TOKEN";
+
+#[cfg(any(feature = "blocking", feature = "async"))]
 pub(crate) fn request_url() -> String {
-  #[cfg(not(test))]
-  let url = "https://readwise.io".to_string();
-  #[cfg(test)]
+  #[cfg(not(all(test, not(target_arch = "wasm32"))))]
+  let url = READWISE_URL.to_string();
+  #[cfg(all(test, not(target_arch = "wasm32")))]
   let url = mockito::server_url();
   url
 }
+
+/// `path` followed by `params` as its URL encoded query string, appended to
+/// any query `path` already has
+#[cfg(feature = "blocking")]
+pub(crate) fn with_query(path: &str, params: &[(&str, &str)]) -> String {
+  append_query(
+    path,
+    &form_urlencoded::Serializer::new(String::new())
+      .extend_pairs(params)
+      .finish(),
+  )
+}
+
+/// `path` followed by an already encoded `query`, appended to any query
+/// `path` already has
+#[cfg(any(feature = "blocking", feature = "async"))]
+pub(crate) fn append_query(path: &str, query: &str) -> String {
+  match (query.is_empty(), path.contains('?')) {
+    (true, _) => path.to_string(),
+    (false, true) => format!("{}&{}", path, query),
+    (false, false) => format!("{}?{}", path, query),
+  }
+}
+
+/// The export endpoint for a page of books updated after `updated_after` in
+/// `category`, continuing from the page `cursor` points at
+#[cfg(any(feature = "blocking", feature = "async"))]
+pub(crate) fn export_endpoint(
+  updated_after: Option<&str>,
+  category: Option<Category>,
+  cursor: Option<&str>,
+) -> String {
+  let mut query = form_urlencoded::Serializer::new(String::new());
+
+  if let Some(updated_after) = updated_after {
+    query.append_pair("updatedAfter", updated_after);
+  }
+
+  if let Some(category) = category {
+    query.append_pair("category", category.as_str());
+  }
+
+  if let Some(cursor) = cursor {
+    query.append_pair("pageCursor", cursor);
+  }
+
+  append_query("/export", &query.finish())
+}
+
+/// The full URL of a v2 API endpoint served from `base_url`
+#[cfg(any(feature = "blocking", feature = "async"))]
+pub(crate) fn api_url(base_url: &str, endpoint: &str) -> String {
+  format!("{}/api/v2{}", base_url, endpoint)
+}
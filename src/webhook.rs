@@ -0,0 +1,237 @@
+//! Models for the webhooks Readwise sends to integrations when highlights
+//! are created
+
+use crate::common::*;
+
+/// The event type of a webhook for a newly created highlight
+pub const HIGHLIGHT_CREATED: &str = "readwise.highlight.created";
+
+/// A webhook request body sent by Readwise
+///
+/// Fields Readwise adds in the future are ignored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookEvent {
+  /// The kind of event, such as `readwise.highlight.created`
+  pub event_type: String,
+  /// The secret configured for the webhook, see `WebhookEvent::verify`
+  #[serde(default)]
+  pub secret: Option<String>,
+  /// The highlight the event is about
+  #[serde(flatten)]
+  pub highlight: WebhookHighlight,
+}
+
+impl WebhookEvent {
+  /// Whether this event announces a newly created highlight
+  pub fn is_highlight_created(&self) -> bool {
+    self.event_type == HIGHLIGHT_CREATED
+  }
+
+  /// Whether the event carries the webhook's configured `secret`, compared
+  /// in constant time
+  pub fn verify(&self, secret: &str) -> bool {
+    let Some(sent) = &self.secret else {
+      return false;
+    };
+
+    sent.len() == secret.len()
+      && sent
+        .bytes()
+        .zip(secret.bytes())
+        .fold(0, |difference, (a, b)| difference | (a ^ b))
+        == 0
+  }
+}
+
+/// A highlight as embedded in a webhook, along with its book and tags
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct WebhookHighlight {
+  #[serde(deserialize_with = "deserialize_lenient_number")]
+  pub id: u64,
+  pub text: String,
+  #[serde(default)]
+  pub note: String,
+  #[serde(default, deserialize_with = "deserialize_lenient_option")]
+  pub location: Option<u64>,
+  pub location_type: Option<LocationType>,
+  pub highlighted_at: Option<String>,
+  #[serde(default, deserialize_with = "deserialize_empty_as_none")]
+  pub url: Option<String>,
+  pub color: Option<String>,
+  pub updated: Option<String>,
+  #[serde(default, deserialize_with = "deserialize_lenient_option")]
+  pub book_id: Option<u64>,
+  #[serde(default)]
+  pub tags: Vec<Tag>,
+  #[serde(default)]
+  pub is_favorite: bool,
+  #[serde(default)]
+  pub is_discard: bool,
+  /// The book the highlight belongs to, when included
+  pub book: Option<WebhookBook>,
+}
+
+/// The book context embedded in a webhook highlight
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct WebhookBook {
+  #[serde(deserialize_with = "deserialize_lenient_number")]
+  pub id: u64,
+  pub title: String,
+  #[serde(default, deserialize_with = "deserialize_empty_as_none")]
+  pub author: Option<String>,
+  pub category: Option<Category>,
+  #[serde(default, deserialize_with = "deserialize_empty_as_none")]
+  pub source_url: Option<String>,
+  pub cover_image_url: Option<String>,
+}
+
+impl From<WebhookHighlight> for Highlight {
+  fn from(highlight: WebhookHighlight) -> Self {
+    Self {
+      id: highlight.id,
+      text: highlight.text,
+      note: highlight.note,
+      location: highlight.location,
+      location_type: highlight.location_type.unwrap_or_default(),
+      highlighted_at: highlight.highlighted_at,
+      url: highlight.url,
+      color: highlight.color.unwrap_or_default(),
+      updated: highlight.updated.unwrap_or_default(),
+      book_id: highlight.book_id.or(highlight.book.map(|book| book.id)),
+      tags: highlight.tags,
+      is_favorite: highlight.is_favorite,
+      is_discard: highlight.is_discard,
+      ..Default::default()
+    }
+  }
+}
+
+/// Parse a webhook request body
+///
+/// ```
+/// use readwise::{model::Highlight, webhook};
+///
+/// let event = webhook::parse_webhook(
+///   br#"{
+///     "event_type": "readwise.highlight.created",
+///     "secret": "s3cret",
+///     "id": 1,
+///     "text": "hello world!"
+///   }"#,
+/// )
+/// .unwrap();
+///
+/// assert!(event.verify("s3cret"));
+///
+/// let highlight = Highlight::from(event.highlight);
+/// ```
+pub fn parse_webhook(body: &[u8]) -> Result<WebhookEvent> {
+  serde_json::from_slice(body).context(error::WebhookSnafu)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const HIGHLIGHT_CREATED_FIXTURE: &str = r#"{
+    "event_type": "readwise.highlight.created",
+    "secret": "s3cret",
+    "id": 59758950,
+    "text": "The fox jumped over the fence.",
+    "note": "A note",
+    "location": 1234,
+    "location_type": "location",
+    "highlighted_at": "2022-10-20T19:31:17.384Z",
+    "url": null,
+    "color": "yellow",
+    "updated": "2022-10-20T19:31:17.623Z",
+    "book_id": 8479913,
+    "tags": [{ "id": 123, "name": "favorite" }],
+    "book": {
+      "id": 8479913,
+      "title": "The Fox",
+      "author": "Jane Doe",
+      "category": "books",
+      "source_url": null,
+      "cover_image_url": "https://example.com/cover.png",
+      "asin": "B00000000"
+    },
+    "is_favorite": false
+  }"#;
+
+  #[test]
+  fn highlight_created() {
+    let event = parse_webhook(HIGHLIGHT_CREATED_FIXTURE.as_bytes()).unwrap();
+
+    assert!(event.is_highlight_created());
+    assert!(event.verify("s3cret"));
+    assert!(!event.verify("s3cre"));
+    assert!(!event.verify("wrong!"));
+
+    assert_eq!(event.highlight.tags[0].name, "favorite");
+
+    let book = event.highlight.book.clone().unwrap();
+
+    assert_eq!(book.title, "The Fox");
+    assert_eq!(book.category, Some(Category::Books));
+
+    let highlight = Highlight::from(event.highlight);
+
+    assert_eq!(highlight.id, 59758950);
+    assert_eq!(highlight.location, Some(1234));
+    assert_eq!(highlight.location_type, LocationType::Location);
+    assert_eq!(highlight.book_id, Some(8479913));
+  }
+
+  #[test]
+  fn numeric_strings() {
+    let event = parse_webhook(
+      HIGHLIGHT_CREATED_FIXTURE
+        .replace(r#""id": 59758950"#, r#""id": "59758950""#)
+        .replace(r#""location": 1234"#, r#""location": "1234""#)
+        .replace(r#""book_id": 8479913"#, r#""book_id": " 8479913""#)
+        .replace(r#""id": 8479913"#, r#""id": "8479913""#)
+        .as_bytes(),
+    )
+    .unwrap();
+
+    assert_eq!(event.highlight.id, 59758950);
+    assert_eq!(event.highlight.location, Some(1234));
+    assert_eq!(event.highlight.book_id, Some(8479913));
+    assert_eq!(event.highlight.book.unwrap().id, 8479913);
+
+    let event = parse_webhook(
+      HIGHLIGHT_CREATED_FIXTURE
+        .replace(r#""location": 1234,"#, "")
+        .replace(r#""book_id": 8479913,"#, r#""book_id": null,"#)
+        .as_bytes(),
+    )
+    .unwrap();
+
+    assert_eq!(event.highlight.location, None);
+    assert_eq!(event.highlight.book_id, None);
+
+    assert!(parse_webhook(
+      HIGHLIGHT_CREATED_FIXTURE
+        .replace(r#""location": 1234"#, r#""location": "12a""#)
+        .as_bytes(),
+    )
+    .is_err());
+  }
+
+  #[test]
+  fn malformed_body() {
+    let error =
+      parse_webhook(br#"{ "event_type": "readwise.highlight.created" }"#)
+        .unwrap_err();
+
+    assert!(matches!(error, Error::Webhook { .. }));
+    assert!(
+      error.to_string().contains("missing field `id`"),
+      "{}",
+      error
+    );
+
+    assert!(parse_webhook(b"not json").is_err());
+  }
+}
@@ -0,0 +1,125 @@
+#![cfg(all(feature = "cli", not(target_arch = "wasm32")))]
+
+use {
+  mockito::{mock, Mock},
+  std::{
+    fs,
+    process::{Command, Output},
+  },
+};
+
+/// Read the anonymized API response sample `tests/fixtures/<name>`
+fn fixture(name: &str) -> String {
+  fs::read_to_string(format!(
+    "{}/tests/fixtures/{}",
+    env!("CARGO_MANIFEST_DIR"),
+    name
+  ))
+  .unwrap()
+}
+
+/// Accept the access token the binary authenticates with
+fn auth() -> Mock {
+  mock("GET", "/api/v2/auth")
+    .match_header("Authorization", "Token token")
+    .with_status(204)
+    .create()
+}
+
+/// Run the binary with `arguments` against the mock server
+fn readwise(arguments: &[&str]) -> Output {
+  Command::new(env!("CARGO_BIN_EXE_readwise"))
+    .args(arguments)
+    .env("READWISE_ACCESS_TOKEN", "token")
+    .env("READWISE_BASE_URL", mockito::server_url())
+    .output()
+    .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+  String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+#[test]
+fn books_list() {
+  let _auth = auth();
+
+  let _books = mock("GET", "/api/v2/books?page=1")
+    .with_status(200)
+    .with_body(fixture("books.json"))
+    .create();
+
+  let output = readwise(&["books", "list"]);
+
+  assert!(output.status.success(), "{:?}", output);
+  assert_eq!(
+    stdout(&output),
+    "  12824517  Meditations by Marcus Aurelius\n  \
+     18775787  How to Take Smart Notes\n"
+  );
+
+  let output = readwise(&["books", "list", "--json"]);
+
+  assert!(output.status.success(), "{:?}", output);
+
+  let books =
+    serde_json::from_str::<serde_json::Value>(&stdout(&output)).unwrap();
+
+  assert_eq!(books[1]["title"], "How to Take Smart Notes");
+}
+
+#[test]
+fn highlight_delete() {
+  let _auth = auth();
+
+  let deleted = mock("DELETE", "/api/v2/highlights/392308438")
+    .with_status(204)
+    .create();
+
+  let output = readwise(&["highlight", "delete", "392308438"]);
+
+  assert!(output.status.success(), "{:?}", output);
+  assert_eq!(stdout(&output), "");
+
+  deleted.assert();
+}
+
+#[test]
+fn export_csv() {
+  let _auth = auth();
+
+  let _export = mock("GET", "/api/v2/export")
+    .with_status(200)
+    .with_body(fixture("export.json"))
+    .create();
+
+  let directory = tempfile::tempdir().unwrap();
+  let out = directory.path().join("highlights.csv");
+
+  let output =
+    readwise(&["export", "--format", "csv", "--out", out.to_str().unwrap()]);
+
+  assert!(output.status.success(), "{:?}", output);
+  assert_eq!(stdout(&output), "");
+  assert_eq!(
+    fs::read_to_string(out).unwrap(),
+    "title,author,text,note,location,highlighted_at\n\
+     Meditations,Marcus Aurelius,\
+     You have power over your mind - not outside events.,,1203,\
+     2022-09-13T16:41:53.186Z\n"
+  );
+}
+
+#[test]
+fn missing_token() {
+  let output = Command::new(env!("CARGO_BIN_EXE_readwise"))
+    .args(["books", "list"])
+    .env_remove("READWISE_ACCESS_TOKEN")
+    .output()
+    .unwrap();
+
+  assert_eq!(output.status.code(), Some(1));
+  assert!(String::from_utf8(output.stderr)
+    .unwrap()
+    .contains("READWISE_ACCESS_TOKEN"));
+}
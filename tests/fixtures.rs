@@ -0,0 +1,120 @@
+#![cfg(all(feature = "blocking", not(target_arch = "wasm32")))]
+
+use {
+  readwise::{
+    client::Client,
+    model::{Book, ExportResponse, HighlightsResponse, LocationType, Page},
+    testing::FakeTransport,
+  },
+  std::fs,
+};
+
+/// Read the anonymized API response sample `tests/fixtures/<name>`
+fn fixture(name: &str) -> String {
+  fs::read_to_string(format!(
+    "{}/tests/fixtures/{}",
+    env!("CARGO_MANIFEST_DIR"),
+    name
+  ))
+  .unwrap_or_else(|error| {
+    panic!("Failed to read fixture `{}`: {}", name, error)
+  })
+}
+
+/// A client whose `GET` requests to each path are answered with the given
+/// fixtures, in order
+fn client(responses: &[(&str, &str)]) -> (Client, FakeTransport) {
+  let transport =
+    responses
+      .iter()
+      .fold(FakeTransport::new(), |transport, (path, name)| {
+        transport.respond("GET", path, 200, &fixture(name))
+      });
+
+  (
+    Client::with_transport("token", Box::new(transport.clone())).unwrap(),
+    transport,
+  )
+}
+
+#[test]
+fn books() {
+  let page =
+    serde_json::from_str::<Page<Book>>(&fixture("books.json")).unwrap();
+
+  assert_eq!(page.count, 2);
+
+  let (meditations, notes) = (&page.results[0], &page.results[1]);
+
+  assert_eq!(meditations.to_string(), "Meditations by Marcus Aurelius");
+  assert_eq!(meditations.num_highlights, 3);
+  assert_eq!(meditations.source_url, None);
+  assert!(meditations.last_highlighted_at.is_some());
+
+  assert_eq!(notes.author, None);
+  assert_eq!(notes.last_highlighted_at, None);
+  assert_eq!(
+    notes.source_url.as_deref(),
+    Some("https://example.com/smart-notes")
+  );
+}
+
+#[test]
+fn highlights_across_pages() {
+  let (client, transport) = client(&[
+    ("/highlights", "highlights-page-1.json"),
+    ("/highlights", "highlights-page-2.json"),
+  ]);
+
+  let highlights = client.all_highlights_from(1).unwrap();
+
+  assert_eq!(
+    highlights
+      .iter()
+      .map(|highlight| highlight.id)
+      .collect::<Vec<u64>>(),
+    [392308438, 392308437, 388800102]
+  );
+
+  assert_eq!(highlights[0].location_type, LocationType::Location);
+  assert_eq!(highlights[0].tags[0].name, "stoicism");
+  assert_eq!(highlights[1].note, "Compare with Epictetus.");
+  assert_eq!(highlights[2].note, "");
+  assert_eq!(highlights[2].location, None);
+  assert_eq!(highlights[2].highlighted_at, None);
+
+  assert_eq!(transport.requests().len(), 2);
+}
+
+#[test]
+fn highlights_page() {
+  let page = serde_json::from_str::<HighlightsResponse>(&fixture(
+    "highlights-page-1.json",
+  ))
+  .unwrap();
+
+  assert_eq!(page.count, 3);
+  assert!(page.next.is_some());
+  assert_eq!(page.results.len(), 2);
+}
+
+#[test]
+fn export() {
+  let response =
+    serde_json::from_str::<ExportResponse>(&fixture("export.json")).unwrap();
+
+  assert_eq!(response.next_page_cursor, None);
+
+  let highlight = &response.results[0].highlights[0];
+
+  assert_eq!(highlight.note, "");
+  assert!(highlight.is_favorite);
+
+  let (client, _) = client(&[("/export", "export.json")]);
+
+  let books = client.export(None, None).unwrap();
+
+  assert_eq!(books.len(), 1);
+  assert_eq!(books[0].title, "Meditations");
+  assert_eq!(books[0].highlights[0].id, 392308438);
+}
@@ -0,0 +1,40 @@
+#![cfg(target_arch = "wasm32")]
+
+use {
+  readwise::model::{Book, Category, Highlight},
+  wasm_bindgen_test::*,
+};
+
+#[wasm_bindgen_test]
+fn highlight_readwise_url() {
+  let highlight = Highlight {
+    id: 1,
+    ..Default::default()
+  };
+
+  assert_eq!(highlight.readwise_url(), "https://readwise.io/open/1");
+}
+
+#[wasm_bindgen_test]
+fn book_round_trip() {
+  let book = Book {
+    id: 1,
+    title: "Quotes".into(),
+    ..Default::default()
+  };
+
+  let parsed =
+    serde_json::from_str::<Book>(&serde_json::to_string(&book).unwrap())
+      .unwrap();
+
+  assert_eq!(parsed.id, 1);
+  assert_eq!(parsed.title, "Quotes");
+}
+
+#[wasm_bindgen_test]
+fn category_serialization() {
+  assert_eq!(
+    serde_json::to_string(&Category::Articles).unwrap(),
+    r#""articles""#
+  );
+}